@@ -0,0 +1,391 @@
+use buffer::RingBuffer;
+use noise::Noise;
+use pulse::Pulse;
+use wave::Wave;
+
+pub use buffer::Sample;
+
+mod buffer;
+mod length;
+mod noise;
+mod pulse;
+mod wave;
+
+//https://gbdev.io/pandocs/Audio_Registers.html
+pub const NR10: u16 = 0xFF10; //channel 1, sweep
+const NR11: u16 = 0xFF11; //duty & length
+const NR14: u16 = 0xFF14;
+const NR20: u16 = 0xFF15; //unused, channel 2 has no sweep
+const NR21: u16 = 0xFF16;
+const NR24: u16 = 0xFF19;
+const NR30: u16 = 0xFF1A; //channel 3, DAC enable
+const NR31: u16 = 0xFF1B;
+const NR34: u16 = 0xFF1E;
+const NR40: u16 = 0xFF1F; //unused
+const NR41: u16 = 0xFF20;
+const NR44: u16 = 0xFF23;
+pub const NR50: u16 = 0xFF24; //master volume
+pub const NR51: u16 = 0xFF25; //panning
+pub const NR52: u16 = 0xFF26; //power & channel status
+pub const WAVE_RAM_START: u16 = 0xFF30;
+pub const WAVE_RAM_END: u16 = 0xFF3F;
+
+pub const DEFAULT_SAMPLE_RATE: u32 = 48000;
+const CLOCK_HZ: u32 = 4194304; //DMG master clock, t_states per second
+const BUFFER_DIVISOR: u32 = 4; //the ring buffer holds a quarter of a second
+const CHARGE_FACTOR: f64 = 0.999958; //high-pass capacitor charge kept per t-cycle
+
+const NR52_POWER: u8 = 0x80;
+
+pub struct APU {
+    power: bool,
+    nr50: u8,
+    nr51: u8,
+    pulse1: Pulse,
+    pulse2: Pulse,
+    wave: Wave,
+    noise: Noise,
+    sequencer_step: u8,
+    sample_rate: u32,
+    sample_timer: u32, //accumulates the sample rate every t-cycle, a sample is due each time it passes the clock rate
+    charge: f32, //CHARGE_FACTOR scaled to one output sample
+    capacitors: [f32; 2],
+    buffer: RingBuffer
+}
+
+impl Default for APU {
+    fn default() -> Self {
+        APU::init(DEFAULT_SAMPLE_RATE)
+    }
+}
+
+impl APU {
+    pub fn init(sample_rate: u32) -> APU {
+        APU {
+            power: false,
+            nr50: 0,
+            nr51: 0,
+            pulse1: Pulse::new(true),
+            pulse2: Pulse::new(false),
+            wave: Wave::new(),
+            noise: Noise::new(),
+            sequencer_step: 0,
+            sample_rate,
+            sample_timer: 0,
+            charge: CHARGE_FACTOR.powf(CLOCK_HZ as f64 / sample_rate as f64) as f32,
+            capacitors: [0.0; 2],
+            buffer: RingBuffer::new((sample_rate / BUFFER_DIVISOR) as usize)
+        }
+    }
+
+    //samples already in the buffer are dropped
+    pub fn set_sample_rate(&mut self, sample_rate: u32) {
+        let apu = APU::init(sample_rate);
+
+        self.sample_rate = sample_rate;
+        self.sample_timer = 0;
+        self.charge = apu.charge;
+        self.buffer = apu.buffer;
+    }
+
+    pub fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+
+    //stereo output at the sample rate, drained by whatever plays or records it
+    pub fn buffer_mut(&mut self) -> &mut RingBuffer {
+        &mut self.buffer
+    }
+
+    pub fn read(&self, addr: u16) -> u8 {
+        match addr {
+            NR10..=NR14 => self.pulse1.read((addr - NR10) as usize),
+            NR20..=NR24 => self.pulse2.read((addr - NR20) as usize),
+            NR30..=NR34 => self.wave.read((addr - NR30) as usize),
+            NR40..=NR44 => self.noise.read((addr - NR40) as usize),
+            NR50 => self.nr50,
+            NR51 => self.nr51,
+            NR52 => self.status(),
+            WAVE_RAM_START..=WAVE_RAM_END => self.wave.read_ram((addr - WAVE_RAM_START) as usize),
+            _ => unreachable!("{:#06x} is not an APU register", addr)
+        }
+    }
+
+    //while powered off only NR52, wave RAM & on DMG the length counters can be written
+    pub fn write(&mut self, addr: u16, value: u8) {
+        match addr {
+            NR52 => self.write_power(value & NR52_POWER != 0),
+            WAVE_RAM_START..=WAVE_RAM_END => self.wave.write_ram((addr - WAVE_RAM_START) as usize, value),
+            NR11 if !self.power => self.pulse1.write_length(value),
+            NR21 if !self.power => self.pulse2.write_length(value),
+            NR31 if !self.power => self.wave.write_length(value),
+            NR41 if !self.power => self.noise.write_length(value),
+            _ if !self.power => {},
+            NR10..=NR14 => self.pulse1.write((addr - NR10) as usize, value),
+            NR20..=NR24 => self.pulse2.write((addr - NR20) as usize, value),
+            NR30..=NR34 => self.wave.write((addr - NR30) as usize, value),
+            NR40..=NR44 => self.noise.write((addr - NR40) as usize, value),
+            NR50 => self.nr50 = value,
+            NR51 => self.nr51 = value,
+            _ => unreachable!("{:#06x} is not an APU register", addr)
+        }
+    }
+
+    //advance by t-cycles, pushing a sample into the buffer whenever one is due
+    pub fn tick(&mut self, t_cycles: u8) {
+        for _ in 0..t_cycles {
+            if self.power {
+                self.pulse1.tick();
+                self.pulse2.tick();
+                self.wave.tick();
+                self.noise.tick();
+            }
+
+            self.sample_timer += self.sample_rate;
+
+            if self.sample_timer >= CLOCK_HZ {
+                self.sample_timer -= CLOCK_HZ;
+
+                let sample = self.high_pass(self.mix());
+                self.buffer.push(sample);
+            }
+        }
+    }
+
+    //called on each falling edge of DIV bit 4, so writing DIV shifts the sequencer as it does on hardware
+    //https://gbdev.io/pandocs/Audio_details.html#div-apu
+    pub fn clock_sequencer(&mut self) {
+        if !self.power {
+            return;
+        }
+
+        match self.sequencer_step {
+            0 | 4 => self.clock_length(),
+            2 | 6 => {
+                self.clock_length();
+                self.pulse1.clock_sweep();
+            },
+            7 => {
+                self.pulse1.clock_envelope();
+                self.pulse2.clock_envelope();
+                self.noise.clock_envelope();
+            },
+            _ => ()
+        }
+
+        self.sequencer_step = (self.sequencer_step + 1) % 8;
+    }
+
+    fn clock_length(&mut self) {
+        self.pulse1.clock_length();
+        self.pulse2.clock_length();
+        self.wave.clock_length();
+        self.noise.clock_length();
+    }
+
+    fn write_power(&mut self, power: bool) {
+        if self.power && !power {
+            self.nr50 = 0;
+            self.nr51 = 0;
+            self.pulse1.reset();
+            self.pulse2.reset();
+            self.wave.reset();
+            self.noise.reset();
+        } else if !self.power && power {
+            self.sequencer_step = 0;
+        }
+
+        self.power = power;
+    }
+
+    //power in bit 7, whether each channel is playing in bits 0-3
+    fn status(&self) -> u8 {
+        let power = if self.power { NR52_POWER } else { 0 };
+
+        power
+            | self.pulse1.is_enabled() as u8
+            | (self.pulse2.is_enabled() as u8) << 1
+            | (self.wave.is_enabled() as u8) << 2
+            | (self.noise.is_enabled() as u8) << 3
+    }
+
+    //NR51 routes each channel left and/or right, NR50 scales each side
+    //https://gbdev.io/pandocs/Audio_details.html#mixer
+    fn mix(&self) -> Sample {
+        let mut sample = [0.0; 2];
+
+        if !self.power {
+            return sample;
+        }
+
+        let outputs = [self.pulse1.output(), self.pulse2.output(), self.wave.output(), self.noise.output()];
+
+        for (channel, output) in outputs.iter().enumerate() {
+            if let Option::Some(digital) = output {
+                let analog = *digital as f32 / 7.5 - 1.0; //the DAC maps 0-15 onto -1.0 to 1.0
+
+                if self.nr51 & (0x10 << channel) != 0 {
+                    sample[0] += analog;
+                }
+
+                if self.nr51 & (0x01 << channel) != 0 {
+                    sample[1] += analog;
+                }
+            }
+        }
+
+        let left_volume = ((self.nr50 >> 4) & 0x07) + 1;
+        let right_volume = (self.nr50 & 0x07) + 1;
+
+        [
+            sample[0] / 4.0 * left_volume as f32 / 8.0,
+            sample[1] / 4.0 * right_volume as f32 / 8.0
+        ]
+    }
+
+    //the capacitor on the output removes the DC offset the DACs leave
+    fn high_pass(&mut self, sample: Sample) -> Sample {
+        let mut output = [0.0; 2];
+
+        for side in 0..2 {
+            output[side] = sample[side] - self.capacitors[side];
+            self.capacitors[side] = sample[side] - output[side] * self.charge;
+        }
+
+        output
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const NR12: u16 = 0xFF12;
+    const NR22: u16 = 0xFF17;
+
+    fn powered_apu() -> APU {
+        let mut apu = APU::init(DEFAULT_SAMPLE_RATE);
+        apu.write(NR52, NR52_POWER);
+
+        apu
+    }
+
+    fn tick_cycles(apu: &mut APU, cycles: u32) {
+        for _ in 0..(cycles / 4) {
+            apu.tick(4);
+        }
+    }
+
+    #[test]
+    fn test_power() {
+        let mut apu = APU::init(DEFAULT_SAMPLE_RATE);
+
+        apu.write(NR50, 0x77); //ignored while off
+        apu.write(WAVE_RAM_START, 0x12);
+        assert_eq!(0x00, apu.read(NR50));
+        assert_eq!(0x00, apu.read(NR52));
+
+        apu.write(NR52, 0xFF);
+        apu.write(NR50, 0x77);
+        apu.write(NR12, 0xF0);
+        apu.write(NR14, 0x80); //trigger channel 1
+
+        assert_eq!(0x77, apu.read(NR50));
+        assert_eq!(NR52_POWER | 0x01, apu.read(NR52));
+
+        apu.write(NR52, 0x00);
+
+        assert_eq!(0x00, apu.read(NR50));
+        assert_eq!(0x00, apu.read(NR12));
+        assert_eq!(0x00, apu.read(NR52));
+        assert_eq!(0x12, apu.read(WAVE_RAM_START)); //survives power off
+    }
+
+    #[test]
+    fn test_length_disables_channel() {
+        let mut apu = powered_apu();
+        apu.write(NR21, 0x3E); //2 length clocks
+        apu.write(NR22, 0xF0);
+        apu.write(NR24, 0xC0); //trigger with length enabled
+
+        assert_eq!(0x02, apu.read(NR52) & 0x0F);
+
+        apu.clock_sequencer(); //step 0 clocks length
+        assert_eq!(0x02, apu.read(NR52) & 0x0F);
+
+        apu.clock_sequencer();
+        apu.clock_sequencer(); //step 2
+        assert_eq!(0x00, apu.read(NR52) & 0x0F);
+    }
+
+    #[test]
+    fn test_length_kept_while_off() {
+        let mut apu = powered_apu();
+        apu.write(NR21, 0x3E); //2 length clocks
+
+        apu.write(NR52, 0x00);
+        apu.write(NR22, 0xF0); //ignored while off
+        apu.write(NR52, NR52_POWER);
+        apu.write(NR22, 0xF0);
+        apu.write(NR24, 0xC0); //the counter survived, triggering doesn't reload it
+
+        apu.clock_length();
+        assert_eq!(0x02, apu.read(NR52) & 0x0F);
+
+        apu.clock_length();
+        assert_eq!(0x00, apu.read(NR52) & 0x0F);
+
+        apu.write(NR52, 0x00);
+        apu.write(NR21, 0x3F); //length loads go through while off
+        apu.write(NR52, NR52_POWER);
+        apu.write(NR22, 0xF0);
+        apu.write(NR24, 0xC0);
+
+        apu.clock_length();
+        assert_eq!(0x00, apu.read(NR52) & 0x0F);
+    }
+
+    #[test]
+    fn test_mix_panning() {
+        let mut apu = powered_apu();
+        apu.write(NR22, 0x08); //DAC on at volume 0, a constant -1.0
+        apu.write(NR24, 0x80);
+        apu.write(NR51, 0x20); //channel 2 on the left only
+
+        apu.write(NR50, 0x70);
+        assert_eq!([-0.25, 0.0], apu.mix());
+
+        apu.write(NR50, 0x30); //left volume halved
+        assert_eq!([-0.125, 0.0], apu.mix());
+
+        apu.write(NR51, 0x22);
+        assert_eq!([-0.125, -0.03125], apu.mix());
+    }
+
+    #[test]
+    fn test_high_pass() {
+        let mut apu = powered_apu();
+        let first = apu.high_pass([-0.25, 0.0]);
+        assert_eq!([-0.25, 0.0], first);
+
+        for _ in 0..DEFAULT_SAMPLE_RATE {
+            apu.high_pass([-0.25, 0.0]);
+        }
+
+        assert!(apu.high_pass([-0.25, 0.0])[0].abs() < 0.001); //a constant level decays away
+    }
+
+    #[test]
+    fn test_sample_rate() {
+        let mut apu = APU::init(44100);
+        tick_cycles(&mut apu, 70224);
+
+        assert_eq!(738, apu.buffer_mut().len()); //one frame, 70224 * 44100 / 4194304
+
+        apu.set_sample_rate(DEFAULT_SAMPLE_RATE);
+        assert!(apu.buffer_mut().is_empty());
+
+        tick_cycles(&mut apu, CLOCK_HZ);
+        assert_eq!(DEFAULT_SAMPLE_RATE as usize / 4, apu.buffer_mut().len()); //full, a second is more than it holds
+    }
+}
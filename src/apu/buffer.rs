@@ -0,0 +1,86 @@
+pub type Sample = [f32; 2]; //left, right
+
+//fixed size so an absent or slow consumer can't grow it, the oldest samples are overwritten
+pub struct RingBuffer {
+    samples: Vec<Sample>,
+    start: usize, //index of the oldest sample
+    len: usize
+}
+
+impl RingBuffer {
+    pub fn new(capacity: usize) -> RingBuffer {
+        RingBuffer {
+            samples: vec![[0.0; 2]; capacity],
+            start: 0,
+            len: 0
+        }
+    }
+
+    pub fn push(&mut self, sample: Sample) {
+        let capacity = self.capacity();
+        self.samples[(self.start + self.len) % capacity] = sample;
+
+        if self.len == capacity {
+            self.start = (self.start + 1) % capacity;
+        } else {
+            self.len += 1;
+        }
+    }
+
+    pub fn pop(&mut self) -> Option<Sample> {
+        if self.len == 0 {
+            return Option::None;
+        }
+
+        let sample = self.samples[self.start];
+        self.start = (self.start + 1) % self.capacity();
+        self.len -= 1;
+
+        Option::Some(sample)
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.samples.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_push_pop() {
+        let mut buffer = RingBuffer::new(4);
+        assert_eq!(Option::None, buffer.pop());
+
+        buffer.push([0.1, 0.2]);
+        buffer.push([0.3, 0.4]);
+
+        assert_eq!(2, buffer.len());
+        assert_eq!(Option::Some([0.1, 0.2]), buffer.pop());
+        assert_eq!(Option::Some([0.3, 0.4]), buffer.pop());
+        assert!(buffer.is_empty());
+    }
+
+    #[test]
+    fn test_overwrite_oldest() {
+        let mut buffer = RingBuffer::new(3);
+
+        for value in 0..5 {
+            buffer.push([value as f32; 2]);
+        }
+
+        assert_eq!(3, buffer.len());
+        assert_eq!(Option::Some([2.0; 2]), buffer.pop());
+        assert_eq!(Option::Some([3.0; 2]), buffer.pop());
+        assert_eq!(Option::Some([4.0; 2]), buffer.pop());
+    }
+}
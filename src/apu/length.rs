@@ -0,0 +1,146 @@
+//https://gbdev.io/pandocs/Audio.html#length-timer, clocked at 256Hz by the frame sequencer
+#[derive(Clone, Copy)]
+pub struct Length {
+    max: u16, //64, or 256 for the wave channel
+    counter: u16,
+    enabled: bool
+}
+
+impl Length {
+    pub fn new(max: u16) -> Length {
+        Length {
+            max,
+            counter: 0,
+            enabled: false
+        }
+    }
+
+    //the value written is how far in the count starts
+    pub fn load(&mut self, value: u8) {
+        self.counter = self.max - value as u16;
+    }
+
+    pub fn set_enabled(&mut self, enabled: bool) {
+        self.enabled = enabled;
+    }
+
+    pub fn trigger(&mut self) {
+        if self.counter == 0 {
+            self.counter = self.max;
+        }
+    }
+
+    //returns false once the counter runs out, which turns the channel off
+    pub fn clock(&mut self) -> bool {
+        if !self.enabled || self.counter == 0 {
+            return true;
+        }
+
+        self.counter -= 1;
+        self.counter != 0
+    }
+}
+
+//https://gbdev.io/pandocs/Audio_Registers.html#ff12--nr12-channel-1-volume--envelope, clocked at 64Hz
+pub struct Envelope {
+    volume: u8,
+    increase: bool,
+    period: u8, //0 leaves the volume alone
+    timer: u8
+}
+
+impl Default for Envelope {
+    fn default() -> Self {
+        Envelope::new()
+    }
+}
+
+impl Envelope {
+    pub fn new() -> Envelope {
+        Envelope {
+            volume: 0,
+            increase: false,
+            period: 0,
+            timer: 0
+        }
+    }
+
+    //NRx2 is latched on trigger, later writes only take effect on the next one
+    pub fn trigger(&mut self, register: u8) {
+        self.volume = register >> 4;
+        self.increase = register & 0x08 != 0;
+        self.period = register & 0x07;
+        self.timer = self.period;
+    }
+
+    pub fn clock(&mut self) {
+        if self.period == 0 {
+            return;
+        }
+
+        self.timer -= 1;
+
+        if self.timer == 0 {
+            self.timer = self.period;
+
+            if self.increase && self.volume < 15 {
+                self.volume += 1;
+            } else if !self.increase && self.volume > 0 {
+                self.volume -= 1;
+            }
+        }
+    }
+
+    pub fn volume(&self) -> u8 {
+        self.volume
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_length() {
+        let mut length = Length::new(64);
+        length.load(60);
+        length.set_enabled(true);
+
+        assert!(length.clock());
+        assert!(length.clock());
+        assert!(length.clock());
+        assert!(!length.clock()); //4 clocks from 60
+
+        length.trigger(); //reloads the full length
+        assert_eq!(64, length.counter);
+    }
+
+    #[test]
+    fn test_length_disabled() {
+        let mut length = Length::new(256);
+        length.load(255);
+
+        assert!(length.clock());
+        assert_eq!(1, length.counter);
+    }
+
+    #[test]
+    fn test_envelope() {
+        let mut envelope = Envelope::new();
+        envelope.trigger(0xF2); //volume 15, decreasing every 2 clocks
+
+        envelope.clock();
+        assert_eq!(15, envelope.volume());
+
+        envelope.clock();
+        assert_eq!(14, envelope.volume());
+
+        envelope.trigger(0xF9); //already at the maximum
+        envelope.clock();
+        assert_eq!(15, envelope.volume());
+
+        envelope.trigger(0x30); //period 0 holds the volume
+        envelope.clock();
+        assert_eq!(3, envelope.volume());
+    }
+}
@@ -0,0 +1,186 @@
+use super::length::{Envelope, Length};
+
+//https://gbdev.io/pandocs/Audio_Registers.html#sound-channel-4--noise
+const DIVISORS: [u16; 8] = [8, 16, 32, 48, 64, 80, 96, 112];
+
+pub struct Noise {
+    registers: [u8; 5], //NR41-NR44 at 1-4, NR40 doesn't exist
+    enabled: bool,
+    length: Length,
+    envelope: Envelope,
+    timer: u32, //t-cycles until the LFSR is next clocked
+    lfsr: u16 //15-bit linear feedback shift register
+}
+
+impl Default for Noise {
+    fn default() -> Self {
+        Noise::new()
+    }
+}
+
+impl Noise {
+    pub fn new() -> Noise {
+        Noise {
+            registers: [0; 5],
+            enabled: false,
+            length: Length::new(64),
+            envelope: Envelope::new(),
+            timer: 0,
+            lfsr: 0
+        }
+    }
+
+    //powering the APU off clears the registers, on DMG the length counter is left running
+    pub fn reset(&mut self) {
+        *self = Noise {
+            length: self.length,
+            ..Noise::new()
+        };
+        self.length.set_enabled(false);
+    }
+
+    pub fn write_length(&mut self, value: u8) {
+        self.length.load(value & 0x3F);
+    }
+
+    pub fn read(&self, register: usize) -> u8 {
+        self.registers[register]
+    }
+
+    pub fn write(&mut self, register: usize, value: u8) {
+        self.registers[register] = value;
+
+        match register {
+            1 => self.length.load(value & 0x3F),
+            2 if !self.dac_enabled() => self.enabled = false,
+            4 => {
+                self.length.set_enabled(value & 0x40 != 0);
+
+                if value & 0x80 != 0 {
+                    self.trigger();
+                }
+            },
+            _ => ()
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    //digital output 0-15, None while the DAC is off
+    pub fn output(&self) -> Option<u8> {
+        if !self.dac_enabled() {
+            return Option::None;
+        }
+
+        let high = self.lfsr & 0x01 == 0; //the output is the inverted low bit
+
+        Option::Some(if self.enabled && high { self.envelope.volume() } else { 0 })
+    }
+
+    pub fn tick(&mut self) {
+        if self.timer <= 1 {
+            self.timer = self.period();
+            self.clock_lfsr();
+        } else {
+            self.timer -= 1;
+        }
+    }
+
+    pub fn clock_length(&mut self) {
+        if !self.length.clock() {
+            self.enabled = false;
+        }
+    }
+
+    pub fn clock_envelope(&mut self) {
+        self.envelope.clock();
+    }
+
+    fn trigger(&mut self) {
+        self.enabled = self.dac_enabled();
+        self.length.trigger();
+        self.envelope.trigger(self.registers[2]);
+        self.timer = self.period();
+        self.lfsr = 0x7FFF;
+    }
+
+    //bits 0 & 1 are XORed into bit 14, and bit 6 as well in 7-bit mode
+    fn clock_lfsr(&mut self) {
+        let feedback = (self.lfsr ^ (self.lfsr >> 1)) & 0x01;
+        self.lfsr = (self.lfsr >> 1) | (feedback << 14);
+
+        if self.registers[3] & 0x08 != 0 {
+            self.lfsr = (self.lfsr & !0x40) | (feedback << 6);
+        }
+    }
+
+    //t-cycles between LFSR clocks
+    fn period(&self) -> u32 {
+        let divisor = DIVISORS[(self.registers[3] & 0x07) as usize] as u32;
+        divisor << (self.registers[3] >> 4)
+    }
+
+    fn dac_enabled(&self) -> bool {
+        self.registers[2] & 0xF8 != 0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn triggered_noise(nr43: u8) -> Noise {
+        let mut noise = Noise::new();
+        noise.write(2, 0xF0);
+        noise.write(3, nr43);
+        noise.write(4, 0x80);
+
+        noise
+    }
+
+    #[test]
+    fn test_lfsr() {
+        let mut noise = triggered_noise(0x00); //every 8 t-cycles
+
+        assert_eq!(0x7FFF, noise.lfsr);
+        assert_eq!(Option::Some(0), noise.output());
+
+        for _ in 0..8 {
+            noise.tick();
+        }
+
+        assert_eq!(0x3FFF, noise.lfsr); //1 ^ 1 shifts in a 0
+
+        for _ in 0..(8 * 13) {
+            noise.tick();
+        }
+
+        assert_eq!(0x0001, noise.lfsr);
+
+        for _ in 0..8 {
+            noise.tick();
+        }
+
+        assert_eq!(0x4000, noise.lfsr);
+        assert_eq!(Option::Some(15), noise.output());
+    }
+
+    #[test]
+    fn test_lfsr_short_mode() {
+        let mut noise = triggered_noise(0x08);
+
+        for _ in 0..8 {
+            noise.tick();
+        }
+
+        assert_eq!(0x3FBF, noise.lfsr); //bit 6 takes the feedback too
+    }
+
+    #[test]
+    fn test_period() {
+        assert_eq!(8, triggered_noise(0x00).period());
+        assert_eq!(48 << 2, triggered_noise(0x23).period());
+    }
+}
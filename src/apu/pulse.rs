@@ -0,0 +1,275 @@
+use super::length::{Envelope, Length};
+
+//https://gbdev.io/pandocs/Audio_Registers.html#sound-channel-1--pulse-with-period-sweep
+const DUTY_PATTERNS: [u8; 4] = [
+    0b0000_0001, //12.5%
+    0b1000_0001, //25%
+    0b1000_0111, //50%
+    0b0111_1110 //75%
+];
+
+const MAX_FREQUENCY: u16 = 2047;
+
+//channels 1 & 2, only channel 1 has the frequency sweep
+pub struct Pulse {
+    registers: [u8; 5], //NRx0-NRx4 as written, NR20 doesn't exist
+    has_sweep: bool,
+    enabled: bool,
+    length: Length,
+    envelope: Envelope,
+    timer: u16, //t-cycles until the next duty step
+    duty_step: u8,
+    sweep_enabled: bool,
+    sweep_timer: u8,
+    shadow: u16 //frequency the sweep works from
+}
+
+impl Pulse {
+    pub fn new(has_sweep: bool) -> Pulse {
+        Pulse {
+            registers: [0; 5],
+            has_sweep,
+            enabled: false,
+            length: Length::new(64),
+            envelope: Envelope::new(),
+            timer: 0,
+            duty_step: 0,
+            sweep_enabled: false,
+            sweep_timer: 0,
+            shadow: 0
+        }
+    }
+
+    //powering the APU off clears the registers, on DMG the length counter is left running
+    pub fn reset(&mut self) {
+        *self = Pulse {
+            length: self.length,
+            ..Pulse::new(self.has_sweep)
+        };
+        self.length.set_enabled(false);
+    }
+
+    //NRx1 while powered off, only the length half of the register is written
+    pub fn write_length(&mut self, value: u8) {
+        self.length.load(value & 0x3F);
+    }
+
+    pub fn read(&self, register: usize) -> u8 {
+        self.registers[register]
+    }
+
+    pub fn write(&mut self, register: usize, value: u8) {
+        self.registers[register] = value;
+
+        match register {
+            1 => self.length.load(value & 0x3F),
+            2 if !self.dac_enabled() => self.enabled = false,
+            4 => {
+                self.length.set_enabled(value & 0x40 != 0);
+
+                if value & 0x80 != 0 {
+                    self.trigger();
+                }
+            },
+            _ => ()
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    //digital output 0-15, None while the DAC is off
+    pub fn output(&self) -> Option<u8> {
+        if !self.dac_enabled() {
+            return Option::None;
+        }
+
+        let duty = DUTY_PATTERNS[(self.registers[1] >> 6) as usize];
+        let high = (duty >> self.duty_step) & 0x01 != 0;
+
+        Option::Some(if self.enabled && high { self.envelope.volume() } else { 0 })
+    }
+
+    pub fn tick(&mut self) {
+        if self.timer <= 1 {
+            self.timer = self.period();
+            self.duty_step = (self.duty_step + 1) % 8;
+        } else {
+            self.timer -= 1;
+        }
+    }
+
+    pub fn clock_length(&mut self) {
+        if !self.length.clock() {
+            self.enabled = false;
+        }
+    }
+
+    pub fn clock_envelope(&mut self) {
+        self.envelope.clock();
+    }
+
+    //https://gbdev.io/pandocs/Audio_details.html#pulse-channel-with-sweep-ch1
+    pub fn clock_sweep(&mut self) {
+        if !self.has_sweep {
+            return;
+        }
+
+        self.sweep_timer = self.sweep_timer.saturating_sub(1);
+
+        if self.sweep_timer > 0 {
+            return;
+        }
+
+        self.sweep_timer = self.sweep_period();
+
+        if !self.sweep_enabled || self.registers[0] & 0x70 == 0 {
+            return;
+        }
+
+        let frequency = self.next_sweep();
+
+        if frequency <= MAX_FREQUENCY && self.sweep_shift() != 0 {
+            self.shadow = frequency;
+            self.set_frequency(frequency);
+            self.next_sweep(); //checked again straight away, overflowing here also disables the channel
+        }
+    }
+
+    fn trigger(&mut self) {
+        self.enabled = self.dac_enabled();
+        self.length.trigger();
+        self.envelope.trigger(self.registers[2]);
+        self.timer = self.period();
+
+        if self.has_sweep {
+            self.shadow = self.frequency();
+            self.sweep_timer = self.sweep_period();
+            self.sweep_enabled = self.registers[0] & 0x70 != 0 || self.sweep_shift() != 0;
+
+            if self.sweep_shift() != 0 {
+                self.next_sweep();
+            }
+        }
+    }
+
+    //the frequency after the next sweep step, going past 2047 turns the channel off
+    fn next_sweep(&mut self) -> u16 {
+        let delta = self.shadow >> self.sweep_shift();
+
+        let frequency = if self.registers[0] & 0x08 != 0 {
+            self.shadow - delta
+        } else {
+            self.shadow + delta
+        };
+
+        if frequency > MAX_FREQUENCY {
+            self.enabled = false;
+        }
+
+        frequency
+    }
+
+    //a period of 0 is treated as 8
+    fn sweep_period(&self) -> u8 {
+        match (self.registers[0] >> 4) & 0x07 {
+            0 => 8,
+            period => period
+        }
+    }
+
+    fn sweep_shift(&self) -> u8 {
+        self.registers[0] & 0x07
+    }
+
+    fn frequency(&self) -> u16 {
+        ((self.registers[4] & 0x07) as u16) << 8 | self.registers[3] as u16
+    }
+
+    fn set_frequency(&mut self, frequency: u16) {
+        self.registers[3] = frequency as u8;
+        self.registers[4] = (self.registers[4] & !0x07) | ((frequency >> 8) as u8 & 0x07);
+    }
+
+    //t-cycles per duty step
+    fn period(&self) -> u16 {
+        (MAX_FREQUENCY + 1 - self.frequency()) * 4
+    }
+
+    //the upper 5 bits of NRx2 power the DAC
+    fn dac_enabled(&self) -> bool {
+        self.registers[2] & 0xF8 != 0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_duty() {
+        let mut pulse = Pulse::new(false);
+        pulse.write(1, 0x80); //50%
+        pulse.write(2, 0xF0);
+        pulse.write(3, 0xFF); //frequency 2047, 4 t-cycles a step
+        pulse.write(4, 0x87);
+
+        let mut steps = Vec::new();
+
+        for _ in 0..8 {
+            for _ in 0..4 {
+                pulse.tick();
+            }
+
+            steps.push(pulse.output().unwrap());
+        }
+
+        assert_eq!(vec![15, 15, 0, 0, 0, 0, 15, 15], steps); //starting from step 1
+    }
+
+    #[test]
+    fn test_dac_off() {
+        let mut pulse = Pulse::new(false);
+        pulse.write(2, 0xF0);
+        pulse.write(4, 0x80);
+        assert!(pulse.is_enabled());
+
+        pulse.write(2, 0x07); //volume 0 & decreasing turns the DAC off
+        assert!(!pulse.is_enabled());
+        assert_eq!(Option::None, pulse.output());
+
+        pulse.write(4, 0x80); //triggering can't enable it
+        assert!(!pulse.is_enabled());
+    }
+
+    #[test]
+    fn test_sweep() {
+        let mut pulse = Pulse::new(true);
+        pulse.write(0, 0x11); //every sweep clock, adding frequency >> 1
+        pulse.write(2, 0xF0);
+        pulse.write(3, 0x00);
+        pulse.write(4, 0x82); //frequency 0x200
+
+        pulse.clock_sweep();
+        assert_eq!(0x300, pulse.frequency());
+
+        pulse.clock_sweep();
+        assert_eq!(0x480, pulse.frequency());
+        assert!(pulse.is_enabled());
+
+        pulse.clock_sweep(); //0x6C0, the follow up check sees 0xA20 and overflows
+        assert!(!pulse.is_enabled());
+    }
+
+    #[test]
+    fn test_sweep_overflow_on_trigger() {
+        let mut pulse = Pulse::new(true);
+        pulse.write(0, 0x01);
+        pulse.write(2, 0xF0);
+        pulse.write(3, 0xFF);
+        pulse.write(4, 0x87); //2047 + 1023 overflows straight away
+
+        assert!(!pulse.is_enabled());
+    }
+}
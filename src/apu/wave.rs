@@ -0,0 +1,165 @@
+use super::length::Length;
+
+pub const WAVE_RAM_SIZE: usize = 16; //32 4-bit samples, upper nibble first
+
+//https://gbdev.io/pandocs/Audio_Registers.html#sound-channel-3--wave-output
+pub struct Wave {
+    registers: [u8; 5], //NR30-NR34 as written
+    ram: [u8; WAVE_RAM_SIZE],
+    enabled: bool,
+    length: Length,
+    timer: u16, //t-cycles until the next sample
+    position: u8 //sample index 0-31
+}
+
+impl Default for Wave {
+    fn default() -> Self {
+        Wave::new()
+    }
+}
+
+impl Wave {
+    pub fn new() -> Wave {
+        Wave {
+            registers: [0; 5],
+            ram: [0; WAVE_RAM_SIZE],
+            enabled: false,
+            length: Length::new(256),
+            timer: 0,
+            position: 0
+        }
+    }
+
+    pub fn read(&self, register: usize) -> u8 {
+        self.registers[register]
+    }
+
+    pub fn write(&mut self, register: usize, value: u8) {
+        self.registers[register] = value;
+
+        match register {
+            0 if !self.dac_enabled() => self.enabled = false,
+            1 => self.length.load(value),
+            4 => {
+                self.length.set_enabled(value & 0x40 != 0);
+
+                if value & 0x80 != 0 {
+                    self.trigger();
+                }
+            },
+            _ => ()
+        }
+    }
+
+    //powering the APU off clears the registers, wave RAM & on DMG the length counter keep their contents
+    pub fn reset(&mut self) {
+        *self = Wave {
+            ram: self.ram,
+            length: self.length,
+            ..Wave::new()
+        };
+        self.length.set_enabled(false);
+    }
+
+    pub fn write_length(&mut self, value: u8) {
+        self.length.load(value);
+    }
+
+    pub fn read_ram(&self, index: usize) -> u8 {
+        self.ram[index]
+    }
+
+    pub fn write_ram(&mut self, index: usize, value: u8) {
+        self.ram[index] = value;
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    //digital output 0-15, None while the DAC is off
+    pub fn output(&self) -> Option<u8> {
+        if !self.dac_enabled() {
+            return Option::None;
+        }
+
+        if !self.enabled {
+            return Option::Some(0);
+        }
+
+        let byte = self.ram[(self.position / 2) as usize];
+        let sample = if self.position.is_multiple_of(2) { byte >> 4 } else { byte & 0x0F };
+
+        let shift = match (self.registers[2] >> 5) & 0x03 {
+            0 => 4, //mute
+            1 => 0, //100%
+            2 => 1, //50%
+            _ => 2 //25%
+        };
+
+        Option::Some(sample >> shift)
+    }
+
+    pub fn tick(&mut self) {
+        if self.timer <= 1 {
+            self.timer = self.period();
+            self.position = (self.position + 1) % 32;
+        } else {
+            self.timer -= 1;
+        }
+    }
+
+    pub fn clock_length(&mut self) {
+        if !self.length.clock() {
+            self.enabled = false;
+        }
+    }
+
+    fn trigger(&mut self) {
+        self.enabled = self.dac_enabled();
+        self.length.trigger();
+        self.timer = self.period();
+        self.position = 0;
+    }
+
+    //t-cycles per sample, twice the rate of the pulse channels
+    fn period(&self) -> u16 {
+        let frequency = ((self.registers[4] & 0x07) as u16) << 8 | self.registers[3] as u16;
+        (2048 - frequency) * 2
+    }
+
+    fn dac_enabled(&self) -> bool {
+        self.registers[0] & 0x80 != 0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_output() {
+        let mut wave = Wave::new();
+        wave.write_ram(0, 0x8F);
+        wave.write(0, 0x80);
+        wave.write(2, 0x20); //100%
+        wave.write(3, 0xFF); //2 t-cycles a sample
+        wave.write(4, 0x87);
+
+        assert_eq!(Option::Some(0x08), wave.output());
+
+        wave.tick();
+        wave.tick();
+        assert_eq!(Option::Some(0x0F), wave.output());
+
+        wave.write(2, 0x60); //25%
+        assert_eq!(Option::Some(0x03), wave.output());
+
+        wave.write(2, 0x00); //mute
+        assert_eq!(Option::Some(0x00), wave.output());
+
+        wave.write(0, 0x00);
+        assert_eq!(Option::None, wave.output());
+        assert!(!wave.is_enabled());
+    }
+}
@@ -0,0 +1,111 @@
+use std::io;
+
+use crate::apu::Sample;
+
+pub use wav::WavWriter;
+#[cfg(feature = "audio")]
+pub use host::HostAudio;
+
+mod wav;
+#[cfg(feature = "audio")]
+mod host;
+
+//https://docs.libretro.com/development/cores/dynamic-rate-control/
+const MAX_RATE_DELTA: f64 = 0.005; //how far the resampling ratio may stray from 1, too small a change in pitch to hear
+
+//consumers of the APU's stereo stream, fed once per emulated frame
+pub trait AudioSink {
+    fn write(&mut self, samples: &[Sample]) -> io::Result<()>;
+
+    //called every `SAVE_INTERVAL` & before exiting, so output survives the process being killed
+    fn flush(&mut self) -> io::Result<()> {
+        Result::Ok(())
+    }
+}
+
+//the emulator and the sound card run off different clocks, nudging the resampling ratio by how full
+//the output queue is keeps it from slowly draining or overflowing without an audible pitch change
+pub fn rate_ratio(queued: usize, target: usize) -> f64 {
+    let fill = (queued as f64 / (target * 2) as f64).min(1.0); //0.5 is on target
+
+    1.0 + MAX_RATE_DELTA * (1.0 - 2.0 * fill)
+}
+
+//linear interpolation, cheap and good enough for ratios this close to 1
+pub struct Resampler {
+    position: f64, //how far between the previous sample and the next input the next output falls
+    previous: Sample
+}
+
+impl Default for Resampler {
+    fn default() -> Self {
+        Resampler::new()
+    }
+}
+
+impl Resampler {
+    pub fn new() -> Resampler {
+        Resampler {
+            position: 0.0,
+            previous: [0.0; 2]
+        }
+    }
+
+    //a ratio above 1 stretches the input into more samples
+    pub fn process(&mut self, input: &[Sample], ratio: f64, output: &mut Vec<Sample>) {
+        let step = 1.0 / ratio;
+
+        for sample in input {
+            while self.position < 1.0 {
+                let weight = self.position as f32;
+
+                output.push([
+                    self.previous[0] + (sample[0] - self.previous[0]) * weight,
+                    self.previous[1] + (sample[1] - self.previous[1]) * weight
+                ]);
+
+                self.position += step;
+            }
+
+            self.position -= 1.0;
+            self.previous = *sample;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rate_ratio() {
+        assert_eq!(1.0, rate_ratio(100, 100));
+        assert_eq!(1.0 + MAX_RATE_DELTA, rate_ratio(0, 100)); //starved, produce more
+        assert_eq!(1.0 - MAX_RATE_DELTA, rate_ratio(200, 100));
+        assert_eq!(1.0 - MAX_RATE_DELTA, rate_ratio(500, 100));
+    }
+
+    #[test]
+    fn test_resample_unchanged() {
+        let mut resampler = Resampler::new();
+        let mut output = Vec::new();
+
+        resampler.process(&[[0.5, -0.5], [1.0, -1.0]], 1.0, &mut output);
+
+        assert_eq!(vec![[0.0, 0.0], [0.5, -0.5]], output); //one sample behind
+    }
+
+    #[test]
+    fn test_resample_ratio() {
+        let mut resampler = Resampler::new();
+        let mut output = Vec::new();
+
+        resampler.process(&[[1.0; 2]; 4], 2.0, &mut output);
+        assert_eq!(8, output.len());
+        assert_eq!([0.5; 2], output[1]); //halfway from silence
+
+        output.clear();
+        resampler.process(&vec![[1.0; 2]; 1000], 1.0 + MAX_RATE_DELTA, &mut output);
+        assert_eq!(1005, output.len());
+    }
+}
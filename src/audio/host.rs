@@ -0,0 +1,119 @@
+use std::collections::VecDeque;
+use std::io;
+use std::sync::{Arc, Mutex};
+
+use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
+use cpal::{BuildStreamError, Device, FromSample, SampleFormat, SizedSample, Stream, StreamConfig, SupportedStreamConfig};
+
+use crate::apu::Sample;
+use super::{AudioSink, Resampler, rate_ratio};
+
+const TARGET_LATENCY_MS: u32 = 80; //how much audio the queue aims to hold
+
+//plays through the default output device, the callback runs on cpal's own thread
+pub struct HostAudio {
+    _stream: Stream, //playback stops when dropped
+    queue: Arc<Mutex<VecDeque<Sample>>>,
+    sample_rate: u32,
+    target: usize, //queued samples the rate control aims for
+    resampler: Resampler,
+    resampled: Vec<Sample>
+}
+
+impl HostAudio {
+    pub fn open() -> io::Result<HostAudio> {
+        let device = cpal::default_host()
+            .default_output_device()
+            .ok_or_else(|| io::Error::other("no audio output device"))?;
+
+        let config = output_config(&device)?;
+        let stream_config = config.config();
+        let sample_rate = config.sample_rate().0;
+        let queue = Arc::new(Mutex::new(VecDeque::new()));
+        let playing = Arc::clone(&queue);
+
+        let stream = match config.sample_format() {
+            SampleFormat::F32 => build_stream::<f32>(&device, &stream_config, playing),
+            SampleFormat::F64 => build_stream::<f64>(&device, &stream_config, playing),
+            SampleFormat::I8 => build_stream::<i8>(&device, &stream_config, playing),
+            SampleFormat::I16 => build_stream::<i16>(&device, &stream_config, playing),
+            SampleFormat::I32 => build_stream::<i32>(&device, &stream_config, playing),
+            SampleFormat::I64 => build_stream::<i64>(&device, &stream_config, playing),
+            SampleFormat::U8 => build_stream::<u8>(&device, &stream_config, playing),
+            SampleFormat::U16 => build_stream::<u16>(&device, &stream_config, playing),
+            SampleFormat::U32 => build_stream::<u32>(&device, &stream_config, playing),
+            SampleFormat::U64 => build_stream::<u64>(&device, &stream_config, playing),
+            format => return Result::Err(io::Error::other(format!("unsupported sample format {}", format)))
+        }
+        .map_err(io::Error::other)?;
+
+        stream.play().map_err(io::Error::other)?;
+
+        Result::Ok(HostAudio {
+            _stream: stream,
+            queue,
+            sample_rate,
+            target: (sample_rate * TARGET_LATENCY_MS / 1000) as usize,
+            resampler: Resampler::new(),
+            resampled: Vec::new()
+        })
+    }
+
+    //the APU should produce samples at this rate
+    pub fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+}
+
+//prefers f32 at the device's default rate, any other format is converted in the callback
+fn output_config(device: &Device) -> io::Result<SupportedStreamConfig> {
+    let default = device.default_output_config().map_err(io::Error::other)?;
+    let float = device
+        .supported_output_configs()
+        .map_err(io::Error::other)?
+        .filter(|range| range.channels() == default.channels() && range.sample_format() == SampleFormat::F32)
+        .find_map(|range| range.try_with_sample_rate(default.sample_rate()));
+
+    Result::Ok(float.unwrap_or(default))
+}
+
+fn build_stream<T: SizedSample + FromSample<f32>>(
+    device: &Device,
+    config: &StreamConfig,
+    playing: Arc<Mutex<VecDeque<Sample>>>
+) -> Result<Stream, BuildStreamError> {
+    let channels = config.channels as usize;
+
+    device.build_output_stream(
+        config,
+        move |data: &mut [T], _: &cpal::OutputCallbackInfo| {
+            let mut queue = playing.lock().unwrap();
+
+            for frame in data.chunks_mut(channels) {
+                let sample = queue.pop_front().unwrap_or([0.0; 2]); //underrun plays silence
+
+                for (channel, output) in frame.iter_mut().enumerate() {
+                    *output = T::from_sample_(sample[channel.min(1)]); //mono devices get the left side
+                }
+            }
+        },
+        |error| eprintln!("Error playing audio, Error: {}", error),
+        Option::None
+    )
+}
+
+impl AudioSink for HostAudio {
+    fn write(&mut self, samples: &[Sample]) -> io::Result<()> {
+        let mut queue = self.queue.lock().unwrap();
+        let ratio = rate_ratio(queue.len(), self.target);
+
+        self.resampled.clear();
+        self.resampler.process(samples, ratio, &mut self.resampled);
+        queue.extend(self.resampled.iter());
+
+        let excess = queue.len().saturating_sub(self.target * 4); //fast-forward outruns any rate control
+        queue.drain(..excess);
+
+        Result::Ok(())
+    }
+}
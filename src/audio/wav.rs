@@ -0,0 +1,180 @@
+use std::fs::File;
+use std::io::{self, BufWriter, Seek, SeekFrom, Write};
+use std::path::Path;
+
+use crate::apu::Sample;
+use super::AudioSink;
+
+//http://soundfile.sapp.org/doc/WaveFormat/
+const HEADER_SIZE: u32 = 44;
+const CHANNELS: u16 = 2;
+const BITS_PER_SAMPLE: u16 = 16;
+const BLOCK_ALIGN: u16 = CHANNELS * BITS_PER_SAMPLE / 8;
+const RIFF_SIZE_OFFSET: u64 = 4;
+const DATA_SIZE_OFFSET: u64 = 40;
+
+//16-bit PCM stereo, the sizes in the header are filled in when flushed, finished or dropped
+pub struct WavWriter<W: Write + Seek> {
+    writer: W,
+    data_size: u32,
+    finished: bool
+}
+
+impl WavWriter<BufWriter<File>> {
+    pub fn create(path: &Path, sample_rate: u32) -> io::Result<WavWriter<BufWriter<File>>> {
+        WavWriter::new(BufWriter::new(File::create(path)?), sample_rate)
+    }
+}
+
+impl<W: Write + Seek> WavWriter<W> {
+    pub fn new(mut writer: W, sample_rate: u32) -> io::Result<WavWriter<W>> {
+        writer.write_all(b"RIFF")?;
+        writer.write_all(&(HEADER_SIZE - 8).to_le_bytes())?; //no data yet
+        writer.write_all(b"WAVE")?;
+
+        writer.write_all(b"fmt ")?;
+        writer.write_all(&16u32.to_le_bytes())?;
+        writer.write_all(&1u16.to_le_bytes())?; //PCM
+        writer.write_all(&CHANNELS.to_le_bytes())?;
+        writer.write_all(&sample_rate.to_le_bytes())?;
+        writer.write_all(&(sample_rate * BLOCK_ALIGN as u32).to_le_bytes())?; //byte rate
+        writer.write_all(&BLOCK_ALIGN.to_le_bytes())?;
+        writer.write_all(&BITS_PER_SAMPLE.to_le_bytes())?;
+
+        writer.write_all(b"data")?;
+        writer.write_all(&0u32.to_le_bytes())?;
+
+        Result::Ok(WavWriter {
+            writer,
+            data_size: 0,
+            finished: false
+        })
+    }
+
+    pub fn finish(&mut self) -> io::Result<()> {
+        self.finished = true;
+        self.write_sizes()
+    }
+
+    //patch the chunk sizes, everything written so far is playable after this
+    fn write_sizes(&mut self) -> io::Result<()> {
+        self.writer.seek(SeekFrom::Start(RIFF_SIZE_OFFSET))?;
+        self.writer.write_all(&(HEADER_SIZE - 8 + self.data_size).to_le_bytes())?;
+        self.writer.seek(SeekFrom::Start(DATA_SIZE_OFFSET))?;
+        self.writer.write_all(&self.data_size.to_le_bytes())?;
+        self.writer.seek(SeekFrom::End(0))?;
+
+        self.writer.flush()
+    }
+}
+
+impl<W: Write + Seek> AudioSink for WavWriter<W> {
+    //the RIFF size field is 32 bits, a little over 6 hours at 48kHz
+    fn write(&mut self, samples: &[Sample]) -> io::Result<()> {
+        let data_size = u32::try_from(samples.len())
+            .ok()
+            .and_then(|len| len.checked_mul(BLOCK_ALIGN as u32))
+            .and_then(|bytes| self.data_size.checked_add(bytes))
+            .filter(|size| size.checked_add(HEADER_SIZE - 8).is_some())
+            .ok_or_else(|| io::Error::other("WAV file has reached the 4 GiB limit"))?;
+
+        for sample in samples {
+            for side in sample {
+                let value = (side.clamp(-1.0, 1.0) * i16::MAX as f32) as i16;
+                self.writer.write_all(&value.to_le_bytes())?;
+            }
+        }
+
+        self.data_size = data_size;
+
+        Result::Ok(())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.write_sizes()
+    }
+}
+
+impl<W: Write + Seek> Drop for WavWriter<W> {
+    fn drop(&mut self) {
+        if self.finished {
+            return;
+        }
+
+        if let Result::Err(error) = self.finish() {
+            eprintln!("Error finishing WAV file, Error: {}", error);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    #[test]
+    fn test_header() {
+        let mut cursor = Cursor::new(Vec::new());
+
+        {
+            let mut wav = WavWriter::new(&mut cursor, 48000).unwrap();
+            wav.write(&[[1.0, -1.0], [0.0, 2.0]]).unwrap();
+        } //finished on drop
+
+        let bytes = cursor.into_inner();
+
+        assert_eq!(HEADER_SIZE as usize + 8, bytes.len());
+        assert_eq!(b"RIFF", &bytes[0..4]);
+        assert_eq!(44u32.to_le_bytes(), bytes[4..8]); //36 + 8 bytes of data
+        assert_eq!(b"WAVEfmt ", &bytes[8..16]);
+        assert_eq!(2u16.to_le_bytes(), bytes[22..24]);
+        assert_eq!(48000u32.to_le_bytes(), bytes[24..28]);
+        assert_eq!(192000u32.to_le_bytes(), bytes[28..32]);
+        assert_eq!(b"data", &bytes[36..40]);
+        assert_eq!(8u32.to_le_bytes(), bytes[40..44]);
+    }
+
+    #[test]
+    fn test_samples() {
+        let mut cursor = Cursor::new(Vec::new());
+        let mut wav = WavWriter::new(&mut cursor, 44100).unwrap();
+
+        wav.write(&[[1.0, -1.0], [0.5, 3.0]]).unwrap();
+        wav.finish().unwrap();
+        drop(wav);
+
+        let bytes = cursor.into_inner();
+        let values: Vec<i16> = bytes[44..]
+            .chunks(2)
+            .map(|pair| i16::from_le_bytes([pair[0], pair[1]]))
+            .collect();
+
+        assert_eq!(vec![32767, -32767, 16383, 32767], values); //clamped
+    }
+
+    #[test]
+    fn test_flush() {
+        let mut cursor = Cursor::new(Vec::new());
+        let mut wav = WavWriter::new(&mut cursor, 48000).unwrap();
+
+        wav.write(&[[0.5, 0.5]]).unwrap();
+        wav.flush().unwrap();
+        wav.write(&[[0.5, 0.5]]).unwrap(); //carries on at the end
+
+        let bytes = wav.writer.get_ref().clone();
+
+        assert_eq!(HEADER_SIZE as usize + 8, bytes.len());
+        assert_eq!(40u32.to_le_bytes(), bytes[4..8]);
+        assert_eq!(4u32.to_le_bytes(), bytes[40..44]);
+    }
+
+    #[test]
+    fn test_size_limit() {
+        let mut wav = WavWriter::new(Cursor::new(Vec::new()), 48000).unwrap();
+        wav.data_size = u32::MAX - (HEADER_SIZE - 8) - 4;
+
+        assert!(wav.write(&[[0.0; 2]]).is_ok()); //exactly at the limit
+        assert!(wav.write(&[[0.0; 2]]).is_err());
+        assert_eq!(u32::MAX - (HEADER_SIZE - 8), wav.data_size);
+    }
+}
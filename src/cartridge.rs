@@ -0,0 +1,511 @@
+use std::{fmt, fs, io, path::{Path, PathBuf}};
+
+pub use header::{CartridgeHeader, CartridgeType, Controller, CgbSupport, HeaderWarning, Licensee};
+use mbc::{MemoryBankController, NoMbc, Mbc1, Mbc2, Mbc3, Mbc5, MBC2_RAM_SIZE, EXTERNAL_RAM_START};
+pub use rtc::{Clock, SystemClock};
+use rtc::{Rtc, RTC_SAVE_SIZE};
+
+mod header;
+mod mbc;
+mod rtc;
+mod save;
+
+#[cfg(test)]
+pub use header::tests::{build_rom, fix_checksums};
+
+const OPEN_BUS: u8 = 0xFF;
+const ROM_END: u16 = 0x7FFF;
+const MBC2_RAM_MASK: u8 = 0xF0; //only the lower nibble is stored, the upper bits read back as 1
+
+#[derive(Debug)]
+pub enum CartridgeError {
+    Io(io::Error),
+    TooSmall(usize), //ROM is not large enough to contain a header
+    UnknownCartridgeType(u8),
+    UnsupportedController(Controller),
+    InvalidRomSize(u8),
+    InvalidRamSize(u8),
+    RomSizeMismatch { expected: usize, actual: usize }, //the file is shorter than the header declares
+    HeaderChecksum { expected: u8, actual: u8 },
+    InvalidSaveSize { expected: usize, actual: usize }
+}
+
+impl fmt::Display for CartridgeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            CartridgeError::Io(error) => write!(f, "unable to read ROM: {}", error),
+            CartridgeError::TooSmall(size) => write!(f, "ROM is {} bytes, too small to contain a header", size),
+            CartridgeError::UnknownCartridgeType(code) => write!(f, "unknown cartridge type {:#04x}", code),
+            CartridgeError::UnsupportedController(controller) => write!(f, "{:?} cartridges are not supported", controller),
+            CartridgeError::InvalidRomSize(code) => write!(f, "invalid ROM size code {:#04x}", code),
+            CartridgeError::InvalidRamSize(code) => write!(f, "invalid RAM size code {:#04x}", code),
+            CartridgeError::RomSizeMismatch { expected, actual } => write!(
+                f,
+                "header declares a ROM of {} bytes but the file is {} bytes",
+                expected,
+                actual
+            ),
+            CartridgeError::HeaderChecksum { expected, actual } => write!(
+                f,
+                "header checksum mismatch, expected {:#04x} but calculated {:#04x}",
+                expected,
+                actual
+            ),
+            CartridgeError::InvalidSaveSize { expected, actual } => write!(
+                f,
+                "save data is {} bytes, expected {} bytes of RAM plus an optional RTC trailer",
+                actual,
+                expected
+            )
+        }
+    }
+}
+
+impl std::error::Error for CartridgeError {}
+
+impl From<io::Error> for CartridgeError {
+    fn from(error: io::Error) -> Self {
+        CartridgeError::Io(error)
+    }
+}
+
+pub struct Cartridge {
+    header: CartridgeHeader,
+    rom: Vec<u8>,
+    ram: Vec<u8>,
+    mbc: Box<dyn MemoryBankController>,
+    save_path: Option<PathBuf>, //only set for battery backed cartridges loaded from disk
+    dirty: bool //RAM or RTC written since the last flush
+}
+
+impl Cartridge {
+    //loads <rom>.sav alongside the ROM when the cartridge has a battery
+    pub fn load(path: &Path) -> Result<Cartridge, CartridgeError> {
+        let mut cartridge = Cartridge::from_bytes(fs::read(path)?)?;
+
+        if cartridge.header.cartridge_type.battery {
+            let save_path = save::save_path(path);
+
+            if let Option::Some(data) = save::read_save(&save_path)? {
+                if let Result::Err(error) = cartridge.load_save_data(&data) {
+                    //the game still boots, the original file is kept aside rather than overwritten by the next flush
+                    eprintln!("Warning loading save {}: {}, loaded what fits", save_path.display(), error);
+
+                    match save::back_up(&save_path) {
+                        Result::Ok(backup) => eprintln!("The original save was moved to {}", backup.display()),
+                        Result::Err(error) => {
+                            eprintln!("Error backing up save, saving is disabled, Error: {}", error);
+                            return Result::Ok(cartridge);
+                        }
+                    }
+                }
+            }
+
+            cartridge.save_path = Option::Some(save_path);
+        }
+
+        Result::Ok(cartridge)
+    }
+
+    pub fn from_bytes(rom: Vec<u8>) -> Result<Cartridge, CartridgeError> {
+        Cartridge::with_clock(rom, Box::new(SystemClock {}))
+    }
+
+    //clock drives the MBC3 RTC, if the cartridge has one
+    pub fn with_clock(rom: Vec<u8>, clock: Box<dyn Clock>) -> Result<Cartridge, CartridgeError> {
+        let header = CartridgeHeader::parse(&rom)?;
+        let mut ram_size = header.ram_size;
+
+        let mbc: Box<dyn MemoryBankController> = match header.cartridge_type.controller {
+            Controller::RomOnly => Box::new(NoMbc {}),
+            Controller::Mbc1 => Box::new(Mbc1::new()),
+            Controller::Mbc2 => {
+                ram_size = MBC2_RAM_SIZE; //header declares 0, the RAM is inside the MBC
+                Box::new(Mbc2::new())
+            },
+            Controller::Mbc3 => Box::new(Mbc3::new(
+                if header.cartridge_type.timer { Option::Some(Rtc::new(clock)) } else { Option::None }
+            )),
+            Controller::Mbc5 => Box::new(Mbc5::new()),
+            controller => return Result::Err(CartridgeError::UnsupportedController(controller))
+        };
+
+        Result::Ok(Cartridge {
+            header,
+            rom,
+            ram: vec![0x00; ram_size],
+            mbc,
+            save_path: Option::None,
+            dirty: false
+        })
+    }
+
+    pub fn header(&self) -> &CartridgeHeader {
+        &self.header
+    }
+
+    pub fn rom(&self) -> &[u8] {
+        &self.rom
+    }
+
+    //read from 0x0000-0x7FFF or 0xA000-0xBFFF
+    pub fn read(&self, addr: u16) -> u8 {
+        if addr <= ROM_END {
+            //ROM sizes are powers of 2 so masking wraps banks that are out of range, bytes past the declared size are never mapped
+            return self.rom[self.mbc.rom_offset(addr) & (self.header.rom_size - 1)];
+        }
+
+        if let Option::Some(value) = self.mbc.rtc_register() {
+            return value;
+        }
+
+        match self.ram_offset(addr) {
+            Option::Some(offset) => self.ram[offset],
+            Option::None => OPEN_BUS
+        }
+    }
+
+    //write to 0x0000-0x7FFF (MBC registers) or 0xA000-0xBFFF
+    pub fn write(&mut self, addr: u16, value: u8) {
+        if addr <= ROM_END {
+            self.mbc.write_register(addr, value);
+
+            //latching catches the clock up, the advanced registers need saving as well
+            if self.mbc.rtc_mut().is_some_and(|rtc| rtc.is_changed()) {
+                self.dirty = true;
+            }
+
+            return;
+        }
+
+        if self.mbc.write_rtc_register(value) {
+            self.dirty = true;
+            return;
+        }
+
+        if let Option::Some(offset) = self.ram_offset(addr) {
+            self.ram[offset] = match self.header.cartridge_type.controller {
+                Controller::Mbc2 => value | MBC2_RAM_MASK,
+                _ => value
+            };
+            self.dirty = true;
+        }
+    }
+
+    pub fn is_dirty(&self) -> bool {
+        self.dirty
+    }
+
+    //write battery backed RAM (and RTC) to the .sav file if anything changed since the last flush
+    pub fn flush_save(&mut self) -> io::Result<()> {
+        if !self.dirty {
+            return Result::Ok(());
+        }
+
+        let data = self.save_data(); //also settles the RTC
+
+        if let Option::Some(path) = &self.save_path {
+            save::write_save(path, &data)?;
+        }
+
+        self.dirty = false;
+
+        Result::Ok(())
+    }
+
+    //external RAM followed by the 48 byte RTC trailer when there is a clock, the layout BGB & VBA use for .sav files
+    pub fn save_data(&mut self) -> Vec<u8> {
+        let mut data = self.ram.clone();
+
+        if let Option::Some(rtc) = self.mbc.rtc_mut() {
+            data.extend_from_slice(&rtc.save_bytes());
+        }
+
+        data
+    }
+
+    //a save without the RTC trailer is accepted, the clock then keeps its current time
+    //data of the wrong size is still loaded as far as it fits, with the rest of RAM zeroed, before the error is returned
+    pub fn load_save_data(&mut self, data: &[u8]) -> Result<(), CartridgeError> {
+        let ram_size = self.ram.len();
+        let (ram, trailer) = data.split_at(ram_size.min(data.len()));
+
+        self.ram.fill(0x00);
+        self.ram[..ram.len()].copy_from_slice(ram);
+
+        if ram.len() < ram_size {
+            return Result::Err(CartridgeError::InvalidSaveSize {
+                expected: ram_size,
+                actual: data.len()
+            });
+        }
+
+        let trailer_loaded = match self.mbc.rtc_mut() {
+            Option::Some(rtc) => trailer.is_empty() || rtc.load_bytes(trailer),
+            Option::None => trailer.is_empty()
+        };
+
+        if !trailer_loaded {
+            return Result::Err(CartridgeError::InvalidSaveSize {
+                expected: ram_size + RTC_SAVE_SIZE,
+                actual: data.len()
+            });
+        }
+
+        Result::Ok(())
+    }
+
+    fn ram_offset(&self, addr: u16) -> Option<usize> {
+        if self.ram.is_empty() || addr < EXTERNAL_RAM_START {
+            return Option::None;
+        }
+
+        self.mbc.ram_offset(addr).map(|offset| offset & (self.ram.len() - 1))
+    }
+
+    pub fn status(&self) {
+        let header = &self.header;
+
+        println!("\n===CARTRIDGE===");
+        println!("TITLE: {}", header.title);
+        println!(
+            "TYPE: {:#02x} ({:?}, RAM: {}, BATTERY: {}, TIMER: {}, RUMBLE: {})",
+            header.cartridge_type.code,
+            header.cartridge_type.controller,
+            header.cartridge_type.ram,
+            header.cartridge_type.battery,
+            header.cartridge_type.timer,
+            header.cartridge_type.rumble
+        );
+        println!("ROM: {} KiB ({} banks)", header.rom_size / 1024, header.rom_banks());
+        println!("RAM: {} KiB", header.ram_size / 1024);
+        println!("LICENSEE: {:?}", header.licensee);
+        println!("CGB: {:?}, SGB: {}", header.cgb, header.sgb);
+        println!("VERSION: {}", header.version);
+
+        for warning in &header.warnings {
+            println!("WARNING: {}", warning);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{cell::Cell, rc::Rc};
+
+    use super::*;
+    use super::rtc::tests::FakeClock;
+
+    #[test]
+    fn test_from_bytes() {
+        let rom = build_rom("ZELDA", 0x00, 0x00, 0x00);
+        let cartridge = Cartridge::from_bytes(rom).unwrap();
+
+        assert_eq!("ZELDA", cartridge.header().title);
+        assert_eq!(0x8000, cartridge.rom().len());
+    }
+
+    #[test]
+    fn test_unsupported_controller() {
+        let rom = build_rom("CAMERA", 0xFC, 0x00, 0x00);
+
+        assert!(matches!(
+            Cartridge::from_bytes(rom),
+            Result::Err(CartridgeError::UnsupportedController(Controller::PocketCamera))
+        ));
+    }
+
+    #[test]
+    fn test_rom_bank_switching() {
+        let mut rom = build_rom("BANKS", 0x01, 0x02, 0x00); //MBC1, 128 KiB
+
+        for bank in 0..8 {
+            rom[bank * 0x4000 + 0x1000] = bank as u8;
+        }
+
+        fix_checksums(&mut rom);
+
+        let mut cartridge = Cartridge::from_bytes(rom).unwrap();
+
+        assert_eq!(0x00, cartridge.read(0x1000));
+        assert_eq!(0x01, cartridge.read(0x5000));
+
+        cartridge.write(0x2000, 0x05);
+
+        assert_eq!(0x05, cartridge.read(0x5000));
+
+        cartridge.write(0x2000, 0x0B); //out of range banks wrap around
+
+        assert_eq!(0x03, cartridge.read(0x5000));
+        assert_eq!(0x00, cartridge.read(0x1000)); //writes never reach ROM
+    }
+
+    #[test]
+    fn test_external_ram() {
+        let rom = build_rom("RAM", 0x03, 0x00, 0x03); //MBC1 + RAM + battery, 32 KiB RAM
+        let mut cartridge = Cartridge::from_bytes(rom).unwrap();
+
+        cartridge.write(0xA000, 0x42);
+
+        assert_eq!(0xFF, cartridge.read(0xA000)); //disabled
+
+        cartridge.write(0x0000, 0x0A);
+        cartridge.write(0x6000, 0x01);
+        cartridge.write(0xA000, 0x42);
+        cartridge.write(0x4000, 0x01);
+        cartridge.write(0xA000, 0x24);
+
+        assert_eq!(0x24, cartridge.read(0xA000));
+
+        cartridge.write(0x4000, 0x00);
+
+        assert_eq!(0x42, cartridge.read(0xA000));
+    }
+
+    #[test]
+    fn test_mbc2_ram() {
+        let rom = build_rom("MBC2", 0x06, 0x00, 0x00);
+        let mut cartridge = Cartridge::from_bytes(rom).unwrap();
+
+        cartridge.write(0x0000, 0x0A);
+        cartridge.write(0xA001, 0x3C);
+
+        assert_eq!(0xFC, cartridge.read(0xA001));
+        assert_eq!(0xFC, cartridge.read(0xA201));
+    }
+
+    #[test]
+    fn test_rtc_save_data() {
+        let now = Rc::new(Cell::new(500));
+        let rom = build_rom("CLOCK", 0x10, 0x00, 0x02); //MBC3 + TIMER + RAM + battery
+        let mut cartridge = Cartridge::with_clock(rom.clone(), Box::new(FakeClock { now: now.clone() })).unwrap();
+
+        cartridge.write(0x0000, 0x0A);
+        cartridge.write(0xA000, 0x77);
+        cartridge.write(0x4000, 0x0A); //hours
+        cartridge.write(0xA000, 0x03);
+
+        let data = cartridge.save_data();
+
+        assert_eq!(0x2000 + 48, data.len());
+        assert_eq!(0x77, data[0]);
+        assert_eq!(0x03, data[0x2000 + 8]);
+
+        let mut loaded = Cartridge::with_clock(rom, Box::new(FakeClock { now: now.clone() })).unwrap();
+
+        assert!(loaded.load_save_data(&data[..0x2000]).is_ok()); //RTC trailer is optional
+        assert!(matches!(
+            loaded.load_save_data(&data[..0x2010]),
+            Result::Err(CartridgeError::InvalidSaveSize { .. })
+        ));
+        assert_eq!(0x77, loaded.ram[0]); //the RAM part still loads
+
+        assert!(matches!(
+            loaded.load_save_data(&data[..0x10]),
+            Result::Err(CartridgeError::InvalidSaveSize { .. })
+        ));
+        assert_eq!(0x77, loaded.ram[0]);
+        assert_eq!(0x00, loaded.ram[0x10]); //zero filled
+
+        assert!(loaded.load_save_data(&data).is_ok());
+
+        loaded.write(0x0000, 0x0A);
+        loaded.write(0x6000, 0x00);
+        loaded.write(0x6000, 0x01);
+        loaded.write(0x4000, 0x0A);
+
+        assert_eq!(0x03, loaded.read(0xA000));
+
+        loaded.write(0x4000, 0x00);
+
+        assert_eq!(0x77, loaded.read(0xA000));
+    }
+
+    #[test]
+    fn test_battery_save_file() {
+        let dir = std::env::temp_dir().join(format!("game_boy_emulator_cart_{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+
+        let rom_path = dir.join("battery.gb");
+        fs::write(&rom_path, build_rom("BATTERY", 0x03, 0x00, 0x02)).unwrap();
+
+        let mut cartridge = Cartridge::load(&rom_path).unwrap();
+
+        assert!(!cartridge.is_dirty());
+
+        cartridge.write(0x0000, 0x0A);
+        cartridge.write(0xA123, 0x5A);
+
+        assert!(cartridge.is_dirty());
+
+        cartridge.flush_save().unwrap();
+
+        assert!(!cartridge.is_dirty());
+
+        let save = fs::read(dir.join("battery.sav")).unwrap();
+
+        assert_eq!(0x2000, save.len()); //raw RAM dump, no header
+        assert_eq!(0x5A, save[0x0123]);
+
+        let mut reloaded = Cartridge::load(&rom_path).unwrap();
+        reloaded.write(0x0000, 0x0A);
+
+        assert_eq!(0x5A, reloaded.read(0xA123));
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_rtc_marks_dirty() {
+        let now = Rc::new(Cell::new(500));
+        let rom = build_rom("CLOCK", 0x0F, 0x00, 0x00); //MBC3 + TIMER + battery
+        let mut cartridge = Cartridge::with_clock(rom, Box::new(FakeClock { now: now.clone() })).unwrap();
+
+        cartridge.write(0x6000, 0x00);
+        cartridge.write(0x6000, 0x01);
+
+        assert!(!cartridge.is_dirty()); //no time has passed
+
+        now.set(510);
+        cartridge.write(0x6000, 0x00);
+        cartridge.write(0x6000, 0x01);
+
+        assert!(cartridge.is_dirty());
+
+        cartridge.flush_save().unwrap();
+        cartridge.write(0x6000, 0x00);
+
+        assert!(!cartridge.is_dirty());
+    }
+
+    #[test]
+    fn test_bad_save_file() {
+        let dir = std::env::temp_dir().join(format!("game_boy_emulator_bad_save_{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+
+        let rom_path = dir.join("bad.gb");
+        fs::write(&rom_path, build_rom("BAD", 0x03, 0x00, 0x02)).unwrap();
+        fs::write(dir.join("bad.sav"), [0x5A; 0x100]).unwrap(); //truncated
+
+        let mut cartridge = Cartridge::load(&rom_path).unwrap();
+        cartridge.write(0x0000, 0x0A);
+
+        assert_eq!(0x5A, cartridge.read(0xA0FF));
+        assert_eq!(0x00, cartridge.read(0xA100));
+
+        cartridge.write(0xA100, 0x01);
+        cartridge.flush_save().unwrap();
+
+        assert_eq!(vec![0x5A; 0x100], fs::read(dir.join("bad.sav.bak")).unwrap()); //the original is kept
+        assert_eq!(0x2000, fs::read(dir.join("bad.sav")).unwrap().len());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_load_missing_file() {
+        let result = Cartridge::load(Path::new("assets/does_not_exist.gb"));
+
+        assert!(matches!(result, Result::Err(CartridgeError::Io(_))));
+    }
+}
@@ -0,0 +1,421 @@
+use std::fmt;
+
+use super::CartridgeError;
+
+//https://gbdev.io/pandocs/The_Cartridge_Header.html
+const HEADER_END: usize = 0x0150;
+
+const TITLE: usize = 0x0134;
+const TITLE_END: usize = 0x0144;
+const CGB_TITLE_END: usize = 0x013F; //CGB carts use the end of the title area for the manufacturer code & CGB flag
+const CGB_FLAG: usize = 0x0143;
+const NEW_LICENSEE: usize = 0x0144;
+const SGB_FLAG: usize = 0x0146;
+const CARTRIDGE_TYPE: usize = 0x0147;
+const ROM_SIZE: usize = 0x0148;
+const RAM_SIZE: usize = 0x0149;
+const DESTINATION: usize = 0x014A;
+const OLD_LICENSEE: usize = 0x014B;
+const VERSION: usize = 0x014C;
+const HEADER_CHECKSUM: usize = 0x014D;
+const GLOBAL_CHECKSUM: usize = 0x014E;
+
+const USE_NEW_LICENSEE: u8 = 0x33;
+const ROM_BANK_SIZE: usize = 0x4000;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Controller {
+    RomOnly,
+    Mbc1,
+    Mbc2,
+    Mmm01,
+    Mbc3,
+    Mbc5,
+    Mbc6,
+    Mbc7,
+    PocketCamera,
+    Tama5,
+    HuC3,
+    HuC1
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct CartridgeType {
+    pub code: u8,
+    pub controller: Controller,
+    pub ram: bool,
+    pub battery: bool,
+    pub timer: bool,
+    pub rumble: bool
+}
+
+impl CartridgeType {
+    pub fn from_code(code: u8) -> Result<CartridgeType, CartridgeError> {
+        //(controller, ram, battery, timer, rumble)
+        let (controller, ram, battery, timer, rumble) = match code {
+            0x00 => (Controller::RomOnly, false, false, false, false),
+            0x01 => (Controller::Mbc1, false, false, false, false),
+            0x02 => (Controller::Mbc1, true, false, false, false),
+            0x03 => (Controller::Mbc1, true, true, false, false),
+            0x05 => (Controller::Mbc2, false, false, false, false),
+            0x06 => (Controller::Mbc2, false, true, false, false),
+            0x08 => (Controller::RomOnly, true, false, false, false),
+            0x09 => (Controller::RomOnly, true, true, false, false),
+            0x0B => (Controller::Mmm01, false, false, false, false),
+            0x0C => (Controller::Mmm01, true, false, false, false),
+            0x0D => (Controller::Mmm01, true, true, false, false),
+            0x0F => (Controller::Mbc3, false, true, true, false),
+            0x10 => (Controller::Mbc3, true, true, true, false),
+            0x11 => (Controller::Mbc3, false, false, false, false),
+            0x12 => (Controller::Mbc3, true, false, false, false),
+            0x13 => (Controller::Mbc3, true, true, false, false),
+            0x19 => (Controller::Mbc5, false, false, false, false),
+            0x1A => (Controller::Mbc5, true, false, false, false),
+            0x1B => (Controller::Mbc5, true, true, false, false),
+            0x1C => (Controller::Mbc5, false, false, false, true),
+            0x1D => (Controller::Mbc5, true, false, false, true),
+            0x1E => (Controller::Mbc5, true, true, false, true),
+            0x20 => (Controller::Mbc6, false, false, false, false),
+            0x22 => (Controller::Mbc7, true, true, false, true),
+            0xFC => (Controller::PocketCamera, false, false, false, false),
+            0xFD => (Controller::Tama5, false, false, false, false),
+            0xFE => (Controller::HuC3, false, false, false, false),
+            0xFF => (Controller::HuC1, true, true, false, false),
+            _ => return Result::Err(CartridgeError::UnknownCartridgeType(code))
+        };
+
+        Result::Ok(CartridgeType {
+            code,
+            controller,
+            ram,
+            battery,
+            timer,
+            rumble
+        })
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum CgbSupport {
+    None,
+    Compatible, //0x80 - works on DMG & CGB
+    Only //0xC0 - CGB only
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Licensee {
+    Old(u8),
+    New(String) //two ascii characters at 0x0144-0x0145, used when the old code is 0x33
+}
+
+//problems real hardware ignores, the cartridge still loads
+#[derive(Clone, Debug, PartialEq)]
+pub enum HeaderWarning {
+    GlobalChecksum { expected: u16, actual: u16 },
+    Oversized { expected: usize, actual: usize } //overdumps & padded homebrew, the extra bytes are never mapped
+}
+
+impl fmt::Display for HeaderWarning {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            HeaderWarning::GlobalChecksum { expected, actual } => write!(
+                f,
+                "global checksum mismatch, expected {:#06x} but calculated {:#06x}",
+                expected,
+                actual
+            ),
+            HeaderWarning::Oversized { expected, actual } => write!(
+                f,
+                "header declares a ROM of {} bytes but the file is {} bytes",
+                expected,
+                actual
+            )
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct CartridgeHeader {
+    pub title: String,
+    pub cgb: CgbSupport,
+    pub sgb: bool,
+    pub licensee: Licensee,
+    pub cartridge_type: CartridgeType,
+    pub rom_size: usize, //bytes
+    pub ram_size: usize, //bytes
+    pub japanese: bool, //destination code
+    pub version: u8,
+    pub header_checksum: u8,
+    pub global_checksum: u16,
+    pub warnings: Vec<HeaderWarning>
+}
+
+impl CartridgeHeader {
+    //parse & validate the header of a whole ROM image, only the header checksum is enforced like the boot ROM does
+    pub fn parse(rom: &[u8]) -> Result<CartridgeHeader, CartridgeError> {
+        if rom.len() < HEADER_END {
+            return Result::Err(CartridgeError::TooSmall(rom.len()));
+        }
+
+        let header_checksum = rom[HEADER_CHECKSUM];
+        let calculated = header_checksum_of(rom);
+
+        if header_checksum != calculated {
+            return Result::Err(CartridgeError::HeaderChecksum {
+                expected: header_checksum,
+                actual: calculated
+            });
+        }
+
+        let cgb = match rom[CGB_FLAG] {
+            0x80 => CgbSupport::Compatible,
+            0xC0 => CgbSupport::Only,
+            _ => CgbSupport::None
+        };
+
+        let title_end = match cgb {
+            CgbSupport::None => TITLE_END,
+            _ => CGB_TITLE_END
+        };
+
+        let title = rom[TITLE..title_end]
+            .iter()
+            .take_while(|c| **c != 0x00)
+            .map(|c| *c as char)
+            .collect();
+
+        let licensee = match rom[OLD_LICENSEE] {
+            USE_NEW_LICENSEE => Licensee::New(
+                rom[NEW_LICENSEE..NEW_LICENSEE + 2]
+                    .iter()
+                    .map(|c| *c as char)
+                    .collect()
+            ),
+            code => Licensee::Old(code)
+        };
+
+        let rom_size = rom_size_from_code(rom[ROM_SIZE])?;
+        let mut warnings = Vec::new();
+
+        if rom.len() < rom_size {
+            return Result::Err(CartridgeError::RomSizeMismatch {
+                expected: rom_size,
+                actual: rom.len()
+            });
+        }
+
+        if rom.len() > rom_size {
+            warnings.push(HeaderWarning::Oversized {
+                expected: rom_size,
+                actual: rom.len()
+            });
+        }
+
+        let global_checksum = to_u16(rom[GLOBAL_CHECKSUM], rom[GLOBAL_CHECKSUM + 1]);
+        let calculated = global_checksum_of(&rom[..rom_size]);
+
+        if global_checksum != calculated {
+            warnings.push(HeaderWarning::GlobalChecksum {
+                expected: global_checksum,
+                actual: calculated
+            });
+        }
+
+        Result::Ok(CartridgeHeader {
+            title,
+            cgb,
+            sgb: rom[SGB_FLAG] == 0x03,
+            licensee,
+            cartridge_type: CartridgeType::from_code(rom[CARTRIDGE_TYPE])?,
+            rom_size,
+            ram_size: ram_size_from_code(rom[RAM_SIZE])?,
+            japanese: rom[DESTINATION] == 0x00,
+            version: rom[VERSION],
+            header_checksum,
+            global_checksum,
+            warnings
+        })
+    }
+
+    pub fn rom_banks(&self) -> usize {
+        self.rom_size / ROM_BANK_SIZE
+    }
+}
+
+//x = x - byte - 1 over 0x0134-0x014C, the boot ROM locks up if this does not match
+pub fn header_checksum_of(rom: &[u8]) -> u8 {
+    rom[TITLE..HEADER_CHECKSUM]
+        .iter()
+        .fold(0u8, |x, byte| x.wrapping_sub(*byte).wrapping_sub(1))
+}
+
+//sum of every byte in the ROM except the two checksum bytes themselves
+pub fn global_checksum_of(rom: &[u8]) -> u16 {
+    rom.iter()
+        .enumerate()
+        .filter(|(addr, _)| *addr != GLOBAL_CHECKSUM && *addr != GLOBAL_CHECKSUM + 1)
+        .fold(0u16, |sum, (_, byte)| sum.wrapping_add(*byte as u16))
+}
+
+fn rom_size_from_code(code: u8) -> Result<usize, CartridgeError> {
+    match code {
+        0x00..=0x08 => Result::Ok((32 * 1024) << code),
+        _ => Result::Err(CartridgeError::InvalidRomSize(code))
+    }
+}
+
+fn ram_size_from_code(code: u8) -> Result<usize, CartridgeError> {
+    match code {
+        0x00 => Result::Ok(0),
+        0x01 => Result::Ok(2 * 1024), //unofficial, listed by some homebrew
+        0x02 => Result::Ok(8 * 1024),
+        0x03 => Result::Ok(32 * 1024),
+        0x04 => Result::Ok(128 * 1024),
+        0x05 => Result::Ok(64 * 1024),
+        _ => Result::Err(CartridgeError::InvalidRamSize(code))
+    }
+}
+
+//checksum is stored big endian, unlike everything else
+fn to_u16(msb: u8, lsb: u8) -> u16 {
+    ((msb as u16) << 8) | lsb as u16
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+
+    //builds a valid ROM image of the given size code with correct checksums
+    pub fn build_rom(title: &str, cartridge_type: u8, rom_size: u8, ram_size: u8) -> Vec<u8> {
+        let mut rom = vec![0x00; (32 * 1024) << rom_size];
+
+        for (i, c) in title.bytes().enumerate() {
+            rom[TITLE + i] = c;
+        }
+
+        rom[CARTRIDGE_TYPE] = cartridge_type;
+        rom[ROM_SIZE] = rom_size;
+        rom[RAM_SIZE] = ram_size;
+        rom[OLD_LICENSEE] = 0x01;
+
+        fix_checksums(&mut rom);
+        rom
+    }
+
+    pub fn fix_checksums(rom: &mut [u8]) {
+        rom[HEADER_CHECKSUM] = header_checksum_of(rom);
+
+        let global = global_checksum_of(rom);
+        rom[GLOBAL_CHECKSUM] = (global >> 8) as u8;
+        rom[GLOBAL_CHECKSUM + 1] = (global & 0xFF) as u8;
+    }
+
+    #[test]
+    fn test_parse() {
+        let rom = build_rom("TETRIS", 0x03, 0x01, 0x02);
+        let header = CartridgeHeader::parse(&rom).unwrap();
+
+        assert_eq!("TETRIS", header.title);
+        assert_eq!(Controller::Mbc1, header.cartridge_type.controller);
+        assert!(header.cartridge_type.ram);
+        assert!(header.cartridge_type.battery);
+        assert_eq!(64 * 1024, header.rom_size);
+        assert_eq!(4, header.rom_banks());
+        assert_eq!(8 * 1024, header.ram_size);
+        assert_eq!(Licensee::Old(0x01), header.licensee);
+        assert_eq!(CgbSupport::None, header.cgb);
+        assert!(!header.sgb);
+        assert!(header.japanese);
+        assert!(header.warnings.is_empty());
+    }
+
+    #[test]
+    fn test_parse_cgb_and_new_licensee() {
+        let mut rom = build_rom("POKEMON GOLD", 0x10, 0x00, 0x03);
+        rom[CGB_FLAG] = 0x80;
+        rom[SGB_FLAG] = 0x03;
+        rom[OLD_LICENSEE] = USE_NEW_LICENSEE;
+        rom[NEW_LICENSEE] = b'0';
+        rom[NEW_LICENSEE + 1] = b'1';
+        fix_checksums(&mut rom);
+
+        let header = CartridgeHeader::parse(&rom).unwrap();
+
+        assert_eq!("POKEMON GOL", header.title); //CGB title is truncated to 11 chars
+        assert_eq!(CgbSupport::Compatible, header.cgb);
+        assert!(header.sgb);
+        assert_eq!(Licensee::New(String::from("01")), header.licensee);
+        assert!(header.cartridge_type.timer);
+    }
+
+    #[test]
+    fn test_parse_too_small() {
+        let result = CartridgeHeader::parse(&[0x00; 0x100]);
+
+        assert!(matches!(result, Result::Err(CartridgeError::TooSmall(0x100))));
+    }
+
+    #[test]
+    fn test_parse_header_checksum() {
+        let mut rom = build_rom("TEST", 0x00, 0x00, 0x00);
+        rom[HEADER_CHECKSUM] = rom[HEADER_CHECKSUM].wrapping_add(1);
+
+        let result = CartridgeHeader::parse(&rom);
+
+        assert!(matches!(result, Result::Err(CartridgeError::HeaderChecksum { .. })));
+    }
+
+    #[test]
+    fn test_parse_global_checksum() {
+        let mut rom = build_rom("TEST", 0x00, 0x00, 0x00);
+        rom[0x4000] = 0xAB;
+
+        let header = CartridgeHeader::parse(&rom).unwrap(); //hardware never checks it
+
+        assert!(matches!(header.warnings[..], [HeaderWarning::GlobalChecksum { .. }]));
+    }
+
+    #[test]
+    fn test_parse_oversized() {
+        let mut rom = build_rom("TEST", 0x00, 0x00, 0x00);
+        rom.resize(0xC000, 0x00);
+
+        let header = CartridgeHeader::parse(&rom).unwrap();
+
+        assert_eq!(vec![HeaderWarning::Oversized { expected: 0x8000, actual: 0xC000 }], header.warnings);
+    }
+
+    #[test]
+    fn test_parse_rom_size_mismatch() {
+        let mut rom = build_rom("TEST", 0x00, 0x00, 0x00);
+        rom.truncate(0x4000);
+
+        let result = CartridgeHeader::parse(&rom);
+
+        assert!(matches!(
+            result,
+            Result::Err(CartridgeError::RomSizeMismatch { expected: 0x8000, actual: 0x4000 })
+        ));
+    }
+
+    #[test]
+    fn test_parse_invalid_sizes_and_type() {
+        let mut rom = build_rom("TEST", 0x00, 0x00, 0x00);
+        rom[RAM_SIZE] = 0x09;
+        fix_checksums(&mut rom);
+
+        assert!(matches!(CartridgeHeader::parse(&rom), Result::Err(CartridgeError::InvalidRamSize(0x09))));
+
+        rom[RAM_SIZE] = 0x00;
+        rom[CARTRIDGE_TYPE] = 0x04;
+        fix_checksums(&mut rom);
+
+        assert!(matches!(CartridgeHeader::parse(&rom), Result::Err(CartridgeError::UnknownCartridgeType(0x04))));
+
+        rom[CARTRIDGE_TYPE] = 0x00;
+        rom[ROM_SIZE] = 0x10;
+        fix_checksums(&mut rom);
+
+        assert!(matches!(CartridgeHeader::parse(&rom), Result::Err(CartridgeError::InvalidRomSize(0x10))));
+    }
+}
@@ -0,0 +1,426 @@
+use super::rtc::{Rtc, RTC_SECONDS, RTC_DAY_HIGH};
+
+//https://gbdev.io/pandocs/MBCs.html
+pub const ROM_BANK_SIZE: usize = 0x4000;
+pub const RAM_BANK_SIZE: usize = 0x2000;
+pub const EXTERNAL_RAM_START: u16 = 0xA000;
+
+const SWITCHABLE_ROM_START: u16 = 0x4000;
+const RAM_ENABLE: u8 = 0x0A;
+
+//maps CPU addresses onto physical ROM/RAM offsets, writes to 0x0000-0x7FFF are routed here as register writes
+pub trait MemoryBankController {
+    //offset into the ROM for 0x0000-0x7FFF
+    fn rom_offset(&self, addr: u16) -> usize;
+
+    //offset into external RAM for 0xA000-0xBFFF, None when RAM is disabled or not mapped
+    fn ram_offset(&self, addr: u16) -> Option<usize>;
+
+    fn write_register(&mut self, addr: u16, value: u8);
+
+    //selected clock register when 0xA000-0xBFFF is mapped to an RTC rather than RAM
+    fn rtc_register(&self) -> Option<u8> {
+        Option::None
+    }
+
+    //returns true when the write was taken by an RTC register
+    fn write_rtc_register(&mut self, _value: u8) -> bool {
+        false
+    }
+
+    fn rtc_mut(&mut self) -> Option<&mut Rtc> {
+        Option::None
+    }
+}
+
+fn is_ram_enable(value: u8) -> bool {
+    (value & 0x0F) == RAM_ENABLE
+}
+
+fn banked_rom_offset(bank: usize, addr: u16) -> usize {
+    bank * ROM_BANK_SIZE + (addr as usize % ROM_BANK_SIZE)
+}
+
+fn banked_ram_offset(bank: usize, addr: u16) -> usize {
+    bank * RAM_BANK_SIZE + (addr - EXTERNAL_RAM_START) as usize
+}
+
+//ROM only (32 KiB), optionally with a single bank of RAM that is always enabled
+pub struct NoMbc {}
+
+impl MemoryBankController for NoMbc {
+    fn rom_offset(&self, addr: u16) -> usize {
+        addr as usize
+    }
+
+    fn ram_offset(&self, addr: u16) -> Option<usize> {
+        Option::Some(banked_ram_offset(0, addr))
+    }
+
+    fn write_register(&mut self, _addr: u16, _value: u8) {
+        //ROM is read only, writes are ignored
+    }
+}
+
+pub struct Mbc1 {
+    ram_enabled: bool,
+    bank1: u8, //5 bit ROM bank number (0x2000-0x3FFF)
+    bank2: u8, //2 bit RAM bank or upper ROM bank bits (0x4000-0x5FFF)
+    advanced_mode: bool //mode select (0x6000-0x7FFF), when set bank2 also applies to 0x0000-0x3FFF & RAM
+}
+
+impl Mbc1 {
+    pub fn new() -> Mbc1 {
+        Mbc1 {
+            ram_enabled: false,
+            bank1: 1,
+            bank2: 0,
+            advanced_mode: false
+        }
+    }
+}
+
+impl MemoryBankController for Mbc1 {
+    fn rom_offset(&self, addr: u16) -> usize {
+        let bank = if addr < SWITCHABLE_ROM_START {
+            if self.advanced_mode { (self.bank2 as usize) << 5 } else { 0 }
+        } else {
+            ((self.bank2 as usize) << 5) | self.bank1 as usize
+        };
+
+        banked_rom_offset(bank, addr)
+    }
+
+    fn ram_offset(&self, addr: u16) -> Option<usize> {
+        if !self.ram_enabled {
+            return Option::None;
+        }
+
+        let bank = if self.advanced_mode { self.bank2 as usize } else { 0 };
+
+        Option::Some(banked_ram_offset(bank, addr))
+    }
+
+    fn write_register(&mut self, addr: u16, value: u8) {
+        match addr {
+            0x0000..=0x1FFF => self.ram_enabled = is_ram_enable(value),
+            0x2000..=0x3FFF => {
+                self.bank1 = value & 0x1F;
+
+                if self.bank1 == 0 { //bank 0 can't be selected here, the check is done on all 5 bits
+                    self.bank1 = 1;
+                }
+            },
+            0x4000..=0x5FFF => self.bank2 = value & 0x03,
+            0x6000..=0x7FFF => self.advanced_mode = (value & 0x01) == 0x01,
+            _ => {}
+        }
+    }
+}
+
+//MBC2 has 512 x 4 bits of RAM built in, mirrored across 0xA000-0xBFFF
+pub const MBC2_RAM_SIZE: usize = 0x0200;
+
+pub struct Mbc2 {
+    ram_enabled: bool,
+    rom_bank: u8
+}
+
+impl Mbc2 {
+    pub fn new() -> Mbc2 {
+        Mbc2 {
+            ram_enabled: false,
+            rom_bank: 1
+        }
+    }
+}
+
+impl MemoryBankController for Mbc2 {
+    fn rom_offset(&self, addr: u16) -> usize {
+        let bank = if addr < SWITCHABLE_ROM_START { 0 } else { self.rom_bank as usize };
+
+        banked_rom_offset(bank, addr)
+    }
+
+    fn ram_offset(&self, addr: u16) -> Option<usize> {
+        if !self.ram_enabled {
+            return Option::None;
+        }
+
+        Option::Some((addr as usize) % MBC2_RAM_SIZE)
+    }
+
+    fn write_register(&mut self, addr: u16, value: u8) {
+        if addr >= SWITCHABLE_ROM_START {
+            return;
+        }
+
+        //bit 8 of the address selects between RAM enable & ROM bank
+        if (addr & 0x0100) == 0 {
+            self.ram_enabled = is_ram_enable(value);
+        } else {
+            self.rom_bank = value & 0x0F;
+
+            if self.rom_bank == 0 {
+                self.rom_bank = 1;
+            }
+        }
+    }
+}
+
+pub struct Mbc3 {
+    ram_enabled: bool, //also enables access to the RTC registers
+    rom_bank: u8, //7 bits
+    ram_bank: u8, //0x00-0x03 selects RAM, 0x08-0x0C selects an RTC register
+    rtc: Option<Rtc> //only present on MBC3+TIMER carts
+}
+
+impl Mbc3 {
+    pub fn new(rtc: Option<Rtc>) -> Mbc3 {
+        Mbc3 {
+            ram_enabled: false,
+            rom_bank: 1,
+            ram_bank: 0,
+            rtc
+        }
+    }
+
+    fn selected_rtc(&self) -> bool {
+        self.ram_enabled && (RTC_SECONDS..=RTC_DAY_HIGH).contains(&self.ram_bank)
+    }
+}
+
+impl MemoryBankController for Mbc3 {
+    fn rom_offset(&self, addr: u16) -> usize {
+        let bank = if addr < SWITCHABLE_ROM_START { 0 } else { self.rom_bank as usize };
+
+        banked_rom_offset(bank, addr)
+    }
+
+    fn ram_offset(&self, addr: u16) -> Option<usize> {
+        if !self.ram_enabled || self.ram_bank > 0x03 {
+            return Option::None;
+        }
+
+        Option::Some(banked_ram_offset(self.ram_bank as usize, addr))
+    }
+
+    fn write_register(&mut self, addr: u16, value: u8) {
+        match addr {
+            0x0000..=0x1FFF => self.ram_enabled = is_ram_enable(value),
+            0x2000..=0x3FFF => {
+                self.rom_bank = value & 0x7F;
+
+                if self.rom_bank == 0 {
+                    self.rom_bank = 1;
+                }
+            },
+            0x4000..=0x5FFF => self.ram_bank = value & 0x0F,
+            0x6000..=0x7FFF => {
+                if let Option::Some(rtc) = &mut self.rtc {
+                    rtc.write_latch(value);
+                }
+            },
+            _ => {}
+        }
+    }
+
+    fn rtc_register(&self) -> Option<u8> {
+        match &self.rtc {
+            Option::Some(rtc) if self.selected_rtc() => Option::Some(rtc.read(self.ram_bank)),
+            _ => Option::None
+        }
+    }
+
+    fn write_rtc_register(&mut self, value: u8) -> bool {
+        if !self.selected_rtc() {
+            return false;
+        }
+
+        match &mut self.rtc {
+            Option::Some(rtc) => {
+                rtc.write(self.ram_bank, value);
+                true
+            },
+            Option::None => false
+        }
+    }
+
+    fn rtc_mut(&mut self) -> Option<&mut Rtc> {
+        self.rtc.as_mut()
+    }
+}
+
+pub struct Mbc5 {
+    ram_enabled: bool,
+    rom_bank: u16, //9 bits, unlike the other MBCs bank 0 can be mapped to 0x4000-0x7FFF
+    ram_bank: u8
+}
+
+impl Mbc5 {
+    pub fn new() -> Mbc5 {
+        Mbc5 {
+            ram_enabled: false,
+            rom_bank: 1,
+            ram_bank: 0
+        }
+    }
+}
+
+impl MemoryBankController for Mbc5 {
+    fn rom_offset(&self, addr: u16) -> usize {
+        let bank = if addr < SWITCHABLE_ROM_START { 0 } else { self.rom_bank as usize };
+
+        banked_rom_offset(bank, addr)
+    }
+
+    fn ram_offset(&self, addr: u16) -> Option<usize> {
+        if !self.ram_enabled {
+            return Option::None;
+        }
+
+        Option::Some(banked_ram_offset(self.ram_bank as usize, addr))
+    }
+
+    fn write_register(&mut self, addr: u16, value: u8) {
+        match addr {
+            0x0000..=0x1FFF => self.ram_enabled = value == RAM_ENABLE, //MBC5 checks all 8 bits
+            0x2000..=0x2FFF => self.rom_bank = (self.rom_bank & 0x100) | value as u16,
+            0x3000..=0x3FFF => self.rom_bank = (self.rom_bank & 0xFF) | (((value & 0x01) as u16) << 8),
+            0x4000..=0x5FFF => self.ram_bank = value & 0x0F, //NOTE: bit 3 drives the motor on rumble carts
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{cell::Cell, rc::Rc};
+
+    use super::*;
+    use super::super::rtc::tests::FakeClock;
+
+    #[test]
+    fn test_no_mbc() {
+        let mut mbc = NoMbc {};
+
+        mbc.write_register(0x2000, 0x05);
+
+        assert_eq!(0x4001, mbc.rom_offset(0x4001));
+        assert_eq!(Option::Some(0x0010), mbc.ram_offset(0xA010));
+    }
+
+    #[test]
+    fn test_mbc1_rom_banking() {
+        let mut mbc = Mbc1::new();
+
+        assert_eq!(0x4000, mbc.rom_offset(0x4000)); //bank 1 by default
+        assert_eq!(0x0100, mbc.rom_offset(0x0100));
+
+        mbc.write_register(0x2000, 0x00); //0 is translated to 1
+        assert_eq!(0x4000, mbc.rom_offset(0x4000));
+
+        mbc.write_register(0x2000, 0xE3); //only the lower 5 bits are used
+        assert_eq!(3 * ROM_BANK_SIZE + 0x10, mbc.rom_offset(0x4010));
+
+        mbc.write_register(0x4000, 0x01); //upper bits select bank 0x23
+        assert_eq!(0x23 * ROM_BANK_SIZE, mbc.rom_offset(0x4000));
+        assert_eq!(0x0000, mbc.rom_offset(0x0000)); //mode 0, bank2 does not affect 0x0000-0x3FFF
+
+        mbc.write_register(0x6000, 0x01);
+        assert_eq!(0x20 * ROM_BANK_SIZE, mbc.rom_offset(0x0000));
+    }
+
+    #[test]
+    fn test_mbc1_ram_banking() {
+        let mut mbc = Mbc1::new();
+
+        assert_eq!(Option::None, mbc.ram_offset(0xA000));
+
+        mbc.write_register(0x0000, 0x0A);
+        mbc.write_register(0x4000, 0x02);
+
+        assert_eq!(Option::Some(0x0005), mbc.ram_offset(0xA005)); //mode 0 always uses RAM bank 0
+
+        mbc.write_register(0x6000, 0x01);
+
+        assert_eq!(Option::Some(2 * RAM_BANK_SIZE + 5), mbc.ram_offset(0xA005));
+
+        mbc.write_register(0x1000, 0x00);
+
+        assert_eq!(Option::None, mbc.ram_offset(0xA005));
+    }
+
+    #[test]
+    fn test_mbc2() {
+        let mut mbc = Mbc2::new();
+
+        mbc.write_register(0x0100, 0x05); //bit 8 set, ROM bank
+        assert_eq!(5 * ROM_BANK_SIZE, mbc.rom_offset(0x4000));
+
+        mbc.write_register(0x0100, 0x00);
+        assert_eq!(ROM_BANK_SIZE, mbc.rom_offset(0x4000));
+
+        assert_eq!(Option::None, mbc.ram_offset(0xA000));
+
+        mbc.write_register(0x0000, 0x0A); //bit 8 clear, RAM enable
+        assert_eq!(Option::Some(0x0010), mbc.ram_offset(0xA010));
+        assert_eq!(Option::Some(0x0010), mbc.ram_offset(0xA210)); //mirrored every 512 bytes
+    }
+
+    #[test]
+    fn test_mbc3() {
+        let mut mbc = Mbc3::new(Option::None);
+
+        mbc.write_register(0x2000, 0x7F);
+        assert_eq!(0x7F * ROM_BANK_SIZE, mbc.rom_offset(0x4000));
+
+        mbc.write_register(0x0000, 0x0A);
+        mbc.write_register(0x4000, 0x03);
+        assert_eq!(Option::Some(3 * RAM_BANK_SIZE), mbc.ram_offset(0xA000));
+
+        mbc.write_register(0x4000, 0x08); //RTC register, not RAM
+        assert_eq!(Option::None, mbc.ram_offset(0xA000));
+        assert_eq!(Option::None, mbc.rtc_register()); //no timer on this cart
+    }
+
+    #[test]
+    fn test_mbc3_rtc() {
+        let now = Rc::new(Cell::new(0));
+        let mut mbc = Mbc3::new(Option::Some(Rtc::new(Box::new(FakeClock { now: now.clone() }))));
+
+        mbc.write_register(0x4000, 0x09); //minutes
+
+        assert_eq!(Option::None, mbc.rtc_register()); //disabled
+        assert!(!mbc.write_rtc_register(0x10));
+
+        mbc.write_register(0x0000, 0x0A);
+
+        assert!(mbc.write_rtc_register(0x10));
+
+        now.set(60);
+        mbc.write_register(0x6000, 0x00);
+        mbc.write_register(0x6000, 0x01);
+
+        assert_eq!(Option::Some(0x11), mbc.rtc_register());
+    }
+
+    #[test]
+    fn test_mbc5() {
+        let mut mbc = Mbc5::new();
+
+        mbc.write_register(0x2000, 0x00); //bank 0 is allowed
+        assert_eq!(0x0000, mbc.rom_offset(0x4000));
+
+        mbc.write_register(0x2000, 0xFF);
+        mbc.write_register(0x3000, 0x01);
+        assert_eq!(0x1FF * ROM_BANK_SIZE, mbc.rom_offset(0x4000));
+
+        mbc.write_register(0x0000, 0x1A); //MBC5 needs exactly 0x0A
+        assert_eq!(Option::None, mbc.ram_offset(0xA000));
+
+        mbc.write_register(0x0000, 0x0A);
+        mbc.write_register(0x4000, 0x0F);
+        assert_eq!(Option::Some(0x0F * RAM_BANK_SIZE), mbc.ram_offset(0xA000));
+    }
+}
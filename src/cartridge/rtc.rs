@@ -0,0 +1,304 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+//MBC3 real time clock - https://gbdev.io/pandocs/MBC3.html#the-clock-counter-registers
+pub const RTC_SECONDS: u8 = 0x08;
+pub const RTC_DAY_HIGH: u8 = 0x0C;
+
+//BGB/VBA append 5 current registers, 5 latched registers (u32 LE each) and a u64 LE unix timestamp to the .sav
+pub const RTC_SAVE_SIZE: usize = 48;
+const RTC_SAVE_SIZE_32BIT: usize = 44; //older VBA builds store a 32 bit timestamp
+
+const DAY_HIGH_MSB: u8 = 0x01; //bit 8 of the day counter
+const DAY_HIGH_HALT: u8 = 0x40;
+const DAY_HIGH_CARRY: u8 = 0x80;
+const DAY_MAX: u64 = 0x200;
+
+//source of wall clock time, injectable so tests can fake it
+pub trait Clock {
+    fn now(&self) -> u64; //seconds since the unix epoch
+}
+
+pub struct SystemClock {}
+
+impl Clock for SystemClock {
+    fn now(&self) -> u64 {
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|duration| duration.as_secs())
+            .unwrap_or(0)
+    }
+}
+
+pub struct Rtc {
+    clock: Box<dyn Clock>,
+    seconds: u8,
+    minutes: u8,
+    hours: u8,
+    days: u16, //9 bits
+    halted: bool,
+    day_carry: bool,
+    latched: [u8; 5], //what the CPU reads, only refreshed by latching
+    latch_armed: bool, //latching happens on a 0x00 then 0x01 write
+    last_sync: u64, //host timestamp the registers were last brought up to date
+    changed: bool //registers advanced or latched since the last save
+}
+
+impl Rtc {
+    pub fn new(clock: Box<dyn Clock>) -> Rtc {
+        let now = clock.now();
+
+        Rtc {
+            clock,
+            seconds: 0,
+            minutes: 0,
+            hours: 0,
+            days: 0,
+            halted: false,
+            day_carry: false,
+            latched: [0x00; 5],
+            latch_armed: false,
+            last_sync: now,
+            changed: false
+        }
+    }
+
+    //value of a latched register, register is the 0x08-0x0C bank number
+    pub fn read(&self, register: u8) -> u8 {
+        self.latched[(register - RTC_SECONDS) as usize]
+    }
+
+    pub fn write(&mut self, register: u8, value: u8) {
+        self.sync();
+
+        match register {
+            0x08 => self.seconds = value & 0x3F,
+            0x09 => self.minutes = value & 0x3F,
+            0x0A => self.hours = value & 0x1F,
+            0x0B => self.days = (self.days & 0x100) | value as u16,
+            0x0C => {
+                self.days = (self.days & 0xFF) | (((value & DAY_HIGH_MSB) as u16) << 8);
+                self.halted = (value & DAY_HIGH_HALT) != 0;
+                self.day_carry = (value & DAY_HIGH_CARRY) != 0;
+            },
+            _ => {}
+        }
+    }
+
+    //0x6000-0x7FFF write, 0x00 followed by 0x01 copies the live registers into the latched ones
+    pub fn write_latch(&mut self, value: u8) {
+        if self.latch_armed && value == 0x01 {
+            self.sync();
+
+            let registers = self.registers();
+            self.changed |= registers != self.latched;
+            self.latched = registers;
+        }
+
+        self.latch_armed = value == 0x00;
+    }
+
+    pub fn save_bytes(&mut self) -> [u8; RTC_SAVE_SIZE] {
+        self.sync();
+
+        let mut bytes = [0x00; RTC_SAVE_SIZE];
+        let registers = self.registers();
+
+        for i in 0..5 {
+            bytes[i * 4] = registers[i];
+            bytes[20 + i * 4] = self.latched[i];
+        }
+
+        bytes[40..48].copy_from_slice(&self.last_sync.to_le_bytes());
+        self.changed = false;
+
+        bytes
+    }
+
+    //whether anything needs saving, cleared by save_bytes
+    pub fn is_changed(&self) -> bool {
+        self.changed
+    }
+
+    //restore from a save trailer, time that passed since it was written is caught up on the next access
+    pub fn load_bytes(&mut self, bytes: &[u8]) -> bool {
+        let timestamp = match bytes.len() {
+            RTC_SAVE_SIZE => u64::from_le_bytes(bytes[40..48].try_into().unwrap()),
+            RTC_SAVE_SIZE_32BIT => u32::from_le_bytes(bytes[40..44].try_into().unwrap()) as u64,
+            _ => return false
+        };
+
+        for i in 0..5 {
+            self.latched[i] = bytes[20 + i * 4];
+        }
+
+        let day_high = bytes[16];
+
+        self.seconds = bytes[0] & 0x3F;
+        self.minutes = bytes[4] & 0x3F;
+        self.hours = bytes[8] & 0x1F;
+        self.days = bytes[12] as u16 | (((day_high & DAY_HIGH_MSB) as u16) << 8);
+        self.halted = (day_high & DAY_HIGH_HALT) != 0;
+        self.day_carry = (day_high & DAY_HIGH_CARRY) != 0;
+        self.last_sync = timestamp;
+
+        true
+    }
+
+    fn registers(&self) -> [u8; 5] {
+        let mut day_high = ((self.days >> 8) as u8) & DAY_HIGH_MSB;
+
+        if self.halted {
+            day_high |= DAY_HIGH_HALT;
+        }
+
+        if self.day_carry {
+            day_high |= DAY_HIGH_CARRY;
+        }
+
+        [
+            self.seconds,
+            self.minutes,
+            self.hours,
+            (self.days & 0xFF) as u8,
+            day_high
+        ]
+    }
+
+    //advance the registers by the host time elapsed since the last sync
+    fn sync(&mut self) {
+        let now = self.clock.now();
+        let elapsed = now.saturating_sub(self.last_sync);
+        self.last_sync = now;
+
+        if self.halted || elapsed == 0 {
+            return;
+        }
+
+        self.changed = true;
+
+        let seconds = self.seconds as u64 + elapsed;
+        let minutes = self.minutes as u64 + seconds / 60;
+        let hours = self.hours as u64 + minutes / 60;
+        let days = self.days as u64 + hours / 24;
+
+        self.seconds = (seconds % 60) as u8;
+        self.minutes = (minutes % 60) as u8;
+        self.hours = (hours % 24) as u8;
+        self.days = (days % DAY_MAX) as u16;
+
+        if days >= DAY_MAX {
+            self.day_carry = true; //sticky until the game clears it
+        }
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use std::{cell::Cell, rc::Rc};
+
+    use super::*;
+
+    pub struct FakeClock {
+        pub now: Rc<Cell<u64>>
+    }
+
+    impl Clock for FakeClock {
+        fn now(&self) -> u64 {
+            self.now.get()
+        }
+    }
+
+    fn prepare_rtc() -> (Rtc, Rc<Cell<u64>>) {
+        let now = Rc::new(Cell::new(1_000_000));
+        let rtc = Rtc::new(Box::new(FakeClock { now: now.clone() }));
+
+        (rtc, now)
+    }
+
+    fn latch(rtc: &mut Rtc) {
+        rtc.write_latch(0x00);
+        rtc.write_latch(0x01);
+    }
+
+    #[test]
+    fn test_latch() {
+        let (mut rtc, now) = prepare_rtc();
+
+        now.set(now.get() + 3_661); //1 hour, 1 minute, 1 second
+
+        assert_eq!(0, rtc.read(0x08)); //not latched yet
+
+        latch(&mut rtc);
+
+        assert_eq!(1, rtc.read(0x08));
+        assert_eq!(1, rtc.read(0x09));
+        assert_eq!(1, rtc.read(0x0A));
+
+        now.set(now.get() + 1);
+        rtc.write_latch(0x01); //not armed by a 0x00 write
+
+        assert_eq!(1, rtc.read(0x08));
+    }
+
+    #[test]
+    fn test_days_and_carry() {
+        let (mut rtc, now) = prepare_rtc();
+
+        now.set(now.get() + 300 * 86_400);
+        latch(&mut rtc);
+
+        assert_eq!((300 & 0xFF) as u8, rtc.read(0x0B));
+        assert_eq!(0x01, rtc.read(0x0C));
+
+        now.set(now.get() + 212 * 86_400); //512 days overflows the counter
+        latch(&mut rtc);
+
+        assert_eq!(0x00, rtc.read(0x0B));
+        assert_eq!(DAY_HIGH_CARRY, rtc.read(0x0C));
+    }
+
+    #[test]
+    fn test_halt() {
+        let (mut rtc, now) = prepare_rtc();
+
+        rtc.write(0x0C, DAY_HIGH_HALT);
+        rtc.write(0x08, 30);
+        now.set(now.get() + 100);
+        latch(&mut rtc);
+
+        assert_eq!(30, rtc.read(0x08));
+        assert_eq!(DAY_HIGH_HALT, rtc.read(0x0C));
+
+        rtc.write(0x0C, 0x00);
+        now.set(now.get() + 5);
+        latch(&mut rtc);
+
+        assert_eq!(35, rtc.read(0x08));
+    }
+
+    #[test]
+    fn test_save_and_load() {
+        let (mut rtc, now) = prepare_rtc();
+
+        rtc.write(0x0A, 5);
+        latch(&mut rtc);
+
+        let bytes = rtc.save_bytes();
+
+        assert_eq!(5, bytes[8]); //current hours
+        assert_eq!(5, bytes[28]); //latched hours
+        assert_eq!(now.get(), u64::from_le_bytes(bytes[40..48].try_into().unwrap()));
+
+        let (mut loaded, later) = prepare_rtc();
+        later.set(now.get() + 120); //emulator was closed for 2 minutes
+
+        assert!(loaded.load_bytes(&bytes));
+        assert_eq!(5, loaded.read(0x0A));
+
+        latch(&mut loaded);
+
+        assert_eq!(2, loaded.read(0x09));
+        assert_eq!(5, loaded.read(0x0A));
+        assert!(!loaded.load_bytes(&bytes[..40]));
+    }
+}
@@ -0,0 +1,56 @@
+use std::{fs, io, path::{Path, PathBuf}};
+
+//game.gb -> game.sav, the naming BGB, SameBoy, mGBA etc. use so saves are interchangeable
+pub fn save_path(rom_path: &Path) -> PathBuf {
+    rom_path.with_extension("sav")
+}
+
+//None when there is no save yet
+pub fn read_save(path: &Path) -> io::Result<Option<Vec<u8>>> {
+    match fs::read(path) {
+        Result::Ok(data) => Result::Ok(Option::Some(data)),
+        Result::Err(error) if error.kind() == io::ErrorKind::NotFound => Result::Ok(Option::None),
+        Result::Err(error) => Result::Err(error)
+    }
+}
+
+//write to a temporary file first so a crash mid-write can't corrupt the existing save
+pub fn write_save(path: &Path, data: &[u8]) -> io::Result<()> {
+    let temp_path = path.with_extension("sav.tmp");
+
+    fs::write(&temp_path, data)?;
+    fs::rename(&temp_path, path)
+}
+
+//move a save that couldn't be loaded out of the way, returns where it went
+pub fn back_up(path: &Path) -> io::Result<PathBuf> {
+    let backup_path = path.with_extension("sav.bak");
+
+    fs::rename(path, &backup_path)?;
+    Result::Ok(backup_path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+
+    #[test]
+    fn test_save_path() {
+        assert_eq!(PathBuf::from("roms/tetris.sav"), save_path(Path::new("roms/tetris.gb")));
+        assert_eq!(PathBuf::from("pokemon.sav"), save_path(Path::new("pokemon")));
+    }
+
+    #[test]
+    fn test_read_write_save() {
+        let path = env::temp_dir().join(format!("game_boy_emulator_save_{}.sav", std::process::id()));
+
+        assert!(read_save(&path).unwrap().is_none());
+
+        write_save(&path, &[0x01, 0x02, 0x03]).unwrap();
+
+        assert_eq!(Option::Some(vec![0x01, 0x02, 0x03]), read_save(&path).unwrap());
+
+        fs::remove_file(&path).unwrap();
+    }
+}
@@ -1,12 +1,20 @@
-use std::{ops::Range, time::Duration};
+use std::{fmt, ops::Range};
+
+use crate::{apu::APU, cartridge::Cartridge, joypad::Button, ppu::PPU};
 
 //Sharp SM83 CPU
-use registers::{Registers, PC_START};
-use flags::Flags;
-use instructions::{StateChange, get_byte_length};
-use memory::Memory;
+use registers::{Registers, RegisterChange, PC_START, to8_bit};
+use flags::{Flags, FlagChange};
+use instructions::{StateChange, get_byte_length, is_illegal};
+use interrupts::{Interrupt, INTERRUPT_FLAG, INTERRUPT_ENABLE, INTERRUPT_T_STATES};
+use memory::{Bus, Memory, MemoryChange, MemoryEdit};
+use timer::DIV;
 
+pub mod interrupts;
 mod memory;
+mod timer;
+mod dma;
+mod serial;
 mod instructions;
 mod registers;
 mod flags;
@@ -16,8 +24,10 @@ mod util;
 #[path = "./cpu_test.rs"]
 mod cpu_test;
 
-const CPU_SPEED_MHZ: f64 = 1e-6 * 2.0; //TODO: currently set to 2hz for testing should be 4.194304Mhz
 const T_TO_M_CYCLE: u8 = 4; //Timing states divisible by 4, 4 t_states = 1 machine cycle
+const KEY1: u16 = 0xFF4D; //CGB speed switch, bit 0 arms it for the next STOP
+const KEY1_ARMED: u8 = 0x01;
+const CGB_MODE: bool = false; //only the DMG is emulated, KEY1 reads back as open bus there
 
 #[derive(Clone, Copy)]
 pub enum ImeStatus {
@@ -26,11 +36,65 @@ pub enum ImeStatus {
     SCHEDULED //When EI is called to enable IME, it is only enabled after the next instruction, schedule first then set ime after next execution
 }
 
+#[derive(Clone, Copy)]
+pub enum PowerStatus {
+    RUNNING,
+    HALTED, //https://gbdev.io/pandocs/halt.html ~ no fetching until IE & IF != 0
+    STOPPED, //https://gbdev.io/pandocs/Reducing_Power_Consumption.html#using-the-stop-instruction ~ LCD blank until joypad input
+    LOCKED //illegal opcode, hardware hangs until power off
+}
+
+//what step does when it fetches one of the unused opcodes
+#[derive(Clone, Copy)]
+pub enum IllegalOpcodePolicy {
+    HANG, //lock up as hardware does
+    ERROR, //return CpuError::IllegalOpcode from step
+    BREAK //return CpuError::Breakpoint with PC past the opcode so execution can be resumed
+}
+
+impl IllegalOpcodePolicy {
+    pub fn parse(value: &str) -> Option<IllegalOpcodePolicy> {
+        match value {
+            "hang" => Option::Some(IllegalOpcodePolicy::HANG),
+            "error" => Option::Some(IllegalOpcodePolicy::ERROR),
+            "break" => Option::Some(IllegalOpcodePolicy::BREAK),
+            _ => Option::None
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub enum CpuError {
+    IllegalOpcode { pc: u16, opcode: u8 },
+    Breakpoint { pc: u16, opcode: u8 }
+}
+
+impl fmt::Display for CpuError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            CpuError::IllegalOpcode { pc, opcode } => write!(f, "illegal opcode {:#04x} at {:#06x}", opcode, pc),
+            CpuError::Breakpoint { pc, opcode } => write!(f, "break on illegal opcode {:#04x} at {:#06x}", opcode, pc)
+        }
+    }
+}
+
+impl std::error::Error for CpuError {}
+
 pub struct CPU {
     memory: Memory,
     registers: Registers,
     flags: Flags,
     ime: ImeStatus, //interupt master enable flag - https://gbdev.io/pandocs/Interrupts.html
+    power: PowerStatus,
+    halt_bug: bool, //next opcode byte is read twice as PC fails to increment
+    illegal_opcode_policy: IllegalOpcodePolicy,
+    cycles: u64 //t_states since power on
+}
+
+impl Default for CPU {
+    fn default() -> Self {
+        CPU::new()
+    }
 }
 
 impl CPU {
@@ -44,7 +108,11 @@ impl CPU {
                 half_carry: false,
                 carry: false
             },
-            ime: ImeStatus::UNSET
+            ime: ImeStatus::UNSET,
+            power: PowerStatus::RUNNING,
+            halt_bug: false,
+            illegal_opcode_policy: IllegalOpcodePolicy::ERROR,
+            cycles: 0
         }
     }
 
@@ -52,6 +120,8 @@ impl CPU {
         println!("\n===CPU STATUS===");
         println!("PC: {:#02x}", self.registers.program_counter);
         println!("SP: {:#02x}", self.registers.stack_pointer);
+        println!("CYCLES: {}", self.cycles);
+        println!("BOOT ROM: {}", if self.is_boot_rom_mapped() { "mapped" } else { "unmapped" });
         println!(
             "IME: {}",
             match self.ime {
@@ -60,6 +130,15 @@ impl CPU {
                 ImeStatus::SCHEDULED => "scheduled"
             }
         );
+        println!(
+            "POWER: {}",
+            match self.power {
+                PowerStatus::RUNNING => "running",
+                PowerStatus::HALTED => "halted",
+                PowerStatus::STOPPED => "stopped",
+                PowerStatus::LOCKED => "locked"
+            }
+        );
         println!(
             "==REG==\nA: {:#02x}, B: {:#02x}, C: {:#02x}, D: {:#02x}, E: {:#02x}, F: {:#02x}, H: {:#02x}, L: {:#02x}",
             self.registers.a,
@@ -78,18 +157,23 @@ impl CPU {
             self.flags.half_carry,
             self.flags.carry
         );
+        println!(
+            "==INTERRUPTS==\nIE: {:#02x}, IF: {:#02x}",
+            self.memory.read(INTERRUPT_ENABLE),
+            self.memory.read(INTERRUPT_FLAG)
+        );
         println!(
             "==MEMORY==\n0xC001: {:#02x}\n0xC002: {:#02x}",
-            self.memory[0xC001],
-            self.memory[0xC002]
+            self.memory.read(0xC001),
+            self.memory.read(0xC002)
         );
         println!(
             "==STACK==\n0x0004: {:#02x}\n0x0003: {:#02x}\n0x0002: {:#02x}\n0x0001: {:#02x}\n0x0000: {:#02x}",
-            self.memory[0x0004],
-            self.memory[0x0003],
-            self.memory[0x0002],
-            self.memory[0x0001],
-            self.memory[0x0000]
+            self.memory.read(0x0004),
+            self.memory.read(0x0003),
+            self.memory.read(0x0002),
+            self.memory.read(0x0001),
+            self.memory.read(0x0000)
         );
     }
 
@@ -102,11 +186,8 @@ impl CPU {
         let pc = self.registers.program_counter;
 
         if let Option::Some(args) = args {
-            let mut pc = pc + 1;
-
-            for i in &args {
-                self.memory[pc as usize] = *i;
-                pc += 1;
+            for (addr, i) in (pc + 1..).zip(args.iter()) {
+                self.memory.write(addr, *i);
             }
         }
 
@@ -119,13 +200,46 @@ impl CPU {
         self.update(&change)
     }
 
+    //overlay the boot ROM at 0x0000, it is unmapped by the program writing to 0xFF50
+    pub fn map_boot_rom(&mut self, rom: Vec<u8>) {
+        self.memory.map_boot_rom(rom);
+    }
+
+    pub fn is_boot_rom_mapped(&self) -> bool {
+        self.memory.is_boot_rom_mapped()
+    }
+
+    pub fn insert_cartridge(&mut self, cartridge: Cartridge) {
+        self.memory.insert_cartridge(cartridge);
+    }
+
+    pub fn cartridge(&self) -> Option<&Cartridge> {
+        self.memory.cartridge()
+    }
+
+    pub fn cartridge_mut(&mut self) -> Option<&mut Cartridge> {
+        self.memory.cartridge_mut()
+    }
+
+    pub fn ppu(&self) -> &PPU {
+        self.memory.ppu()
+    }
+
+    pub fn ppu_mut(&mut self) -> &mut PPU {
+        self.memory.ppu_mut()
+    }
+
+    pub fn apu_mut(&mut self) -> &mut APU {
+        self.memory.apu_mut()
+    }
+
     //map values by bulk to memory, mem_range specifies where in memory
     pub fn memory_map(&mut self, mem_range: Range<usize>, values: Vec<u8>) {
         let mut idx = 0;
         let len = values.len();
 
         for addr in mem_range {
-            self.memory[addr] = values[idx];
+            self.memory.write(addr as u16, values[idx]);
             idx += 1;
 
             if idx >= len {
@@ -134,15 +248,78 @@ impl CPU {
         }
     }
 
-    //perform a fetch-execute cycle and return the processing time based on t_states
-    pub fn step(&mut self) -> Duration {
+    //peripherals raise interrupts by setting their bit in IF
+    pub fn request_interrupt(&mut self, interrupt: Interrupt) {
+        self.memory.request_interrupt(interrupt);
+    }
+
+    pub fn set_illegal_opcode_policy(&mut self, policy: IllegalOpcodePolicy) {
+        self.illegal_opcode_policy = policy;
+    }
+
+    pub fn set_button(&mut self, button: Button, pressed: bool) {
+        self.memory.set_button(button, pressed);
+    }
+
+    //when disabled the CPU can reach VRAM & OAM whatever the PPU is doing
+    pub fn set_ppu_access_blocking(&mut self, enabled: bool) {
+        self.memory.set_access_blocking(enabled);
+    }
+
+    pub fn cycles(&self) -> u64 {
+        self.cycles
+    }
+
+    pub fn is_stopped(&self) -> bool {
+        matches!(self.power, PowerStatus::STOPPED)
+    }
+
+    //perform a fetch-execute cycle and return the t_states it took, the rest of the system is ticked by the same amount
+    pub fn step(&mut self) -> Result<u8, CpuError> {
+        let t_states = self.cycle()?;
+        self.cycles += t_states as u64;
+
+        if !self.is_stopped() { //the divider is held and the LCD blank while stopped
+            self.memory.tick(t_states);
+        }
+
+        Result::Ok(t_states)
+    }
+
+    //service an interrupt or fetch-execute the next instruction, returning the t_states taken
+    fn cycle(&mut self) -> Result<u8, CpuError> {
+        if let PowerStatus::LOCKED = self.power {
+            return Result::Ok(T_TO_M_CYCLE);
+        }
+
+        if let PowerStatus::HALTED = self.power {
+            if self.memory.pending_interrupts() == 0 {
+                return Result::Ok(T_TO_M_CYCLE); //idle, nothing is fetched
+            }
+
+            self.power = PowerStatus::RUNNING; //wake, IME decides if the interrupt is serviced
+        }
+
+        if let PowerStatus::STOPPED = self.power {
+            if !self.memory.take_key_press() {
+                return Result::Ok(T_TO_M_CYCLE);
+            }
+
+            self.power = PowerStatus::RUNNING; //any joypad input resumes regardless of IE
+        }
+
+        if let Option::Some(interrupt) = self.pending_interrupt() {
+            let change = self.service_interrupt(interrupt);
+            self.update(&change);
+
+            return Result::Ok(change.t_states);
+        }
+
         let pc = self.registers.program_counter;
-        let op_code = self.memory[pc as usize];
+        let op_code = self.memory.read(pc);
 
-        if op_code == 0xCB { //TEMP
-            println!("Executing PREFIX {:#02x}", self.memory[(pc + 1) as usize]);
-        } else {
-            println!("Executing {:#02x}", op_code);
+        if is_illegal(op_code) {
+            return self.illegal_opcode(pc, op_code);
         }
 
         /*
@@ -155,6 +332,13 @@ impl CPU {
         }
         */
 
+        if self.halt_bug { //the byte after HALT is read again, so operands start at the opcode itself
+            self.halt_bug = false;
+            self.registers.program_counter = pc.wrapping_sub(1);
+        }
+
+        let pc = self.registers.program_counter;
+
         let change = instructions::execute(
             self,
             op_code
@@ -164,10 +348,68 @@ impl CPU {
         self.update(&change);
 
         if pc == PC_START { //emulate initial fetch that is not overlapped (2 cycles min)
-            return delay(change.t_states + T_TO_M_CYCLE);
+            return Result::Ok(change.t_states + T_TO_M_CYCLE);
+        }
+
+        Result::Ok(change.t_states)
+    }
+
+    //HANG locks the CPU on the opcode as the hardware would
+    fn illegal_opcode(&mut self, pc: u16, opcode: u8) -> Result<u8, CpuError> {
+        match self.illegal_opcode_policy {
+            IllegalOpcodePolicy::HANG => {
+                self.power = PowerStatus::LOCKED;
+                Result::Ok(T_TO_M_CYCLE)
+            },
+            IllegalOpcodePolicy::ERROR => Result::Err(CpuError::IllegalOpcode { pc, opcode }),
+            IllegalOpcodePolicy::BREAK => {
+                self.registers.program_counter = pc.wrapping_add(1);
+                Result::Err(CpuError::Breakpoint { pc, opcode })
+            }
+        }
+    }
+
+    //interrupts are only serviced while IME is set, highest priority first
+    fn pending_interrupt(&self) -> Option<Interrupt> {
+        if let ImeStatus::SET = self.ime {
+            return Interrupt::from_pending(self.memory.pending_interrupts());
         }
 
-        delay(change.t_states)
+        Option::None
+    }
+
+    //ISR: acknowledge the interrupt in IF, disable IME, push PC and jump to the vector
+    fn service_interrupt(&self, interrupt: Interrupt) -> StateChange {
+        let sp = self.registers.stack_pointer;
+        let (lsb, msb) = to8_bit(self.registers.program_counter);
+
+        StateChange {
+            t_states: INTERRUPT_T_STATES,
+            ime: Option::Some(ImeStatus::UNSET),
+            power: Option::None,
+            flags: FlagChange::default(),
+            register: RegisterChange {
+                pc: Option::Some(interrupt.vector()),
+                sp: Option::Some(sp.wrapping_sub(2)),
+                ..RegisterChange::default()
+            },
+            memory: MemoryChange {
+                changes: vec![
+                    MemoryEdit {
+                        key: sp.wrapping_sub(1),
+                        value: msb
+                    },
+                    MemoryEdit {
+                        key: sp.wrapping_sub(2),
+                        value: lsb
+                    },
+                    MemoryEdit {
+                        key: INTERRUPT_FLAG,
+                        value: self.memory.read(INTERRUPT_FLAG) & !interrupt.bit()
+                    }
+                ]
+            }
+        }
     }
 
     fn update(&mut self, change: &StateChange) {
@@ -182,13 +424,40 @@ impl CPU {
         self.registers.update(&change.register);
         self.flags.update(&change.flags);
         self.memory.update(&change.memory);
+
+        match change.power {
+            Some(PowerStatus::HALTED) => self.halt(),
+            Some(PowerStatus::STOPPED) => self.stop(),
+            Some(PowerStatus::LOCKED) => self.power = PowerStatus::LOCKED,
+            _ => ()
+        }
     }
-}
 
-fn delay(t_states: u8) -> Duration {
-    const SPEED_HZ: f64 = CPU_SPEED_MHZ * 1e+6;
-    const M_CYCLE_TO_SECOND: f64 = 1.0 / SPEED_HZ; //1 hz = 1 machine cycle per second
+    //with IME unset and an interrupt already pending HALT exits immediately and triggers the HALT bug
+    fn halt(&mut self) {
+        if let ImeStatus::UNSET = self.ime {
+            if self.memory.pending_interrupts() != 0 {
+                self.halt_bug = true;
+                return;
+            }
+        }
 
-    let m_cycles = (t_states / T_TO_M_CYCLE) as f64;
-    Duration::from_secs_f64(m_cycles * M_CYCLE_TO_SECOND)
+        self.power = PowerStatus::HALTED;
+    }
+
+    //on CGB, STOP performs the speed switch instead when it has been armed via KEY1
+    fn stop(&mut self) {
+        if self.is_speed_switch_armed() {
+            return; //double speed would be toggled here once CGB is supported
+        }
+
+        self.memory.take_key_press(); //only a press after STOP wakes it, IF is left alone
+        self.memory.write(DIV, 0x00); //DIV is reset and held while stopped
+        self.memory.ppu_mut().blank();
+        self.power = PowerStatus::STOPPED;
+    }
+
+    fn is_speed_switch_armed(&self) -> bool {
+        CGB_MODE && self.memory.read(KEY1) & KEY1_ARMED != 0
+    }
 }
\ No newline at end of file
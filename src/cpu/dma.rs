@@ -0,0 +1,90 @@
+//https://gbdev.io/pandocs/OAM_DMA_Transfer.html
+pub const DMA: u16 = 0xFF46; //writing XX copies XX00-XX9F into OAM
+
+const LENGTH: u16 = 0xA0; //all of OAM
+const T_CYCLES_PER_BYTE: u16 = 4; //one byte per m-cycle, 640 t-cycles in total
+
+pub struct Dma {
+    register: u8, //last value written, the source page
+    progress: Option<u16>, //t-cycles into the running transfer
+    value: u8 //last byte transferred, what the CPU reads from the busy bus
+}
+
+impl Default for Dma {
+    fn default() -> Self {
+        Dma::new()
+    }
+}
+
+impl Dma {
+    pub fn new() -> Dma {
+        Dma {
+            register: 0,
+            progress: Option::None,
+            value: 0
+        }
+    }
+
+    pub fn read(&self) -> u8 {
+        self.register
+    }
+
+    //a write while a transfer is running restarts it from the new source
+    pub fn write(&mut self, value: u8) {
+        self.register = value;
+        self.progress = Option::Some(0);
+    }
+
+    pub fn is_active(&self) -> bool {
+        self.progress.is_some()
+    }
+
+    pub fn source(&self) -> u16 {
+        (self.register as u16) << 8
+    }
+
+    pub fn value(&self) -> u8 {
+        self.value
+    }
+
+    pub fn set_value(&mut self, value: u8) {
+        self.value = value;
+    }
+
+    //advance one t-cycle, returning the offset of the byte to copy when one is due
+    pub fn tick(&mut self) -> Option<u16> {
+        let progress = self.progress? + 1;
+
+        self.progress = if progress == LENGTH * T_CYCLES_PER_BYTE {
+            Option::None
+        } else {
+            Option::Some(progress)
+        };
+
+        if progress % T_CYCLES_PER_BYTE == 0 {
+            Option::Some(progress / T_CYCLES_PER_BYTE - 1)
+        } else {
+            Option::None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_transfer_timing() {
+        let mut dma = Dma::new();
+        dma.write(0xC1);
+
+        assert_eq!(0xC100, dma.source());
+
+        let offsets: Vec<u16> = (0..640).filter_map(|_| dma.tick()).collect();
+
+        assert_eq!((0..0xA0).collect::<Vec<u16>>(), offsets);
+        assert!(!dma.is_active());
+        assert_eq!(Option::None, dma.tick());
+        assert_eq!(0xC1, dma.read());
+    }
+}
@@ -126,8 +126,8 @@ mod tests {
             ..FlagChange::default()
         });
 
-        assert_eq!(flags.zero, true);
-        assert_eq!(flags.subtract, false);
+        assert!(flags.zero);
+        assert!(!flags.subtract);
     }
 
     #[test]
@@ -156,8 +156,8 @@ mod tests {
 
         flags.update(&FlagChange::reset());
 
-        assert_eq!(flags.zero, false);
-        assert_eq!(flags.subtract, false);
+        assert!(!flags.zero);
+        assert!(!flags.subtract);
     }
 
     #[test]
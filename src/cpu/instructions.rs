@@ -3,6 +3,7 @@ use self::prefixed::prefixed_execute;
 use super::{
     CPU,
     ImeStatus,
+    PowerStatus,
     registers::{to8_bit, to16_bit, RegisterChange},
     flags::{
         FlagChange,
@@ -10,7 +11,7 @@ use super::{
         is_carry_add_16, is_half_carry_add_16,
         is_carry_add, is_carry_subtract
     },
-    memory::{MemoryChange, MemoryEdit},
+    memory::{Bus, MemoryChange, MemoryEdit},
     util::{
         add16_bit, sub16_bit,
         add8_bit, sub8_bit
@@ -22,6 +23,7 @@ mod prefixed;
 pub struct StateChange {
     pub t_states: u8,
     pub ime: Option<ImeStatus>,
+    pub power: Option<PowerStatus>,
     pub flags: FlagChange,
     pub register: RegisterChange,
     pub memory: MemoryChange
@@ -77,6 +79,11 @@ pub fn get_byte_length(op_code: u8) -> u8 {
     }
 }
 
+//unused opcodes, hardware locks up when fetching one of these
+pub fn is_illegal(op_code: u8) -> bool {
+    matches!(op_code, 0xD3 | 0xDB | 0xDD | 0xE3 | 0xE4 | 0xEB..=0xED | 0xF4 | 0xFC..=0xFD)
+}
+
 //How to interpret instruction comments:
 //INC A = Increment the value in register A
 //INC (A) or INC [A] = Increment the value at the memory address that the A register contains.
@@ -87,8 +94,8 @@ pub fn execute(cpu: &CPU, op_code: u8) -> StateChange {
             let pc = cpu.registers.program_counter;
 
             RegisterChange {
-                b: Some(cpu.memory[add16_bit(pc, 1) as usize]),
-                c: Some(cpu.memory[add16_bit(pc, 2) as usize]),
+                b: Some(cpu.memory.read(add16_bit(pc, 1))),
+                c: Some(cpu.memory.read(add16_bit(pc, 2))),
                 ..RegisterChange::default()
             }
         }),
@@ -138,7 +145,7 @@ pub fn execute(cpu: &CPU, op_code: u8) -> StateChange {
             b: {
                 let pc = cpu.registers.program_counter;
 
-                Some(cpu.memory[add16_bit(pc, 1) as usize])
+                Some(cpu.memory.read(add16_bit(pc, 1)))
             },
             ..RegisterChange::default()
         }),
@@ -156,13 +163,14 @@ pub fn execute(cpu: &CPU, op_code: u8) -> StateChange {
         0x08 => StateChange { //LD [n16], SP (load stack pointer into memory)
             t_states: 20,
             ime: Option::None,
+            power: Option::None,
             flags: FlagChange::default(),
             register: RegisterChange::default(),
             memory: {
                 let pc = cpu.registers.program_counter;
                 let addr = to16_bit(
-                    cpu.memory[add16_bit(pc, 1) as usize],
-                    cpu.memory[add16_bit(pc, 2) as usize]
+                    cpu.memory.read(add16_bit(pc, 1)),
+                    cpu.memory.read(add16_bit(pc, 2))
                 );
 
                 let (lsb, msb) = to8_bit(cpu.registers.stack_pointer);
@@ -187,7 +195,7 @@ pub fn execute(cpu: &CPU, op_code: u8) -> StateChange {
         ),
         0x0A => ld_from_absolute( //LD A, [BC]
             RegisterChange {
-                a: Some(cpu.memory[cpu.registers.bc() as usize]),
+                a: Some(cpu.memory.read(cpu.registers.bc())),
                 ..RegisterChange::default()
             }
         ),
@@ -229,7 +237,7 @@ pub fn execute(cpu: &CPU, op_code: u8) -> StateChange {
             c: {
                 let pc = cpu.registers.program_counter;
 
-                Some(cpu.memory[add16_bit(pc, 1) as usize])
+                Some(cpu.memory.read(add16_bit(pc, 1)))
             },
             ..RegisterChange::default()
         }),
@@ -244,15 +252,16 @@ pub fn execute(cpu: &CPU, op_code: u8) -> StateChange {
                 (a & 0x80) == 0x80 //check leftmost bit
             )
         },
-        0x10 => StateChange { //STOP - TODO: something about switching between power modes on GBC cpu
+        0x10 => StateChange { //STOP - CPU also hooks the CGB speed switch on update
+            power: Option::Some(PowerStatus::STOPPED),
             ..nop()
         },
         0x11 => ld16_immediate({ //LD DE, u16
             let pc = cpu.registers.program_counter;
 
             RegisterChange {
-                d: Some(cpu.memory[add16_bit(pc, 1) as usize]),
-                e: Some(cpu.memory[add16_bit(pc, 2) as usize]),
+                d: Some(cpu.memory.read(add16_bit(pc, 1))),
+                e: Some(cpu.memory.read(add16_bit(pc, 2))),
                 ..RegisterChange::default()
             }
         }),
@@ -302,7 +311,7 @@ pub fn execute(cpu: &CPU, op_code: u8) -> StateChange {
             d: {
                 let pc = cpu.registers.program_counter;
 
-                Some(cpu.memory[add16_bit(pc, 1) as usize])
+                Some(cpu.memory.read(add16_bit(pc, 1)))
             },
             ..RegisterChange::default()
         }),
@@ -326,7 +335,7 @@ pub fn execute(cpu: &CPU, op_code: u8) -> StateChange {
             let pc = cpu.registers.program_counter;
 
             #[allow(overflowing_literals)]
-            let modifier = cpu.memory[(pc + 1) as usize] as i8;
+            let modifier = cpu.memory.read(pc + 1) as i8;
 
             relative_jmp(pc, modifier)
         },
@@ -336,7 +345,7 @@ pub fn execute(cpu: &CPU, op_code: u8) -> StateChange {
         ),
         0x1A => ld_from_absolute( //LD A, [DE]
             RegisterChange {
-                a: Some(cpu.memory[cpu.registers.de() as usize]),
+                a: Some(cpu.memory.read(cpu.registers.de())),
                 ..RegisterChange::default()
             }
         ),
@@ -378,7 +387,7 @@ pub fn execute(cpu: &CPU, op_code: u8) -> StateChange {
             e: {
                 let pc = cpu.registers.program_counter;
 
-                Some(cpu.memory[add16_bit(pc, 1) as usize])
+                Some(cpu.memory.read(add16_bit(pc, 1)))
             },
             ..RegisterChange::default()
         }),
@@ -386,7 +395,7 @@ pub fn execute(cpu: &CPU, op_code: u8) -> StateChange {
             let mut a = cpu.registers.a;
             let set_carry = (a & 0x1) == 0x1; //if rightmost is 1 then it will carry
 
-            a = a >> 1;
+            a >>= 1;
 
             if cpu.flags.carry {
                 a += 0x80; //0x80 will set the new leftmost bit
@@ -408,7 +417,7 @@ pub fn execute(cpu: &CPU, op_code: u8) -> StateChange {
             let pc = cpu.registers.program_counter;
 
             #[allow(overflowing_literals)]
-            let modifier = cpu.memory[(pc + 1) as usize] as i8;
+            let modifier = cpu.memory.read(pc + 1) as i8;
 
             relative_jmp(pc, modifier)
         },
@@ -416,8 +425,8 @@ pub fn execute(cpu: &CPU, op_code: u8) -> StateChange {
             let pc = cpu.registers.program_counter;
 
             RegisterChange {
-                l: Some(cpu.memory[add16_bit(pc, 1) as usize]),
-                h: Some(cpu.memory[add16_bit(pc, 2) as usize]),
+                l: Some(cpu.memory.read(add16_bit(pc, 1))),
+                h: Some(cpu.memory.read(add16_bit(pc, 2))),
                 ..RegisterChange::default()
             }
         }),
@@ -481,7 +490,7 @@ pub fn execute(cpu: &CPU, op_code: u8) -> StateChange {
             h: {
                 let pc = cpu.registers.program_counter;
 
-                Some(cpu.memory[add16_bit(pc, 1) as usize])
+                Some(cpu.memory.read(add16_bit(pc, 1)))
             },
             ..RegisterChange::default()
         }),
@@ -512,6 +521,7 @@ pub fn execute(cpu: &CPU, op_code: u8) -> StateChange {
             StateChange {
                 t_states: 4,
                 ime: Option::None,
+                power: Option::None,
                 memory: MemoryChange::default(),
                 flags: FlagChange {
                     carry: Some(set_carry),
@@ -533,7 +543,7 @@ pub fn execute(cpu: &CPU, op_code: u8) -> StateChange {
             let pc = cpu.registers.program_counter;
 
             #[allow(overflowing_literals)]
-            let modifier = cpu.memory[(pc + 1) as usize] as i8;
+            let modifier = cpu.memory.read(pc + 1) as i8;
 
             relative_jmp(pc, modifier)
         },
@@ -545,7 +555,7 @@ pub fn execute(cpu: &CPU, op_code: u8) -> StateChange {
             let (l, h) = to8_bit(add16_bit(cpu.registers.hl(), 1));
 
             ld_from_absolute(RegisterChange {
-                a: Some(cpu.memory[cpu.registers.hl() as usize]),
+                a: Some(cpu.memory.read(cpu.registers.hl())),
                 h: Some(h),
                 l: Some(l),
                 ..RegisterChange::default()
@@ -589,13 +599,14 @@ pub fn execute(cpu: &CPU, op_code: u8) -> StateChange {
             l: {
                 let pc = cpu.registers.program_counter;
 
-                Some(cpu.memory[add16_bit(pc, 1) as usize])
+                Some(cpu.memory.read(add16_bit(pc, 1)))
             },
             ..RegisterChange::default()
         }),
         0x2F => StateChange { //CPL
             t_states: 4,
             ime: Option::None,
+            power: Option::None,
             flags: FlagChange {
                 subtract: Some(true),
                 half_carry: Some(true),
@@ -615,7 +626,7 @@ pub fn execute(cpu: &CPU, op_code: u8) -> StateChange {
             let pc = cpu.registers.program_counter;
 
             #[allow(overflowing_literals)]
-            let modifier = cpu.memory[(pc + 1) as usize] as i8;
+            let modifier = cpu.memory.read(pc + 1) as i8;
 
             relative_jmp(pc, modifier)
         },
@@ -624,8 +635,8 @@ pub fn execute(cpu: &CPU, op_code: u8) -> StateChange {
 
             RegisterChange {
                 sp: Option::Some(to16_bit(
-                    cpu.memory[add16_bit(pc, 1) as usize],
-                    cpu.memory[add16_bit(pc, 2) as usize]
+                    cpu.memory.read(add16_bit(pc, 1)),
+                    cpu.memory.read(add16_bit(pc, 2))
                 )),
                 ..RegisterChange::default()
             }
@@ -664,7 +675,7 @@ pub fn execute(cpu: &CPU, op_code: u8) -> StateChange {
         }),
         0x34 => { //INC (HL)
             let addr = cpu.registers.hl();
-            let value = cpu.memory[addr as usize];
+            let value = cpu.memory.read(addr);
             let result = add8_bit(value, 1);
 
             inc_absolute(
@@ -682,7 +693,7 @@ pub fn execute(cpu: &CPU, op_code: u8) -> StateChange {
         },
         0x35 => { //DEC (HL)
             let addr = cpu.registers.hl();
-            let value = cpu.memory[addr as usize];
+            let value = cpu.memory.read(addr);
             let result = sub8_bit(value, 1);
 
             dec_absolute(
@@ -700,11 +711,12 @@ pub fn execute(cpu: &CPU, op_code: u8) -> StateChange {
         },
         0x36 => { //LD (HL), u8
             let pc = cpu.registers.program_counter;
-            let arg = cpu.memory[add16_bit(pc, 1) as usize];
+            let arg = cpu.memory.read(add16_bit(pc, 1));
 
             StateChange {
                 t_states: 12,
                 ime: Option::None,
+                power: Option::None,
                 flags: FlagChange::default(),
                 register: RegisterChange::default(),
                 memory: MemoryChange {
@@ -720,6 +732,7 @@ pub fn execute(cpu: &CPU, op_code: u8) -> StateChange {
         0x37 => StateChange { //SCF (Set Carry Flag)
             t_states: 4,
             ime: Option::None,
+            power: Option::None,
             flags: FlagChange {
                 subtract: Some(false),
                 half_carry: Some(false),
@@ -737,7 +750,7 @@ pub fn execute(cpu: &CPU, op_code: u8) -> StateChange {
             let pc = cpu.registers.program_counter;
 
             #[allow(overflowing_literals)]
-            let modifier = cpu.memory[(pc + 1) as usize] as i8;
+            let modifier = cpu.memory.read(pc + 1) as i8;
 
             relative_jmp(pc, modifier)
         },
@@ -749,7 +762,7 @@ pub fn execute(cpu: &CPU, op_code: u8) -> StateChange {
             let (l, h) = to8_bit(sub16_bit(cpu.registers.hl(), 1));
 
             ld_from_absolute(RegisterChange {
-                a: Some(cpu.memory[cpu.registers.hl() as usize]),
+                a: Some(cpu.memory.read(cpu.registers.hl())),
                 h: Some(h),
                 l: Some(l),
                 ..RegisterChange::default()
@@ -791,13 +804,14 @@ pub fn execute(cpu: &CPU, op_code: u8) -> StateChange {
             a: {
                 let pc = cpu.registers.program_counter;
 
-                Some(cpu.memory[add16_bit(pc, 1) as usize])
+                Some(cpu.memory.read(add16_bit(pc, 1)))
             },
             ..RegisterChange::default()
         }),
         0x3F => StateChange { //CCF (Complement/Invert Carry Flag)
             t_states: 4,
             ime: Option::None,
+            power: Option::None,
             flags: FlagChange {
                 subtract: Some(false),
                 half_carry: Some(false),
@@ -832,7 +846,7 @@ pub fn execute(cpu: &CPU, op_code: u8) -> StateChange {
             ..RegisterChange::default()
         }),
         0x46 => ld_from_absolute(RegisterChange { //LD B, [HL]
-            b: Some(cpu.memory[cpu.registers.hl() as usize]),
+            b: Some(cpu.memory.read(cpu.registers.hl())),
             ..RegisterChange::default()
         }),
         0x47 => ld_register_to_register(RegisterChange { //LD B, A
@@ -864,7 +878,7 @@ pub fn execute(cpu: &CPU, op_code: u8) -> StateChange {
             ..RegisterChange::default()
         }),
         0x4E => ld_from_absolute(RegisterChange { //LD C, [HL]
-            c: Some(cpu.memory[cpu.registers.hl() as usize]),
+            c: Some(cpu.memory.read(cpu.registers.hl())),
             ..RegisterChange::default()
         }),
         0x4F => ld_register_to_register(RegisterChange { //LD C, A
@@ -896,7 +910,7 @@ pub fn execute(cpu: &CPU, op_code: u8) -> StateChange {
             ..RegisterChange::default()
         }),
         0x56 => ld_from_absolute(RegisterChange { //LD D, [HL]
-            d: Some(cpu.memory[cpu.registers.hl() as usize]),
+            d: Some(cpu.memory.read(cpu.registers.hl())),
             ..RegisterChange::default()
         }),
         0x57 => ld_register_to_register(RegisterChange { //LD D, A
@@ -928,7 +942,7 @@ pub fn execute(cpu: &CPU, op_code: u8) -> StateChange {
             ..RegisterChange::default()
         }),
         0x5E => ld_from_absolute(RegisterChange { //LD E, [HL]
-            e: Some(cpu.memory[cpu.registers.hl() as usize]),
+            e: Some(cpu.memory.read(cpu.registers.hl())),
             ..RegisterChange::default()
         }),
         0x5F => ld_register_to_register(RegisterChange { //LD E, A
@@ -960,7 +974,7 @@ pub fn execute(cpu: &CPU, op_code: u8) -> StateChange {
             ..RegisterChange::default()
         }),
         0x66 => ld_from_absolute(RegisterChange { //LD H, [HL]
-            h: Some(cpu.memory[cpu.registers.hl() as usize]),
+            h: Some(cpu.memory.read(cpu.registers.hl())),
             ..RegisterChange::default()
         }),
         0x67 => ld_register_to_register(RegisterChange { //LD H, A
@@ -992,7 +1006,7 @@ pub fn execute(cpu: &CPU, op_code: u8) -> StateChange {
             ..RegisterChange::default()
         }),
         0x6E => ld_from_absolute(RegisterChange { //LD L, [HL]
-            l: Some(cpu.memory[cpu.registers.hl() as usize]),
+            l: Some(cpu.memory.read(cpu.registers.hl())),
             ..RegisterChange::default()
         }),
         0x6F => ld_register_to_register(RegisterChange { //LD L, A
@@ -1047,7 +1061,10 @@ pub fn execute(cpu: &CPU, op_code: u8) -> StateChange {
                 }
             ]
         }),
-        0x76 => nop(), //HALT //TODO: is this the same as nop?
+        0x76 => StateChange { //HALT - CPU decides between halting and the HALT bug on update
+            power: Option::Some(PowerStatus::HALTED),
+            ..nop()
+        },
         0x77 => ld_to_absolute(MemoryChange { //LD [HL], A
             changes: vec![
                 MemoryEdit {
@@ -1081,7 +1098,7 @@ pub fn execute(cpu: &CPU, op_code: u8) -> StateChange {
             ..RegisterChange::default()
         }),
         0x7E => ld_from_absolute(RegisterChange { //LD A, [HL]
-            a: Some(cpu.memory[cpu.registers.hl() as usize]),
+            a: Some(cpu.memory.read(cpu.registers.hl())),
             ..RegisterChange::default()
         }),
         0x7F => ld_register_to_register(RegisterChange { //LD A, A
@@ -1116,7 +1133,7 @@ pub fn execute(cpu: &CPU, op_code: u8) -> StateChange {
             t_states: 8,
             ..add_to_a(
                 cpu.registers.a,
-                cpu.memory[cpu.registers.hl() as usize]
+                cpu.memory.read(cpu.registers.hl())
             )
         },
         0x87 => add_to_a( //ADD A, A
@@ -1178,7 +1195,7 @@ pub fn execute(cpu: &CPU, op_code: u8) -> StateChange {
             add_to_a(cpu.registers.a, operand)
         },
         0x8E => { //ADC A, [HL]
-            let mut operand = cpu.memory[cpu.registers.hl() as usize];
+            let mut operand = cpu.memory.read(cpu.registers.hl());
 
             if cpu.flags.carry {
                 operand = add8_bit(operand, 1);
@@ -1229,7 +1246,7 @@ pub fn execute(cpu: &CPU, op_code: u8) -> StateChange {
             t_states: 8,
             ..sub_from_a(
                 cpu.registers.a,
-                cpu.memory[cpu.registers.hl() as usize]
+                cpu.memory.read(cpu.registers.hl())
             )
         },
         0x97 => sub_from_a( //SUB A, A
@@ -1291,7 +1308,7 @@ pub fn execute(cpu: &CPU, op_code: u8) -> StateChange {
             sub_from_a(cpu.registers.a, operand)
         },
         0x9E => { //SBC A, [HL]
-            let mut operand = cpu.memory[cpu.registers.hl() as usize];
+            let mut operand = cpu.memory.read(cpu.registers.hl());
 
             if cpu.flags.carry {
                 operand = add8_bit(operand, 1);
@@ -1342,7 +1359,7 @@ pub fn execute(cpu: &CPU, op_code: u8) -> StateChange {
             t_states: 8,
             ..and_to_a(
                 cpu.registers.a,
-                cpu.memory[cpu.registers.hl() as usize]
+                cpu.memory.read(cpu.registers.hl())
             )
         },
         0xA7 => and_to_a( //AND A, A
@@ -1377,7 +1394,7 @@ pub fn execute(cpu: &CPU, op_code: u8) -> StateChange {
             t_states: 8,
             ..xor_to_a(
                 cpu.registers.a,
-                cpu.memory[cpu.registers.hl() as usize]
+                cpu.memory.read(cpu.registers.hl())
             )
         },
         0xAF => xor_to_a( //XOR A, A
@@ -1412,7 +1429,7 @@ pub fn execute(cpu: &CPU, op_code: u8) -> StateChange {
             t_states: 8,
             ..or_to_a(
                 cpu.registers.a,
-                cpu.memory[cpu.registers.hl() as usize]
+                cpu.memory.read(cpu.registers.hl())
             )
         },
         0xB7 => or_to_a( //OR A, A
@@ -1447,7 +1464,7 @@ pub fn execute(cpu: &CPU, op_code: u8) -> StateChange {
             t_states: 8,
             ..cp_to_a(
                 cpu.registers.a,
-                cpu.memory[cpu.registers.hl() as usize]
+                cpu.memory.read(cpu.registers.hl())
             )
         },
         0xBF => cp_to_a( //CP A, A
@@ -1467,8 +1484,8 @@ pub fn execute(cpu: &CPU, op_code: u8) -> StateChange {
         0xC1 => pop_to_register_16_bit( //POP BC
             cpu.registers.stack_pointer,
             RegisterChange {
-                c: Some(cpu.memory[cpu.registers.stack_pointer as usize]),
-                b: Some(cpu.memory[(cpu.registers.stack_pointer + 1) as usize]),
+                c: Some(cpu.memory.read(cpu.registers.stack_pointer)),
+                b: Some(cpu.memory.read(cpu.registers.stack_pointer + 1)),
                 ..RegisterChange::default()
             }
         ),
@@ -1478,14 +1495,14 @@ pub fn execute(cpu: &CPU, op_code: u8) -> StateChange {
             }
 
             absolute_jmp(to16_bit(
-                cpu.memory[(cpu.registers.program_counter + 1) as usize],
-                cpu.memory[(cpu.registers.program_counter + 2) as usize]
+                cpu.memory.read(cpu.registers.program_counter + 1),
+                cpu.memory.read(cpu.registers.program_counter + 2)
             ))
         },
         0xC3 => { //JP a16
             let addr = to16_bit(
-                cpu.memory[(cpu.registers.program_counter + 1) as usize],
-                cpu.memory[(cpu.registers.program_counter + 2) as usize]
+                cpu.memory.read(cpu.registers.program_counter + 1),
+                cpu.memory.read(cpu.registers.program_counter + 2)
             );
 
             absolute_jmp(addr)
@@ -1496,8 +1513,8 @@ pub fn execute(cpu: &CPU, op_code: u8) -> StateChange {
             }
 
             let addr = to16_bit(
-                cpu.memory[(cpu.registers.program_counter + 1) as usize],
-                cpu.memory[(cpu.registers.program_counter + 2) as usize]
+                cpu.memory.read(cpu.registers.program_counter + 1),
+                cpu.memory.read(cpu.registers.program_counter + 2)
             );
 
             call(cpu, addr)
@@ -1521,7 +1538,7 @@ pub fn execute(cpu: &CPU, op_code: u8) -> StateChange {
             t_states: 8,
             ..add_to_a(
                 cpu.registers.a,
-                cpu.memory[(cpu.registers.program_counter + 1) as usize]
+                cpu.memory.read(cpu.registers.program_counter + 1)
             )
         },
         0xC7 => restart( //RST $00
@@ -1545,13 +1562,13 @@ pub fn execute(cpu: &CPU, op_code: u8) -> StateChange {
             }
 
             absolute_jmp(to16_bit(
-                cpu.memory[(cpu.registers.program_counter + 1) as usize],
-                cpu.memory[(cpu.registers.program_counter + 2) as usize]
+                cpu.memory.read(cpu.registers.program_counter + 1),
+                cpu.memory.read(cpu.registers.program_counter + 2)
             ))
         },
         0xCB => prefixed_execute( //PREFIX
             cpu,
-            cpu.memory[(cpu.registers.program_counter + 1) as usize]
+            cpu.memory.read(cpu.registers.program_counter + 1)
         ),
         0xCC => { //CALL Z, a16
             if !cpu.flags.zero {
@@ -1559,22 +1576,22 @@ pub fn execute(cpu: &CPU, op_code: u8) -> StateChange {
             }
 
             let addr = to16_bit(
-                cpu.memory[(cpu.registers.program_counter + 1) as usize],
-                cpu.memory[(cpu.registers.program_counter + 2) as usize]
+                cpu.memory.read(cpu.registers.program_counter + 1),
+                cpu.memory.read(cpu.registers.program_counter + 2)
             );
 
             call(cpu, addr)
         },
         0xCD => { //CALL a16
             let addr = to16_bit(
-                cpu.memory[(cpu.registers.program_counter + 1) as usize],
-                cpu.memory[(cpu.registers.program_counter + 2) as usize]
+                cpu.memory.read(cpu.registers.program_counter + 1),
+                cpu.memory.read(cpu.registers.program_counter + 2)
             );
 
             call(cpu, addr)
         },
         0xCE => { //ADC A, n8
-            let mut operand = cpu.memory[(cpu.registers.program_counter + 1) as usize];
+            let mut operand = cpu.memory.read(cpu.registers.program_counter + 1);
 
             if cpu.flags.carry {
                 operand = add8_bit(operand, 1);
@@ -1603,8 +1620,8 @@ pub fn execute(cpu: &CPU, op_code: u8) -> StateChange {
         0xD1 => pop_to_register_16_bit( //POP DE
             cpu.registers.stack_pointer,
             RegisterChange {
-                e: Some(cpu.memory[cpu.registers.stack_pointer as usize]),
-                d: Some(cpu.memory[(cpu.registers.stack_pointer + 1) as usize]),
+                e: Some(cpu.memory.read(cpu.registers.stack_pointer)),
+                d: Some(cpu.memory.read(cpu.registers.stack_pointer + 1)),
                 ..RegisterChange::default()
             }
         ),
@@ -1614,8 +1631,8 @@ pub fn execute(cpu: &CPU, op_code: u8) -> StateChange {
             }
 
             absolute_jmp(to16_bit(
-                cpu.memory[(cpu.registers.program_counter + 1) as usize],
-                cpu.memory[(cpu.registers.program_counter + 2) as usize]
+                cpu.memory.read(cpu.registers.program_counter + 1),
+                cpu.memory.read(cpu.registers.program_counter + 2)
             ))
         },
         0xD4 => { //CALL NC, a16
@@ -1624,8 +1641,8 @@ pub fn execute(cpu: &CPU, op_code: u8) -> StateChange {
             }
 
             let addr = to16_bit(
-                cpu.memory[(cpu.registers.program_counter + 1) as usize],
-                cpu.memory[(cpu.registers.program_counter + 2) as usize]
+                cpu.memory.read(cpu.registers.program_counter + 1),
+                cpu.memory.read(cpu.registers.program_counter + 2)
             );
 
             call(cpu, addr)
@@ -1649,7 +1666,7 @@ pub fn execute(cpu: &CPU, op_code: u8) -> StateChange {
             t_states: 8,
             ..sub_from_a(
                 cpu.registers.a,
-                cpu.memory[(cpu.registers.program_counter + 1) as usize]
+                cpu.memory.read(cpu.registers.program_counter + 1)
             )
         },
         0xD7 => restart( //RST $10
@@ -1668,6 +1685,7 @@ pub fn execute(cpu: &CPU, op_code: u8) -> StateChange {
         },
         0xD9 => StateChange { //RETI
             ime: Some(ImeStatus::SET),
+            power: None,
             ..ret(cpu)
         },
         0xDA => { //JP C, a16
@@ -1676,8 +1694,8 @@ pub fn execute(cpu: &CPU, op_code: u8) -> StateChange {
             }
 
             absolute_jmp(to16_bit(
-                cpu.memory[(cpu.registers.program_counter + 1) as usize],
-                cpu.memory[(cpu.registers.program_counter + 2) as usize]
+                cpu.memory.read(cpu.registers.program_counter + 1),
+                cpu.memory.read(cpu.registers.program_counter + 2)
             ))
         },
         0xDC => { //CALL C, a16
@@ -1686,14 +1704,14 @@ pub fn execute(cpu: &CPU, op_code: u8) -> StateChange {
             }
 
             let addr = to16_bit(
-                cpu.memory[(cpu.registers.program_counter + 1) as usize],
-                cpu.memory[(cpu.registers.program_counter + 2) as usize]
+                cpu.memory.read(cpu.registers.program_counter + 1),
+                cpu.memory.read(cpu.registers.program_counter + 2)
             );
 
             call(cpu, addr)
         },
         0xDE => { //SBC A, n8
-            let mut operand = cpu.memory[(cpu.registers.program_counter + 1) as usize];
+            let mut operand = cpu.memory.read(cpu.registers.program_counter + 1);
 
             if cpu.flags.carry {
                 operand = add8_bit(operand, 1);
@@ -1714,7 +1732,7 @@ pub fn execute(cpu: &CPU, op_code: u8) -> StateChange {
                 changes: vec![
                     MemoryEdit {
                         key: to16_bit(
-                            cpu.memory[(cpu.registers.program_counter + 1) as usize],
+                            cpu.memory.read(cpu.registers.program_counter + 1),
                             0xFF
                         ),
                         value: cpu.registers.a
@@ -1725,8 +1743,8 @@ pub fn execute(cpu: &CPU, op_code: u8) -> StateChange {
         0xE1 => pop_to_register_16_bit( //POP HL
             cpu.registers.stack_pointer,
             RegisterChange {
-                l: Some(cpu.memory[cpu.registers.stack_pointer as usize]),
-                h: Some(cpu.memory[(cpu.registers.stack_pointer + 1) as usize]),
+                l: Some(cpu.memory.read(cpu.registers.stack_pointer)),
+                h: Some(cpu.memory.read(cpu.registers.stack_pointer + 1)),
                 ..RegisterChange::default()
             }
         ),
@@ -1760,7 +1778,7 @@ pub fn execute(cpu: &CPU, op_code: u8) -> StateChange {
             t_states: 8,
             ..and_to_a(
                 cpu.registers.a,
-                cpu.memory[(cpu.registers.program_counter + 1) as usize]
+                cpu.memory.read(cpu.registers.program_counter + 1)
             )
         },
         0xE7 => restart( //RST $20
@@ -1769,15 +1787,16 @@ pub fn execute(cpu: &CPU, op_code: u8) -> StateChange {
         ),
         0xE8 => { //ADD SP, e8
             //operand is SIGNED
-            let mut operand = cpu.memory[(cpu.registers.program_counter + 1) as usize] as u16;
+            let mut operand = cpu.memory.read(cpu.registers.program_counter + 1) as u16;
 
             if operand & 0x80 != 0 { //check if negative
-                operand = 0xFF00 | operand; //convert to signed 16bit
+                operand |= 0xFF00; //convert to signed 16bit
             }
 
             StateChange {
                 t_states: 16,
                 ime: None,
+                power: None,
                 flags: FlagChange {
                     zero: Some(false),
                     subtract: Some(false),
@@ -1801,8 +1820,8 @@ pub fn execute(cpu: &CPU, op_code: u8) -> StateChange {
                 MemoryChange {
                     changes: vec![MemoryEdit {
                         key: to16_bit(
-                            cpu.memory[(cpu.registers.program_counter + 1) as usize],
-                            cpu.memory[(cpu.registers.program_counter + 2) as usize],
+                            cpu.memory.read(cpu.registers.program_counter + 1),
+                            cpu.memory.read(cpu.registers.program_counter + 2),
                         ),
                         value: cpu.registers.a
                     }]
@@ -1813,7 +1832,7 @@ pub fn execute(cpu: &CPU, op_code: u8) -> StateChange {
             t_states: 8,
             ..xor_to_a(
                 cpu.registers.a,
-                cpu.memory[(cpu.registers.program_counter + 1) as usize]
+                cpu.memory.read(cpu.registers.program_counter + 1)
             )
         },
         0xEF => restart( //RST $28
@@ -1825,21 +1844,21 @@ pub fn execute(cpu: &CPU, op_code: u8) -> StateChange {
             ..ld_from_absolute(RegisterChange {
                 a: {
                     let addr = to16_bit(
-                        cpu.memory[(cpu.registers.program_counter + 1) as usize],
+                        cpu.memory.read(cpu.registers.program_counter + 1),
                         0xFF
                     );
 
-                    Some(cpu.memory[addr as usize])
+                    Some(cpu.memory.read(addr))
                 },
                 ..RegisterChange::default()
             })
         },
         0xF1 => StateChange { //POP AF
-            flags: FlagChange::from_u8(cpu.memory[cpu.registers.stack_pointer as usize]),
+            flags: FlagChange::from_u8(cpu.memory.read(cpu.registers.stack_pointer)),
             ..pop_to_register_16_bit(
                 cpu.registers.stack_pointer,
                 RegisterChange {
-                    a: Some(cpu.memory[(cpu.registers.stack_pointer + 1) as usize]),
+                    a: Some(cpu.memory.read(cpu.registers.stack_pointer + 1)),
                     ..RegisterChange::default()
                 }
             )
@@ -1850,7 +1869,7 @@ pub fn execute(cpu: &CPU, op_code: u8) -> StateChange {
                 a: {
                     let addr = to16_bit(cpu.registers.c, 0xFF);
 
-                    Some(cpu.memory[addr as usize])
+                    Some(cpu.memory.read(addr))
                 },
                 ..RegisterChange::default()
             })
@@ -1858,6 +1877,7 @@ pub fn execute(cpu: &CPU, op_code: u8) -> StateChange {
         0xF3 => StateChange { //DI
             t_states: 4,
             ime: Some(ImeStatus::UNSET),
+            power: None,
             flags: FlagChange::default(),
             register: RegisterChange::default(),
             memory: MemoryChange::default()
@@ -1881,7 +1901,7 @@ pub fn execute(cpu: &CPU, op_code: u8) -> StateChange {
             t_states: 8,
             ..or_to_a(
                 cpu.registers.a,
-                cpu.memory[(cpu.registers.program_counter + 1) as usize]
+                cpu.memory.read(cpu.registers.program_counter + 1)
             )
         },
         0xF7 => restart( //RST $30
@@ -1890,10 +1910,10 @@ pub fn execute(cpu: &CPU, op_code: u8) -> StateChange {
         ),
         0xF8 => { //LD HL, SP + e8
             //operand is SIGNED
-            let mut operand = cpu.memory[(cpu.registers.program_counter + 1) as usize] as u16;
+            let mut operand = cpu.memory.read(cpu.registers.program_counter + 1) as u16;
 
             if operand & 0x80 != 0 { //check if negative
-                operand = 0xFF00 | operand; //convert to signed 16bit
+                operand |= 0xFF00; //convert to signed 16bit
             }
 
             let (l, h) = to8_bit(
@@ -1903,6 +1923,7 @@ pub fn execute(cpu: &CPU, op_code: u8) -> StateChange {
             StateChange {
                 t_states: 12,
                 ime: None,
+                power: None,
                 flags: FlagChange {
                     zero: Some(false),
                     subtract: Some(false),
@@ -1930,10 +1951,10 @@ pub fn execute(cpu: &CPU, op_code: u8) -> StateChange {
             t_states: 16,
             ..ld_from_absolute(RegisterChange {
                 a: Some(
-                    cpu.memory[to16_bit(
-                        cpu.memory[(cpu.registers.program_counter + 1) as usize],
-                        cpu.memory[(cpu.registers.program_counter + 2) as usize],
-                    ) as usize]
+                    cpu.memory.read(to16_bit(
+                        cpu.memory.read(cpu.registers.program_counter + 1),
+                        cpu.memory.read(cpu.registers.program_counter + 2),
+                    ))
                 ),
                 ..RegisterChange::default()
             })
@@ -1941,6 +1962,7 @@ pub fn execute(cpu: &CPU, op_code: u8) -> StateChange {
         0xFB => StateChange { //EI
             t_states: 4,
             ime: Some(ImeStatus::SCHEDULED),
+            power: None,
             flags: FlagChange::default(),
             register: RegisterChange::default(),
             memory: MemoryChange::default()
@@ -1949,19 +1971,16 @@ pub fn execute(cpu: &CPU, op_code: u8) -> StateChange {
             t_states: 8,
             ..cp_to_a(
                 cpu.registers.a,
-                cpu.memory[(cpu.registers.program_counter + 1) as usize]
+                cpu.memory.read(cpu.registers.program_counter + 1)
             )
         },
         0xFF => restart( //RST $38
             cpu,
             0x38
         ),
-        _ => StateChange {
-            t_states: 0,
-            ime: Option::None,
-            flags: FlagChange::default(),
-            register: RegisterChange::default(),
-            memory: MemoryChange::default()
+        _ => StateChange { //illegal opcode, the CPU hangs
+            power: Option::Some(PowerStatus::LOCKED),
+            ..nop()
         }
     }
 }
@@ -1970,6 +1989,7 @@ fn push_from_register_16_bit(sp: u16, change: MemoryChange) -> StateChange {
     StateChange {
         t_states: 16,
         ime: Option::None,
+        power: Option::None,
         flags: FlagChange::default(),
         register: RegisterChange {
             sp: Some(sp - 2),
@@ -1983,6 +2003,7 @@ fn pop_to_register_16_bit(sp: u16, change: RegisterChange) -> StateChange {
     StateChange {
         t_states: 12,
         ime: Option::None,
+        power: Option::None,
         flags: FlagChange::default(),
         register: RegisterChange {
             sp: Some(sp + 2),
@@ -1996,6 +2017,7 @@ fn no_ret() -> StateChange {
     StateChange {
         t_states: 8,
         ime: Option::None,
+        power: Option::None,
         flags: FlagChange::default(),
         memory: MemoryChange::default(),
         register: RegisterChange::default()
@@ -2004,13 +2026,14 @@ fn no_ret() -> StateChange {
 
 //return from subroutine. JP back to the addr that is in the stack
 fn ret(cpu: &CPU) -> StateChange {
-    let lsb = cpu.memory[cpu.registers.stack_pointer as usize];
-    let msb = cpu.memory[(cpu.registers.stack_pointer + 1) as usize];
+    let lsb = cpu.memory.read(cpu.registers.stack_pointer);
+    let msb = cpu.memory.read(cpu.registers.stack_pointer + 1);
     let new_addr = to16_bit(lsb, msb);
 
     StateChange {
         t_states: 16,
         ime: Option::None,
+        power: Option::None,
         flags: FlagChange::default(),
         register: RegisterChange {
             pc: Some(new_addr),
@@ -2025,6 +2048,7 @@ fn no_call() -> StateChange {
     StateChange {
         t_states: 12,
         ime: Option::None,
+        power: Option::None,
         flags: FlagChange::default(),
         register: RegisterChange::default(),
         memory: MemoryChange::default()
@@ -2046,6 +2070,7 @@ fn call(cpu: &CPU, new_addr: u16) -> StateChange {
     StateChange {
         t_states: 24,
         ime: Option::None,
+        power: Option::None,
         flags: FlagChange::default(),
         register: RegisterChange {
             pc: Some(new_addr),
@@ -2071,6 +2096,7 @@ fn absolute_jmp(address: u16) -> StateChange {
     StateChange {
         t_states: 16,
         ime: Option::None,
+        power: Option::None,
         register: RegisterChange {
             pc: Some(address),
             ..RegisterChange::default()
@@ -2084,6 +2110,7 @@ fn no_absolute_jmp() -> StateChange {
     StateChange {
         t_states: 12,
         ime: Option::None,
+        power: Option::None,
         flags: FlagChange::default(),
         register: RegisterChange::default(),
         memory: MemoryChange::default()
@@ -2094,6 +2121,7 @@ fn no_relative_jmp() -> StateChange {
     StateChange {
         t_states: 8,
         ime: Option::None,
+        power: Option::None,
         flags: FlagChange::default(),
         register: RegisterChange::default(),
         memory: MemoryChange::default()
@@ -2106,6 +2134,7 @@ fn relative_jmp(pc: u16, modifier: i8) -> StateChange {
     StateChange {
         t_states: 12,
         ime: Option::None,
+        power: Option::None,
         flags: FlagChange::default(),
         register: RegisterChange {
             pc: Some(pc),
@@ -2121,6 +2150,7 @@ fn cp_to_a(a_value: u8, operand: u8) -> StateChange {
     StateChange {
         t_states: 4,
         ime: Option::None,
+        power: Option::None,
         flags: FlagChange {
             subtract: Some(true),
             carry: Some(is_carry_subtract(a_value, operand)),
@@ -2138,6 +2168,7 @@ fn or_to_a(a_value: u8, operand: u8) -> StateChange {
     StateChange {
         t_states: 4,
         ime: Option::None,
+        power: Option::None,
         flags: FlagChange {
             subtract: Some(false),
             carry: Some(false),
@@ -2158,6 +2189,7 @@ fn xor_to_a(a_value: u8, operand: u8) -> StateChange {
     StateChange {
         t_states: 4,
         ime: Option::None,
+        power: Option::None,
         flags: FlagChange {
             subtract: Some(false),
             carry: Some(false),
@@ -2178,6 +2210,7 @@ fn and_to_a(a_value: u8, operand: u8) -> StateChange {
     StateChange {
         t_states: 4,
         ime: Option::None,
+        power: Option::None,
         flags: FlagChange {
             subtract: Some(false),
             carry: Some(false),
@@ -2198,6 +2231,7 @@ fn add_to_a(a_value: u8, operand: u8) -> StateChange {
     StateChange {
         t_states: 4,
         ime: Option::None,
+        power: Option::None,
         flags: FlagChange {
             subtract: Some(false),
             carry: Some(is_carry_add(a_value, operand)),
@@ -2218,6 +2252,7 @@ fn sub_from_a(a_value: u8, operand: u8) -> StateChange {
     StateChange {
         t_states: 4,
         ime: Option::None,
+        power: Option::None,
         flags: FlagChange {
             subtract: Some(true),
             carry: Some(is_carry_subtract(a_value, operand)),
@@ -2236,6 +2271,7 @@ fn add_to_hl(hl_value: u16, operand: u16) -> StateChange {
     StateChange {
         t_states: 8,
         ime: Option::None,
+        power: Option::None,
         flags: FlagChange {
             subtract: Some(false),
             carry: Some(is_carry_add_16(hl_value, operand)),
@@ -2259,6 +2295,7 @@ fn dec_absolute(change: MemoryChange, set_zero: bool, set_half_carry: bool) -> S
     StateChange {
         t_states: 12,
         ime: Option::None,
+        power: Option::None,
         flags: FlagChange {
             zero: Option::Some(set_zero),
             subtract: Option::Some(true),
@@ -2274,6 +2311,7 @@ fn dec8_bit(change: RegisterChange, set_zero: bool, set_half_carry: bool) -> Sta
     StateChange {
         t_states: 4,
         ime: Option::None,
+        power: Option::None,
         flags: FlagChange {
             zero: Option::Some(set_zero),
             subtract: Option::Some(true),
@@ -2289,6 +2327,7 @@ fn dec16_bit(change: RegisterChange) -> StateChange {
     StateChange {
         t_states: 8,
         ime: Option::None,
+        power: Option::None,
         flags: FlagChange::default(),
         register: change,
         memory: MemoryChange::default()
@@ -2299,6 +2338,7 @@ fn inc_absolute(change: MemoryChange, set_zero: bool, set_half_carry: bool) -> S
     StateChange {
         t_states: 12,
         ime: Option::None,
+        power: Option::None,
         flags: FlagChange {
             zero: Option::Some(set_zero),
             subtract: Option::Some(false),
@@ -2314,6 +2354,7 @@ fn inc8_bit(change: RegisterChange, set_zero: bool, set_half_carry: bool) -> Sta
     StateChange {
         t_states: 4,
         ime: Option::None,
+        power: Option::None,
         flags: FlagChange {
             zero: Option::Some(set_zero),
             subtract: Option::Some(false),
@@ -2329,6 +2370,7 @@ fn inc16_bit(change: RegisterChange) -> StateChange {
     StateChange {
         t_states: 8,
         ime: Option::None,
+        power: Option::None,
         flags: FlagChange::default(),
         register: change,
         memory: MemoryChange::default()
@@ -2339,6 +2381,7 @@ fn ld_to_absolute(change: MemoryChange) -> StateChange {
     StateChange {
         t_states: 8,
         ime: Option::None,
+        power: Option::None,
         flags: FlagChange::default(),
         register: RegisterChange::default(),
         memory: change
@@ -2349,6 +2392,7 @@ fn ld_from_absolute(change: RegisterChange) -> StateChange {
     StateChange {
         t_states: 8,
         ime: Option::None,
+        power: Option::None,
         flags: FlagChange::default(),
         register: change,
         memory: MemoryChange::default()
@@ -2359,6 +2403,7 @@ fn ld_immediate(change: RegisterChange) -> StateChange {
     StateChange {
         t_states: 8,
         ime: Option::None,
+        power: Option::None,
         flags: FlagChange::default(),
         register: change,
         memory: MemoryChange::default()
@@ -2369,6 +2414,7 @@ fn ld16_immediate(change: RegisterChange) -> StateChange {
     StateChange {
         t_states: 12,
         ime: Option::None,
+        power: Option::None,
         flags: FlagChange::default(),
         register: change,
         memory: MemoryChange::default()
@@ -2379,6 +2425,7 @@ fn ld_register_to_register(change: RegisterChange) -> StateChange {
     StateChange {
         t_states: 4,
         ime: Option::None,
+        power: Option::None,
         flags: FlagChange::default(),
         register: change,
         memory: MemoryChange::default()
@@ -2389,6 +2436,7 @@ fn rotate_register(change: RegisterChange, set_carry: bool) -> StateChange {
     StateChange {
         t_states: 4,
         ime: Option::None,
+        power: Option::None,
         flags: FlagChange {
             carry: Some(set_carry),
             ..FlagChange::reset()
@@ -2403,6 +2451,7 @@ fn nop() -> StateChange {
     StateChange {
         t_states: 4,
         ime: Option::None,
+        power: Option::None,
         flags: FlagChange::default(),
         register: RegisterChange::default(),
         memory: MemoryChange::default()
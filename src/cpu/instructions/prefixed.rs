@@ -2,7 +2,7 @@ use crate::cpu::{
     CPU,
     flags::FlagChange,
     registers::RegisterChange,
-    memory::{MemoryChange, MemoryEdit}, util::BINARY_BASE
+    memory::{Bus, MemoryChange, MemoryEdit}, util::BINARY_BASE
 };
 
 use super::StateChange;
@@ -82,7 +82,7 @@ pub fn prefixed_execute(cpu: &CPU, op_code: u8) -> StateChange {
             )
         },
         0x06 => { //RLC [HL]
-            let result = cpu.memory[cpu.registers.hl() as usize].rotate_left(1);
+            let result = cpu.memory.read(cpu.registers.hl()).rotate_left(1);
             let set_carry = (result & 0x01) == 0x01;
 
             rotate_shift_absolute(
@@ -183,7 +183,7 @@ pub fn prefixed_execute(cpu: &CPU, op_code: u8) -> StateChange {
             )
         },
         0x0E => { //RRC [HL]
-            let result = cpu.memory[cpu.registers.hl() as usize].rotate_right(1);
+            let result = cpu.memory.read(cpu.registers.hl()).rotate_right(1);
             let set_carry = (result & 0x80) == 0x80;
 
             rotate_shift_absolute(
@@ -303,7 +303,7 @@ pub fn prefixed_execute(cpu: &CPU, op_code: u8) -> StateChange {
         },
         0x16 => { //RL [HL]
             let (result, set_carry) = rotate_left_through_carry(
-                cpu.memory[cpu.registers.hl() as usize],
+                cpu.memory.read(cpu.registers.hl()),
                 cpu.flags.carry
             );
 
@@ -427,7 +427,7 @@ pub fn prefixed_execute(cpu: &CPU, op_code: u8) -> StateChange {
         },
         0x1E => { //RR [HL]
             let (result, set_carry) = rotate_right_through_carry(
-                cpu.memory[cpu.registers.hl() as usize],
+                cpu.memory.read(cpu.registers.hl()),
                 cpu.flags.carry
             );
 
@@ -545,7 +545,7 @@ pub fn prefixed_execute(cpu: &CPU, op_code: u8) -> StateChange {
         },
         0x26 => { //SLA [HL]
             let (result, set_carry) = shift_left_arithmetically(
-                cpu.memory[cpu.registers.hl() as usize]
+                cpu.memory.read(cpu.registers.hl())
             );
 
             rotate_shift_absolute(
@@ -661,7 +661,7 @@ pub fn prefixed_execute(cpu: &CPU, op_code: u8) -> StateChange {
         },
         0x2E => { //SRA [HL]
             let (result, set_carry) = shift_right_arithmetically(
-                cpu.memory[cpu.registers.hl() as usize]
+                cpu.memory.read(cpu.registers.hl())
             );
 
             rotate_shift_absolute(
@@ -758,7 +758,7 @@ pub fn prefixed_execute(cpu: &CPU, op_code: u8) -> StateChange {
             )
         },
         0x36 => { //SWAP [HL]
-            let result = swap(cpu.memory[cpu.registers.hl() as usize]);
+            let result = swap(cpu.memory.read(cpu.registers.hl()));
 
             swap_absolute(
                 MemoryChange {
@@ -869,7 +869,7 @@ pub fn prefixed_execute(cpu: &CPU, op_code: u8) -> StateChange {
         },
         0x3E => { //SRL [HL]
             let (result, set_carry) = shift_right_logically(
-                cpu.memory[cpu.registers.hl() as usize]
+                cpu.memory.read(cpu.registers.hl())
             );
 
             rotate_shift_absolute(
@@ -1038,6 +1038,7 @@ fn set_bit_register(cpu: &CPU, op_code: u8, bit_index: u8) -> StateChange {
     StateChange {
         t_states: 8,
         ime: None,
+        power: None,
         flags: FlagChange::default(),
         register: RegisterChange::create_from_opcode(
             op_code,
@@ -1049,7 +1050,7 @@ fn set_bit_register(cpu: &CPU, op_code: u8, bit_index: u8) -> StateChange {
 
 fn set_bit_absolute_hl(cpu: &CPU, bit_index: u8) -> StateChange {
     let test = BINARY_BASE.pow(bit_index as u32);
-    let mut value = cpu.memory[cpu.registers.hl() as usize];
+    let mut value = cpu.memory.read(cpu.registers.hl());
 
     if (value & test) != test { //the bit is not set, set it
         value += test;
@@ -1058,6 +1059,7 @@ fn set_bit_absolute_hl(cpu: &CPU, bit_index: u8) -> StateChange {
     StateChange {
         t_states: 16,
         ime: None,
+        power: None,
         flags: FlagChange::default(),
         register: RegisterChange::default(),
         memory: MemoryChange {
@@ -1082,6 +1084,7 @@ fn reset_bit_register(cpu: &CPU, op_code: u8, bit_index: u8) -> StateChange {
     StateChange {
         t_states: 8,
         ime: None,
+        power: None,
         flags: FlagChange::default(),
         register: RegisterChange::create_from_opcode(
             op_code,
@@ -1093,7 +1096,7 @@ fn reset_bit_register(cpu: &CPU, op_code: u8, bit_index: u8) -> StateChange {
 
 fn reset_bit_absolute_hl(cpu: &CPU, bit_index: u8) -> StateChange {
     let test = BINARY_BASE.pow(bit_index as u32);
-    let mut value = cpu.memory[cpu.registers.hl() as usize];
+    let mut value = cpu.memory.read(cpu.registers.hl());
 
     if (value & test) == test { //the bit is set, reset it
         value -= test;
@@ -1102,6 +1105,7 @@ fn reset_bit_absolute_hl(cpu: &CPU, bit_index: u8) -> StateChange {
     StateChange {
         t_states: 16,
         ime: None,
+        power: None,
         flags: FlagChange::default(),
         register: RegisterChange::default(),
         memory: MemoryChange {
@@ -1123,6 +1127,7 @@ fn bit_test_register(cpu: &CPU, op_code: u8, bit_index: u8) -> StateChange {
     StateChange {
         t_states: 8,
         ime: None,
+        power: None,
         flags: FlagChange {
             zero: Some(set_zero),
             subtract: Some(false),
@@ -1136,12 +1141,13 @@ fn bit_test_register(cpu: &CPU, op_code: u8, bit_index: u8) -> StateChange {
 
 fn bit_test_absolute_hl(cpu: &CPU, bit_index: u8) -> StateChange {
     let test = BINARY_BASE.pow(bit_index as u32);
-    let value = cpu.memory[cpu.registers.hl() as usize];
+    let value = cpu.memory.read(cpu.registers.hl());
     let set_zero = value & test != test;
 
     StateChange {
         t_states: 12,
         ime: None,
+        power: None,
         flags: FlagChange {
             zero: Some(set_zero),
             subtract: Some(false),
@@ -1157,6 +1163,7 @@ fn swap_absolute(change: MemoryChange, set_zero: bool) -> StateChange {
     StateChange {
         t_states: 16,
         ime: Option::None,
+        power: Option::None,
         flags: FlagChange {
             zero: Some(set_zero),
             ..FlagChange::reset()
@@ -1170,6 +1177,7 @@ fn swap_register(change: RegisterChange, set_zero: bool) -> StateChange {
     StateChange {
         t_states: 8,
         ime: Option::None,
+        power: Option::None,
         flags: FlagChange {
             zero: Some(set_zero),
             ..FlagChange::reset()
@@ -1183,6 +1191,7 @@ fn rotate_shift_absolute(change: MemoryChange, set_carry: bool, set_zero: bool)
     StateChange {
         t_states: 16,
         ime: Option::None,
+        power: Option::None,
         flags: FlagChange {
             carry: Some(set_carry),
             zero: Some(set_zero),
@@ -1197,6 +1206,7 @@ fn rotate_shift_register(change: RegisterChange, set_carry: bool, set_zero: bool
     StateChange {
         t_states: 8,
         ime: Option::None,
+        power: Option::None,
         flags: FlagChange {
             carry: Some(set_carry),
             zero: Some(set_zero),
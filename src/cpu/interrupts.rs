@@ -0,0 +1,69 @@
+//https://gbdev.io/pandocs/Interrupts.html
+pub const INTERRUPT_FLAG: u16 = 0xFF0F; //IF
+pub const INTERRUPT_ENABLE: u16 = 0xFFFF; //IE
+pub const INTERRUPT_MASK: u8 = 0x1F; //only the lower 5 bits are wired to anything
+
+pub const INTERRUPT_T_STATES: u8 = 20; //2 wait states, push PC (2 m-cycles) & jump to the vector
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Interrupt {
+    VBlank,
+    Stat,
+    Timer,
+    Serial,
+    Joypad
+}
+
+impl Interrupt {
+    pub fn bit(&self) -> u8 {
+        match self {
+            Interrupt::VBlank => 0x01,
+            Interrupt::Stat => 0x02,
+            Interrupt::Timer => 0x04,
+            Interrupt::Serial => 0x08,
+            Interrupt::Joypad => 0x10
+        }
+    }
+
+    pub fn vector(&self) -> u16 {
+        match self {
+            Interrupt::VBlank => 0x40,
+            Interrupt::Stat => 0x48,
+            Interrupt::Timer => 0x50,
+            Interrupt::Serial => 0x58,
+            Interrupt::Joypad => 0x60
+        }
+    }
+
+    //highest priority interrupt in a set of IE & IF bits, lower bits win
+    pub fn from_pending(pending: u8) -> Option<Interrupt> {
+        [
+            Interrupt::VBlank,
+            Interrupt::Stat,
+            Interrupt::Timer,
+            Interrupt::Serial,
+            Interrupt::Joypad
+        ]
+            .into_iter()
+            .find(|interrupt| (pending & interrupt.bit()) != 0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_pending() {
+        assert_eq!(Option::None, Interrupt::from_pending(0x00));
+        assert_eq!(Option::Some(Interrupt::VBlank), Interrupt::from_pending(0x1F));
+        assert_eq!(Option::Some(Interrupt::Timer), Interrupt::from_pending(0x14));
+        assert_eq!(Option::Some(Interrupt::Joypad), Interrupt::from_pending(0x10));
+    }
+
+    #[test]
+    fn test_vector() {
+        assert_eq!(0x48, Interrupt::Stat.vector());
+        assert_eq!(0x58, Interrupt::Serial.vector());
+    }
+}
@@ -1,6 +1,25 @@
-use std::ops::{Index, IndexMut};
+use crate::apu::{APU, NR10, NR52, WAVE_RAM_START, WAVE_RAM_END, DEFAULT_SAMPLE_RATE};
+use crate::cartridge::Cartridge;
+use crate::joypad::{Joypad, Button, P1};
+use crate::ppu::{PPU, Mode, OAM_START, LCDC, LYC, BGP, WX};
+use super::interrupts::{Interrupt, INTERRUPT_FLAG, INTERRUPT_ENABLE, INTERRUPT_MASK};
+use super::timer::{Timer, DIV, TAC};
+use super::dma::{Dma, DMA};
+use super::serial::{Serial, SB, SC};
 
-const MEMORY_SIZE: usize = 0xFFFF;
+//https://gbdev.io/pandocs/Memory_Map.html
+const MEMORY_SIZE: usize = 0x10000; //full 64 KiB, 0xFFFF is the IE register
+const BOOT_ROM_SIZE: usize = 0x0100;
+const BOOT_ROM_UNMAP: u16 = 0xFF50; //any non-zero write hands the first 256 bytes back to the cartridge
+const ECHO_OFFSET: u16 = 0x2000; //0xE000-0xFDFF mirrors 0xC000-0xDDFF
+const OPEN_BUS: u8 = 0xFF;
+const UNUSABLE_READ: u8 = 0x00; //DMG returns 0 from 0xFEA0-0xFEFF outside of OAM blocking
+const BLOCKED_READ: u8 = 0xFF; //VRAM & OAM while the PPU is using them
+
+pub trait Bus {
+    fn read(&self, addr: u16) -> u8;
+    fn write(&mut self, addr: u16, value: u8);
+}
 
 pub struct MemoryEdit {
     pub key: u16,
@@ -18,49 +37,305 @@ impl MemoryChange {
 }
 
 pub struct Memory {
-    memory: [u8; MEMORY_SIZE],
+    memory: [u8; MEMORY_SIZE], //backing store for the regions that are plain RAM
+    boot_rom: Option<Vec<u8>>, //overlays 0x0000-0x00FF until unmapped via 0xFF50
+    cartridge: Option<Cartridge>, //without a cartridge ROM & external RAM behave as plain memory
+    timer: Timer,
+    serial: Serial,
+    ppu: PPU,
+    apu: APU,
+    dma: Dma,
+    joypad: Joypad,
+    key_pressed: bool, //a P1 line fell since the last take_key_press, what wakes STOP
+    access_blocking: bool //VRAM & OAM are cut off from the CPU while the PPU reads them, can be turned off for debugging
 }
 
-//allows read for Memory[index]
-impl Index<usize> for Memory {
-    type Output = u8;
+impl Bus for Memory {
+    fn read(&self, addr: u16) -> u8 {
+        if let Option::Some(value) = self.dma_conflict(addr) {
+            return value;
+        }
 
-    fn index(&self, index: usize) -> &Self::Output {
-        &self.memory[index]
+        match addr {
+            0x0000..=0x7FFF => self.read_rom(addr),
+            0x8000..=0x9FFF if self.vram_blocked() => BLOCKED_READ,
+            0x8000..=0x9FFF => self.ppu.read_vram(addr),
+            0xA000..=0xBFFF => self.read_external_ram(addr),
+            0xC000..=0xDFFF => self.memory[addr as usize], //WRAM
+            0xE000..=0xFDFF => self.memory[(addr - ECHO_OFFSET) as usize], //echo RAM
+            0xFE00..=0xFEFF if self.oam_blocked() => BLOCKED_READ,
+            0xFE00..=0xFE9F => self.ppu.read_oam(addr),
+            0xFEA0..=0xFEFF => UNUSABLE_READ,
+            0xFF00..=0xFF7F => self.read_io(addr),
+            0xFF80..=0xFFFE => self.memory[addr as usize], //HRAM
+            0xFFFF => self.memory[addr as usize] //IE
+        }
     }
-}
 
-//allows write for Memory[index]
-impl IndexMut<usize> for Memory {
-    fn index_mut(&mut self, index: usize) -> &mut Self::Output {
-        &mut self.memory[index]
+    fn write(&mut self, addr: u16, value: u8) {
+        if self.dma_conflict(addr).is_some() {
+            return;
+        }
+
+        match addr {
+            0x0000..=0x7FFF | 0xA000..=0xBFFF => self.write_cartridge(addr, value),
+            0x8000..=0x9FFF if self.vram_blocked() => {},
+            0x8000..=0x9FFF => self.ppu.write_vram(addr, value),
+            0xFE00..=0xFE9F if self.oam_blocked() => {},
+            0xFE00..=0xFE9F => self.ppu.write_oam(addr, value),
+            0xE000..=0xFDFF => self.memory[(addr - ECHO_OFFSET) as usize] = value,
+            0xFEA0..=0xFEFF => {}, //unusable, writes are ignored
+            0xFF00..=0xFF7F => self.write_io(addr, value),
+            _ => self.memory[addr as usize] = value //WRAM, HRAM & IE
+        }
     }
 }
 
 impl Memory {
     pub fn new() -> Memory {
         Memory {
-            memory: [0; MEMORY_SIZE]
+            memory: [0; MEMORY_SIZE],
+            boot_rom: Option::None,
+            cartridge: Option::None,
+            timer: Timer::new(),
+            serial: Serial::new(),
+            ppu: PPU::init(),
+            apu: APU::init(DEFAULT_SAMPLE_RATE),
+            dma: Dma::new(),
+            joypad: Joypad::new(),
+            key_pressed: false,
+            access_blocking: true
+        }
+    }
+
+    pub fn insert_cartridge(&mut self, cartridge: Cartridge) {
+        self.cartridge = Option::Some(cartridge);
+    }
+
+    pub fn cartridge(&self) -> Option<&Cartridge> {
+        self.cartridge.as_ref()
+    }
+
+    pub fn cartridge_mut(&mut self) -> Option<&mut Cartridge> {
+        self.cartridge.as_mut()
+    }
+
+    pub fn map_boot_rom(&mut self, mut rom: Vec<u8>) {
+        rom.truncate(BOOT_ROM_SIZE);
+        self.boot_rom = Option::Some(rom);
+    }
+
+    pub fn is_boot_rom_mapped(&self) -> bool {
+        self.boot_rom.is_some()
+    }
+
+    pub fn ppu(&self) -> &PPU {
+        &self.ppu
+    }
+
+    pub fn ppu_mut(&mut self) -> &mut PPU {
+        &mut self.ppu
+    }
+
+    pub fn apu_mut(&mut self) -> &mut APU {
+        &mut self.apu
+    }
+
+    pub fn set_button(&mut self, button: Button, pressed: bool) {
+        if self.joypad.set_button(button, pressed) {
+            self.key_pressed = true;
+            self.request_interrupt(Interrupt::Joypad);
         }
     }
 
+    pub fn take_key_press(&mut self) -> bool {
+        std::mem::take(&mut self.key_pressed)
+    }
+
+    pub fn set_access_blocking(&mut self, enabled: bool) {
+        self.access_blocking = enabled;
+    }
+
+    //advance the peripherals on the bus by t-cycles
+    pub fn tick(&mut self, t_cycles: u8) {
+        for _ in 0..t_cycles {
+            if let Option::Some(offset) = self.dma.tick() {
+                let value = self.dma_read(self.dma.source() + offset);
+                self.dma.set_value(value);
+                self.ppu.write_oam(OAM_START + offset, value);
+            }
+        }
+
+        let interrupts = self.timer.tick(t_cycles) | self.serial.tick(t_cycles) | self.ppu.tick(t_cycles);
+        self.memory[INTERRUPT_FLAG as usize] |= interrupts;
+
+        for _ in 0..self.timer.take_div_apu() { //also picks up edges from DIV writes
+            self.apu.clock_sequencer();
+        }
+
+        self.apu.tick(t_cycles);
+    }
+
+    pub fn request_interrupt(&mut self, interrupt: Interrupt) {
+        self.memory[INTERRUPT_FLAG as usize] |= interrupt.bit();
+    }
+
+    //interrupts that are both requested (IF) and enabled (IE)
+    pub fn pending_interrupts(&self) -> u8 {
+        self.memory[INTERRUPT_FLAG as usize] & self.memory[INTERRUPT_ENABLE as usize] & INTERRUPT_MASK
+    }
+
     pub fn update(&mut self, change: &MemoryChange) {
         for mem_change in change.changes.iter() {
-            self[mem_change.key as usize] = mem_change.value;
+            self.write(mem_change.key, mem_change.value);
+        }
+    }
+
+    //what the CPU sees while OAM DMA holds a bus, None when the access goes through as normal
+    fn dma_conflict(&self, addr: u16) -> Option<u8> {
+        if !self.dma.is_active() {
+            return Option::None;
+        }
+
+        //VRAM has its own bus, everything else below OAM shares the external one with the cartridge
+        let is_vram = |addr| (0x8000..=0x9FFF).contains(&addr);
+
+        match addr {
+            0xFE00..=0xFEFF => Option::Some(OPEN_BUS), //OAM is being written
+            0xFF00..=0xFFFF => Option::None, //IO, HRAM & IE are inside the CPU
+            _ if is_vram(addr) == is_vram(self.dma.source()) => Option::Some(self.dma.value()),
+            _ => Option::None
+        }
+    }
+
+    //DMA reads bypass PPU blocking, sources from 0xE000 up see WRAM through the echo
+    fn dma_read(&self, addr: u16) -> u8 {
+        match addr {
+            0x0000..=0x7FFF => self.read_rom(addr),
+            0x8000..=0x9FFF => self.ppu.read_vram(addr),
+            0xA000..=0xBFFF => self.read_external_ram(addr),
+            0xC000..=0xDFFF => self.memory[addr as usize],
+            _ => self.memory[(addr - ECHO_OFFSET) as usize]
+        }
+    }
+
+    //https://gbdev.io/pandocs/Accessing_VRAM_and_OAM.html
+    fn vram_blocked(&self) -> bool {
+        self.access_blocking && self.ppu.mode() == Mode::DRAWING
+    }
+
+    fn oam_blocked(&self) -> bool {
+        self.access_blocking && matches!(self.ppu.mode(), Mode::OAM | Mode::DRAWING)
+    }
+
+    fn read_rom(&self, addr: u16) -> u8 {
+        if let Option::Some(boot_rom) = &self.boot_rom {
+            if (addr as usize) < boot_rom.len() {
+                return boot_rom[addr as usize];
+            }
+        }
+
+        match &self.cartridge {
+            Option::Some(cartridge) => cartridge.read(addr),
+            Option::None => self.memory[addr as usize]
+        }
+    }
+
+    fn read_external_ram(&self, addr: u16) -> u8 {
+        match &self.cartridge {
+            Option::Some(cartridge) => cartridge.read(addr),
+            Option::None => self.memory[addr as usize]
+        }
+    }
+
+    //ROM writes go to the MBC, writes always land underneath the boot ROM overlay
+    fn write_cartridge(&mut self, addr: u16, value: u8) {
+        match &mut self.cartridge {
+            Option::Some(cartridge) => cartridge.write(addr, value),
+            Option::None => self.memory[addr as usize] = value
+        }
+    }
+
+    fn read_io(&self, addr: u16) -> u8 {
+        match io_unused_bits(addr) {
+            Option::Some(unused) => unused | match addr {
+                P1 => self.joypad.read(),
+                SB | SC => self.serial.read(addr),
+                DIV..=TAC => self.timer.read(addr),
+                LCDC..=LYC | BGP..=WX => self.ppu.read(addr),
+                DMA => self.dma.read(),
+                NR10..=NR52 | WAVE_RAM_START..=WAVE_RAM_END => self.apu.read(addr),
+                _ => self.memory[addr as usize]
+            },
+            Option::None => OPEN_BUS
+        }
+    }
+
+    fn write_io(&mut self, addr: u16, value: u8) {
+        if io_unused_bits(addr).is_none() {
+            return;
+        }
+
+        match addr {
+            P1 => {
+                if self.joypad.write(value) {
+                    self.key_pressed = true;
+                    self.request_interrupt(Interrupt::Joypad);
+                }
+            },
+            SB | SC => self.serial.write(addr, value),
+            DIV..=TAC => self.timer.write(addr, value),
+            LCDC..=LYC | BGP..=WX => self.ppu.write(addr, value),
+            DMA => self.dma.write(value),
+            NR10..=NR52 | WAVE_RAM_START..=WAVE_RAM_END => self.apu.write(addr, value),
+            BOOT_ROM_UNMAP => {
+                if value != 0 {
+                    self.boot_rom = Option::None;
+                }
+            },
+            _ => self.memory[addr as usize] = value
         }
     }
 }
 
+//bits of an IO register that always read back as 1, None for addresses with no register behind them
+//https://gbdev.io/pandocs/Hardware_Reg_List.html
+fn io_unused_bits(addr: u16) -> Option<u8> {
+    match addr {
+        0xFF00 => Option::Some(0xC0), //P1
+        SB => Option::Some(0x00),
+        SC => Option::Some(0x7E),
+        0xFF04..=0xFF06 => Option::Some(0x00), //DIV, TIMA, TMA
+        0xFF07 => Option::Some(0xF8), //TAC
+        0xFF0F => Option::Some(0xE0), //IF
+        0xFF10 => Option::Some(0x80), //NR10
+        0xFF11 | 0xFF16 => Option::Some(0x3F), //NR11, NR21 (length is write only)
+        0xFF12 | 0xFF17 | 0xFF21 | 0xFF22 | 0xFF24 | 0xFF25 => Option::Some(0x00),
+        0xFF13 | 0xFF18 | 0xFF1B | 0xFF1D | 0xFF20 => Option::Some(0xFF), //write only
+        0xFF14 | 0xFF19 | 0xFF1E | 0xFF23 => Option::Some(0xBF), //only the length enable bit reads back
+        0xFF1A => Option::Some(0x7F), //NR30
+        0xFF1C => Option::Some(0x9F), //NR32
+        0xFF26 => Option::Some(0x70), //NR52
+        0xFF30..=0xFF3F => Option::Some(0x00), //wave RAM
+        0xFF41 => Option::Some(0x80), //STAT
+        DMA => Option::Some(0x00),
+        0xFF40..=0xFF4B => Option::Some(0x00), //LCD
+        BOOT_ROM_UNMAP => Option::Some(0xFF),
+        _ => Option::None
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::cartridge::{build_rom, fix_checksums};
 
     #[test]
     fn test_read_write() {
         let mut memory = Memory::new();
 
-        memory[0x01] = 10;
-        assert_eq!(memory[0x01], 10);
+        memory.write(0x01, 10);
+        assert_eq!(memory.read(0x01), 10);
     }
 
     #[test]
@@ -74,7 +349,307 @@ mod tests {
             }]
         });
 
-        assert_eq!(memory[0x01], 0x0A);
-        assert_eq!(memory[0x02], 0x00);
+        assert_eq!(memory.read(0x01), 0x0A);
+        assert_eq!(memory.read(0x02), 0x00);
+    }
+
+    #[test]
+    fn test_boot_rom_overlay() {
+        let mut memory = Memory::new();
+
+        memory.write(0x00, 0xC3); //cartridge byte underneath the boot ROM
+        memory.write(0x100, 0x00);
+        memory.map_boot_rom(vec![0x31; 0x200]);
+
+        assert!(memory.is_boot_rom_mapped());
+        assert_eq!(memory.read(0x00), 0x31);
+        assert_eq!(memory.read(0xFF), 0x31);
+        assert_eq!(memory.read(0x100), 0x00); //boot ROM is truncated to 256 bytes
+
+        memory.update(&MemoryChange {
+            changes: vec![MemoryEdit {
+                key: BOOT_ROM_UNMAP,
+                value: 0x00
+            }]
+        });
+
+        assert!(memory.is_boot_rom_mapped()); //writing zero has no effect
+
+        memory.update(&MemoryChange {
+            changes: vec![MemoryEdit {
+                key: BOOT_ROM_UNMAP,
+                value: 0x01
+            }]
+        });
+
+        assert!(!memory.is_boot_rom_mapped());
+        assert_eq!(memory.read(0x00), 0xC3);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_cartridge_routing() {
+        let mut rom = build_rom("MBC", 0x03, 0x01, 0x02); //MBC1 + RAM + battery, 64 KiB
+        rom[0x4000] = 0x01;
+        rom[0x8000] = 0x02;
+        fix_checksums(&mut rom);
+
+        let mut memory = Memory::new();
+        memory.insert_cartridge(Cartridge::from_bytes(rom).unwrap());
+
+        assert_eq!(memory.read(0x4000), 0x01);
+
+        memory.update(&MemoryChange {
+            changes: vec![
+                MemoryEdit { key: 0x2000, value: 0x02 }, //select ROM bank 2
+                MemoryEdit { key: 0x0000, value: 0x0A }, //enable RAM
+                MemoryEdit { key: 0xA000, value: 0x99 }
+            ]
+        });
+
+        assert_eq!(memory.read(0x4000), 0x02);
+        assert_eq!(memory.read(0x2000), 0x00); //ROM is untouched
+        assert_eq!(memory.read(0xA000), 0x99);
+    }
+
+    #[test]
+    fn test_echo_ram() {
+        let mut memory = Memory::new();
+
+        memory.write(0xC123, 0x11);
+        assert_eq!(memory.read(0xE123), 0x11);
+
+        memory.write(0xFDFF, 0x22);
+        assert_eq!(memory.read(0xDDFF), 0x22);
+    }
+
+    #[test]
+    fn test_unusable_region() {
+        let mut memory = Memory::new();
+
+        memory.write(0xFEA0, 0x33);
+        assert_eq!(memory.read(0xFEA0), UNUSABLE_READ);
+    }
+
+    #[test]
+    fn test_io_registers() {
+        let mut memory = Memory::new();
+
+        memory.write(0xFF03, 0x12); //nothing mapped
+        assert_eq!(memory.read(0xFF03), OPEN_BUS);
+        assert_eq!(memory.read(0xFF4C), OPEN_BUS);
+
+        memory.write(0xFF0F, 0x01); //IF, upper 3 bits read as 1
+        assert_eq!(memory.read(0xFF0F), 0xE1);
+
+        memory.write(0xFF07, 0x05); //TAC
+        assert_eq!(memory.read(0xFF07), 0xFD);
+
+        memory.write(DIV, 0xAB); //writing any value resets DIV
+        assert_eq!(memory.read(DIV), 0x00);
+
+        memory.write(0xFF42, 0x64); //SCY is fully read/write
+        assert_eq!(memory.read(0xFF42), 0x64);
+    }
+
+    #[test]
+    fn test_interrupt_enable() {
+        let mut memory = Memory::new();
+
+        assert_eq!(memory.read(0xFFFF), 0x00);
+
+        memory.write(0xFFFF, 0x1F);
+        assert_eq!(memory.read(0xFFFF), 0x1F);
+
+        memory.update(&MemoryChange {
+            changes: vec![MemoryEdit {
+                key: 0xFFFF,
+                value: 0x05
+            }]
+        });
+
+        assert_eq!(memory.read(0xFFFF), 0x05);
+        assert_eq!(memory.read(0xFFFE), 0x00); //HRAM is separate
+    }
+
+    #[test]
+    fn test_lcd_registers() {
+        let mut memory = Memory::new();
+
+        memory.write(LCDC, 0x91);
+        memory.write(0xFF43, 0x07); //SCX
+        memory.write(0xFF41, 0x4F); //STAT, only the interrupt selects stick
+        memory.tick(200);
+
+        assert_eq!(memory.read(LCDC), 0x91); //ticking the PPU leaves CPU writes alone
+        assert_eq!(memory.read(0xFF43), 0x07);
+        assert_eq!(memory.read(0xFF41), 0xCF); //bit 7 unused, coincidence & mode 3 from the PPU
+
+        for _ in 0..12 {
+            memory.tick(228);
+        }
+
+        assert_eq!(memory.read(0xFF44), 0x06);
+        memory.write(0xFF44, 0x99); //LY
+        assert_eq!(memory.read(0xFF44), 0x00);
+    }
+
+    #[test]
+    fn test_access_blocking() {
+        let mut memory = Memory::new();
+        memory.write(0x8000, 0x11);
+        memory.write(0xFE00, 0x22);
+        memory.write(LCDC, 0x80); //LCD on, starts in OAM scan
+
+        assert_eq!(memory.read(0x8000), 0x11);
+        assert_eq!(memory.read(0xFE00), BLOCKED_READ);
+        assert_eq!(memory.read(0xFEA0), BLOCKED_READ);
+
+        memory.write(0xFE00, 0x33);
+        memory.tick(80); //drawing
+
+        assert_eq!(memory.read(0x8000), BLOCKED_READ);
+        memory.write(0x8000, 0x44);
+
+        memory.set_access_blocking(false);
+        assert_eq!(memory.read(0x8000), 0x11); //blocked writes are dropped
+        assert_eq!(memory.read(0xFE00), 0x22);
+
+        memory.set_access_blocking(true);
+        memory.tick(172); //HBlank
+
+        memory.write(0x8000, 0x55);
+        assert_eq!(memory.read(0x8000), 0x55);
+        assert_eq!(memory.read(0xFE00), 0x22);
+        assert_eq!(memory.read(0xFEA0), UNUSABLE_READ);
+    }
+
+    #[test]
+    fn test_dma() {
+        let mut memory = Memory::new();
+
+        for offset in 0..0xA0 {
+            memory.write(0xC100 + offset, offset as u8);
+        }
+
+        memory.write(0xFF80, 0x77);
+        memory.write(DMA, 0xC1);
+
+        for _ in 0..9 {
+            memory.tick(70);
+        }
+
+        assert_eq!(memory.read(0xFE00), OPEN_BUS); //OAM is busy
+        assert_eq!(memory.read(0xD000), 0x9C); //WRAM shares the bus, last byte copied
+        assert_eq!(memory.read(0x8000), 0x00); //VRAM is on its own bus
+        assert_eq!(memory.read(0xFF80), 0x77); //HRAM is always reachable
+        assert_eq!(memory.read(DMA), 0xC1);
+
+        memory.write(0xC000, 0x55); //dropped
+        memory.tick(10);
+
+        assert_eq!(memory.read(0xFE00), 0x00);
+        assert_eq!(memory.read(0xFE9F), 0x9F);
+        assert_eq!(memory.read(0xC000), 0x00);
+    }
+
+    #[test]
+    fn test_dma_echo_source() {
+        let mut memory = Memory::new();
+        memory.write(0xDE05, 0x42);
+        memory.write(DMA, 0xFE); //0xFE00 reads through the echo to 0xDE00
+
+        for _ in 0..4 {
+            memory.tick(160);
+        }
+
+        assert_eq!(memory.read(0xFE05), 0x42);
+    }
+
+    #[test]
+    fn test_apu_registers() {
+        let mut memory = Memory::new();
+
+        memory.write(NR52, 0x80); //the boot ROM powers the APU before anything else
+        memory.write(0xFF11, 0x80);
+        memory.write(0xFF24, 0x77);
+
+        assert_eq!(memory.read(NR52), 0xF0);
+        assert_eq!(memory.read(0xFF11), 0xBF); //length is write only
+        assert_eq!(memory.read(0xFF24), 0x77);
+        assert_eq!(memory.read(0xFF15), OPEN_BUS);
+    }
+
+    #[test]
+    fn test_div_clocks_apu() {
+        let mut memory = Memory::new();
+        let tick = |memory: &mut Memory, cycles: u32| (0..cycles / 4).for_each(|_| memory.tick(4));
+
+        memory.write(NR52, 0x80);
+        memory.write(0xFF16, 0x3F); //NR21, one length clock
+        memory.write(0xFF17, 0xF0);
+        memory.write(0xFF19, 0xC0); //trigger channel 2 with length enabled
+
+        tick(&mut memory, 8188);
+        assert_eq!(memory.read(NR52), 0xF2);
+
+        tick(&mut memory, 4); //DIV bit 4 falls, step 0 clocks length
+        assert_eq!(memory.read(NR52), 0xF0);
+
+        memory.write(0xFF16, 0x3F);
+        memory.write(0xFF19, 0xC0);
+        tick(&mut memory, 8192 + 4096); //step 1 doesn't clock length, DIV bit 4 is set again
+
+        assert_eq!(memory.read(NR52), 0xF2);
+
+        memory.write(DIV, 0x00); //the reset is a falling edge, step 2
+        tick(&mut memory, 4);
+        assert_eq!(memory.read(NR52), 0xF0);
+    }
+
+    #[test]
+    fn test_joypad() {
+        let mut memory = Memory::new();
+        memory.write(P1, 0x10); //buttons
+
+        memory.set_button(Button::A, true);
+
+        assert_eq!(memory.read(P1), 0xDE);
+        assert_eq!(memory.read(INTERRUPT_FLAG), 0xE0 | Interrupt::Joypad.bit());
+
+        memory.write(INTERRUPT_FLAG, 0x00);
+        memory.write(P1, 0x30);
+        memory.write(P1, 0x10); //reselecting the buttons with A held
+
+        assert_eq!(memory.read(INTERRUPT_FLAG), 0xE0 | Interrupt::Joypad.bit());
+    }
+
+    #[test]
+    fn test_serial() {
+        let mut memory = Memory::new();
+        memory.write(SB, 0x42);
+        memory.write(SC, 0x81); //start, internal clock
+
+        for _ in 0..(4096 / 128) {
+            memory.tick(128);
+        }
+
+        assert_eq!(memory.read(SB), 0xFF); //nothing on the other end
+        assert_eq!(memory.read(SC), 0x7F);
+        assert_eq!(memory.read(INTERRUPT_FLAG), 0xE0 | Interrupt::Serial.bit());
+    }
+
+    #[test]
+    fn test_request_interrupt() {
+        let mut memory = Memory::new();
+
+        memory.request_interrupt(Interrupt::Timer);
+        memory.request_interrupt(Interrupt::Joypad);
+
+        assert_eq!(memory.read(INTERRUPT_FLAG), 0xF4);
+        assert_eq!(memory.pending_interrupts(), 0x00); //nothing enabled
+
+        memory.write(INTERRUPT_ENABLE, 0xFF);
+
+        assert_eq!(memory.pending_interrupts(), 0x14);
+    }
+}
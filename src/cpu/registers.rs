@@ -121,6 +121,7 @@ impl Registers {
         to16_bit(self.l, self.h)
     }
 
+    #[allow(clippy::wrong_self_convention)]
     pub fn from_opcode_index(&self, opcode: u8) -> u8 {
         let index = opcode % 0x08;
 
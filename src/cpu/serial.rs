@@ -0,0 +1,111 @@
+use super::interrupts::Interrupt;
+
+//https://gbdev.io/pandocs/Serial_Data_Transfer_(Link_Cable).html
+pub const SB: u16 = 0xFF01; //serial transfer data
+pub const SC: u16 = 0xFF02; //serial transfer control
+
+const SC_TRANSFER: u8 = 0x80; //set to start, cleared by the hardware when done
+const SC_INTERNAL_CLOCK: u8 = 0x01;
+const T_CYCLES_PER_BIT: u16 = 512; //8192 Hz internal clock
+const BITS_PER_TRANSFER: u8 = 8;
+
+//no link cable is attached, 1s are shifted in and an externally clocked transfer never finishes
+pub struct Serial {
+    sb: u8,
+    sc: u8,
+    bits: u8, //left to shift in the running transfer
+    cycles: u16 //t-cycles into the current bit
+}
+
+impl Default for Serial {
+    fn default() -> Self {
+        Serial::new()
+    }
+}
+
+impl Serial {
+    pub fn new() -> Serial {
+        Serial {
+            sb: 0,
+            sc: 0,
+            bits: 0,
+            cycles: 0
+        }
+    }
+
+    pub fn read(&self, addr: u16) -> u8 {
+        match addr {
+            SB => self.sb,
+            SC => self.sc,
+            _ => unreachable!("{:#06x} is not a serial register", addr)
+        }
+    }
+
+    pub fn write(&mut self, addr: u16, value: u8) {
+        match addr {
+            SB => self.sb = value,
+            SC => {
+                self.sc = value;
+                self.bits = if value & SC_TRANSFER != 0 { BITS_PER_TRANSFER } else { 0 };
+                self.cycles = 0;
+            },
+            _ => unreachable!("{:#06x} is not a serial register", addr)
+        }
+    }
+
+    //advance by t-cycles, returning the IF bits of the interrupts raised along the way
+    pub fn tick(&mut self, t_cycles: u8) -> u8 {
+        if self.bits == 0 || self.sc & SC_INTERNAL_CLOCK == 0 {
+            return 0;
+        }
+
+        self.cycles += t_cycles as u16;
+
+        while self.cycles >= T_CYCLES_PER_BIT && self.bits > 0 {
+            self.cycles -= T_CYCLES_PER_BIT;
+            self.sb = (self.sb << 1) | 0x01;
+            self.bits -= 1;
+        }
+
+        if self.bits > 0 {
+            return 0;
+        }
+
+        self.sc &= !SC_TRANSFER;
+        Interrupt::Serial.bit()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_internal_clock_transfer() {
+        let mut serial = Serial::new();
+        serial.write(SB, 0x0F);
+        serial.write(SC, SC_TRANSFER | SC_INTERNAL_CLOCK);
+
+        for _ in 0..(T_CYCLES_PER_BIT * BITS_PER_TRANSFER as u16 / 4 - 1) { //one m-cycle short of 8 bits
+            assert_eq!(0, serial.tick(4));
+        }
+
+        assert_eq!(0xFF, serial.read(SB)); //the 0x0F has been shifted out for 1s
+        assert_eq!(SC_TRANSFER, serial.read(SC) & SC_TRANSFER);
+        assert_eq!(Interrupt::Serial.bit(), serial.tick(4));
+        assert_eq!(0, serial.read(SC) & SC_TRANSFER);
+        assert_eq!(0, serial.tick(4));
+    }
+
+    #[test]
+    fn test_external_clock_waits() {
+        let mut serial = Serial::new();
+        serial.write(SC, SC_TRANSFER);
+
+        for _ in 0..10000 {
+            assert_eq!(0, serial.tick(4));
+        }
+
+        assert_eq!(SC_TRANSFER, serial.read(SC));
+    }
+}
@@ -0,0 +1,259 @@
+use super::interrupts::Interrupt;
+
+//https://gbdev.io/pandocs/Timer_and_Divider_Registers.html
+pub const DIV: u16 = 0xFF04; //upper 8 bits of the internal divider
+pub const TIMA: u16 = 0xFF05; //timer counter
+pub const TMA: u16 = 0xFF06; //timer modulo, reloaded into TIMA on overflow
+pub const TAC: u16 = 0xFF07; //timer control
+
+const TAC_ENABLE: u8 = 0x04;
+const TAC_CLOCK_SELECT: u8 = 0x03;
+const RELOAD_DELAY: u8 = 4; //TIMA reads 0x00 for one m-cycle after overflowing before TMA is loaded
+const DIV_APU_BIT: u16 = 1 << 12; //bit 4 of DIV, its falling edge clocks the APU frame sequencer at 512Hz
+
+pub struct Timer {
+    counter: u16, //internal divider, incremented every t-cycle
+    tima: u8,
+    tma: u8,
+    tac: u8,
+    reload: Option<u8>, //t-cycles left until an overflowed TIMA is reloaded from TMA
+    div_apu: u8 //DIV-APU edges not yet passed on to the APU
+}
+
+impl Default for Timer {
+    fn default() -> Self {
+        Timer::new()
+    }
+}
+
+impl Timer {
+    pub fn new() -> Timer {
+        Timer {
+            counter: 0,
+            tima: 0,
+            tma: 0,
+            tac: 0,
+            reload: Option::None,
+            div_apu: 0
+        }
+    }
+
+    pub fn read(&self, addr: u16) -> u8 {
+        match addr {
+            DIV => (self.counter >> 8) as u8,
+            TIMA => self.tima,
+            TMA => self.tma,
+            TAC => self.tac,
+            _ => unreachable!("{:#06x} is not a timer register", addr)
+        }
+    }
+
+    //changing DIV or TAC can drop the selected divider bit, which the edge detector sees as a tick
+    pub fn write(&mut self, addr: u16, value: u8) {
+        let signal = self.signal();
+
+        match addr {
+            DIV => {
+                if self.counter & DIV_APU_BIT != 0 { //resetting the divider with the bit set is a falling edge too
+                    self.div_apu += 1;
+                }
+
+                self.counter = 0;
+            },
+            TIMA => {
+                self.tima = value;
+                self.reload = Option::None; //writing during the delay cancels the reload
+            },
+            TMA => self.tma = value,
+            TAC => self.tac = value,
+            _ => unreachable!("{:#06x} is not a timer register", addr)
+        }
+
+        if signal && !self.signal() {
+            self.increment();
+        }
+    }
+
+    //advance by t-cycles, returning the IF bits of the interrupts raised along the way
+    pub fn tick(&mut self, t_cycles: u8) -> u8 {
+        let mut interrupts = 0;
+
+        for _ in 0..t_cycles {
+            if let Option::Some(remaining) = self.reload {
+                if remaining <= 1 {
+                    self.reload = Option::None;
+                    self.tima = self.tma;
+                    interrupts |= Interrupt::Timer.bit();
+                } else {
+                    self.reload = Option::Some(remaining - 1);
+                }
+            }
+
+            let signal = self.signal();
+            let counter = self.counter;
+            self.counter = self.counter.wrapping_add(1);
+
+            if counter & !self.counter & DIV_APU_BIT != 0 {
+                self.div_apu += 1;
+            }
+
+            if signal && !self.signal() { //falling edge
+                self.increment();
+            }
+        }
+
+        interrupts
+    }
+
+    //frame sequencer clocks due since the last call
+    pub fn take_div_apu(&mut self) -> u8 {
+        std::mem::take(&mut self.div_apu)
+    }
+
+    //the divider bit TAC selects, AND the enable bit
+    fn signal(&self) -> bool {
+        let bit = match self.tac & TAC_CLOCK_SELECT {
+            0b00 => 9, //4096 Hz
+            0b01 => 3, //262144 Hz
+            0b10 => 5, //65536 Hz
+            _ => 7 //16384 Hz
+        };
+
+        (self.tac & TAC_ENABLE) != 0 && (self.counter & (1 << bit)) != 0
+    }
+
+    fn increment(&mut self) {
+        let (tima, overflow) = self.tima.overflowing_add(1);
+        self.tima = tima;
+
+        if overflow {
+            self.reload = Option::Some(RELOAD_DELAY);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_div() {
+        let mut timer = Timer::new();
+
+        timer.tick(255);
+        assert_eq!(0x00, timer.read(DIV));
+
+        timer.tick(1);
+        assert_eq!(0x01, timer.read(DIV));
+
+        timer.write(DIV, 0xAB); //any write resets the whole counter
+        assert_eq!(0x00, timer.read(DIV));
+        assert_eq!(0x0000, timer.counter);
+    }
+
+    #[test]
+    fn test_div_apu() {
+        let mut timer = Timer::new();
+
+        for _ in 0..0x1F {
+            timer.tick(128);
+            timer.tick(128);
+        }
+
+        timer.tick(255);
+        assert_eq!(0, timer.take_div_apu());
+
+        timer.tick(1); //DIV goes from 0x1F to 0x20, bit 4 falls
+        assert_eq!(1, timer.take_div_apu());
+        assert_eq!(0, timer.take_div_apu());
+
+        for _ in 0..0x10 {
+            timer.tick(128);
+            timer.tick(128);
+        }
+
+        timer.write(DIV, 0x00); //bit 4 was set
+        assert_eq!(1, timer.take_div_apu());
+
+        timer.write(DIV, 0x00);
+        assert_eq!(0, timer.take_div_apu());
+    }
+
+    #[test]
+    fn test_tima_frequency() {
+        let mut timer = Timer::new();
+
+        timer.write(TAC, 0x05); //enabled, every 16 t-cycles
+        timer.tick(15);
+        assert_eq!(0x00, timer.read(TIMA));
+
+        timer.tick(1);
+        assert_eq!(0x01, timer.read(TIMA));
+
+        timer.tick(32);
+        assert_eq!(0x03, timer.read(TIMA));
+
+        timer.write(TAC, 0x01); //disabled
+        timer.tick(64);
+        assert_eq!(0x03, timer.read(TIMA));
+    }
+
+    #[test]
+    fn test_overflow_reload() {
+        let mut timer = Timer::new();
+
+        timer.write(TMA, 0xF0);
+        timer.write(TIMA, 0xFF);
+        timer.write(TAC, 0x05);
+
+        assert_eq!(0, timer.tick(16));
+        assert_eq!(0x00, timer.read(TIMA)); //reload is delayed by one m-cycle
+
+        assert_eq!(0, timer.tick(3));
+        assert_eq!(0x00, timer.read(TIMA));
+
+        assert_eq!(Interrupt::Timer.bit(), timer.tick(1));
+        assert_eq!(0xF0, timer.read(TIMA));
+    }
+
+    #[test]
+    fn test_overflow_reload_cancelled() {
+        let mut timer = Timer::new();
+
+        timer.write(TMA, 0xF0);
+        timer.write(TIMA, 0xFF);
+        timer.write(TAC, 0x05);
+
+        timer.tick(16);
+        timer.write(TIMA, 0x42); //written during the delay, no reload or interrupt
+
+        assert_eq!(0, timer.tick(4));
+        assert_eq!(0x42, timer.read(TIMA));
+    }
+
+    #[test]
+    fn test_div_write_glitch() {
+        let mut timer = Timer::new();
+
+        timer.write(TAC, 0x05); //bit 3 of the divider
+        timer.tick(8); //bit 3 is now set
+
+        timer.write(DIV, 0x00); //resetting drops the bit, TIMA sees a falling edge
+        assert_eq!(0x01, timer.read(TIMA));
+
+        timer.tick(4);
+        timer.write(DIV, 0x00); //bit 3 was clear, no extra increment
+        assert_eq!(0x01, timer.read(TIMA));
+    }
+
+    #[test]
+    fn test_tac_write_glitch() {
+        let mut timer = Timer::new();
+
+        timer.write(TAC, 0x05);
+        timer.tick(8);
+
+        timer.write(TAC, 0x00); //disabling while the selected bit is set increments TIMA
+        assert_eq!(0x01, timer.read(TIMA));
+    }
+}
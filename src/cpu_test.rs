@@ -1,10 +1,14 @@
 use std::collections::HashMap;
 
-use super::CPU;
+use super::{CPU, CpuError, IllegalOpcodePolicy};
+use crate::joypad::Button;
 use crate::cpu::{
     flags::is_half_carry_subtract,
+    interrupts::{Interrupt, INTERRUPT_ENABLE, INTERRUPT_FLAG},
+    memory::Bus,
     registers::to16_bit,
-    ImeStatus, util::BINARY_BASE
+    ImeStatus, PowerStatus, util::BINARY_BASE,
+    instructions::{execute, is_illegal}
 };
 
 const PROGRAM_COUNTER: u16 = 0;
@@ -40,8 +44,373 @@ fn test_memory_map() {
         vec![0x10, 0x20, 0x30, 0x40]
     );
 
-    assert_eq!(cpu.memory[0xC001], 0x10);
-    assert_eq!(cpu.memory[0xC004], 0x40);
+    assert_eq!(cpu.memory.read(0xC001), 0x10);
+    assert_eq!(cpu.memory.read(0xC004), 0x40);
+}
+
+#[test]
+fn test_boot_rom_unmap() {
+    let mut cpu = prepare_cpu();
+
+    cpu.memory_map(0x0000..0x0004, vec![0x00, 0x00, 0x00, 0xC3]); //cartridge
+    cpu.map_boot_rom(vec![0xE0, 0x50, 0x00]); //LDH [$FF50], A
+    cpu.registers.a = 0x01;
+
+    assert_eq!(0xE0, cpu.memory.read(0x0000));
+
+    cpu.step().unwrap();
+
+    assert!(!cpu.is_boot_rom_mapped());
+    assert_eq!(2, cpu.registers.program_counter);
+    assert_eq!(0x00, cpu.memory.read(0x0000));
+    assert_eq!(0xC3, cpu.memory.read(0x0003));
+}
+
+#[test]
+fn test_interrupt_dispatch() {
+    let mut cpu = prepare_cpu();
+
+    cpu.registers.program_counter = 0x1234;
+    cpu.registers.stack_pointer = 0xFFFE;
+    cpu.ime = ImeStatus::SET;
+    cpu.memory.write(INTERRUPT_ENABLE, 0x01);
+    cpu.request_interrupt(Interrupt::VBlank);
+
+    let t_states = cpu.step().unwrap();
+
+    assert_eq!(20, t_states);
+    assert_eq!(0x40, cpu.registers.program_counter);
+    assert_eq!(0xFFFC, cpu.registers.stack_pointer);
+    assert_eq!(0x12, cpu.memory.read(0xFFFD));
+    assert_eq!(0x34, cpu.memory.read(0xFFFC));
+    assert_eq!(0x00, cpu.memory.read(INTERRUPT_FLAG) & 0x1F);
+    assert!(matches!(cpu.ime, ImeStatus::UNSET));
+}
+
+#[test]
+fn test_interrupt_priority() {
+    let mut cpu = prepare_cpu();
+
+    cpu.registers.stack_pointer = 0xFFFE;
+    cpu.ime = ImeStatus::SET;
+    cpu.memory.write(INTERRUPT_ENABLE, 0x1F);
+    cpu.request_interrupt(Interrupt::Joypad);
+    cpu.request_interrupt(Interrupt::Timer);
+
+    cpu.step().unwrap();
+
+    assert_eq!(0x50, cpu.registers.program_counter);
+    assert_eq!(0x10, cpu.memory.read(INTERRUPT_FLAG) & 0x1F); //joypad still pending
+}
+
+#[test]
+fn test_interrupt_not_serviced() {
+    let mut cpu = prepare_cpu();
+
+    cpu.memory.write(INTERRUPT_ENABLE, 0x1F);
+    cpu.request_interrupt(Interrupt::Stat);
+
+    cpu.step().unwrap(); //IME unset, executes the nop at 0x0000
+
+    assert_eq!(1, cpu.registers.program_counter);
+    assert_eq!(0x02, cpu.memory.read(INTERRUPT_FLAG) & 0x1F);
+
+    cpu.ime = ImeStatus::SET;
+    cpu.memory.write(INTERRUPT_ENABLE, 0x1D); //stat masked
+
+    cpu.step().unwrap();
+
+    assert_eq!(2, cpu.registers.program_counter);
+}
+
+#[test]
+fn test_interrupt_after_ei() {
+    let mut cpu = prepare_cpu();
+
+    cpu.registers.stack_pointer = 0xFFFE;
+    cpu.memory_map(0x0000..0x0002, vec![0xFB, 0x00]); //EI, nop
+    cpu.memory.write(INTERRUPT_ENABLE, 0x04);
+    cpu.request_interrupt(Interrupt::Timer);
+
+    cpu.step().unwrap(); //EI
+    assert_eq!(1, cpu.registers.program_counter);
+
+    cpu.step().unwrap(); //IME only takes effect after the instruction following EI
+    assert_eq!(2, cpu.registers.program_counter);
+
+    cpu.step().unwrap();
+    assert_eq!(0x50, cpu.registers.program_counter);
+}
+
+#[test]
+fn test_halt_ime_set() {
+    let mut cpu = prepare_cpu();
+
+    cpu.registers.stack_pointer = 0xFFFE;
+    cpu.ime = ImeStatus::SET;
+    cpu.memory_map(0x0000..0x0002, vec![0x76, 0x3C]); //HALT, INC A
+    cpu.memory.write(INTERRUPT_ENABLE, 0x04);
+
+    cpu.step().unwrap();
+    assert!(matches!(cpu.power, PowerStatus::HALTED));
+    assert_eq!(1, cpu.registers.program_counter);
+
+    cpu.step().unwrap(); //nothing pending, stays halted
+    assert!(matches!(cpu.power, PowerStatus::HALTED));
+    assert_eq!(1, cpu.registers.program_counter);
+
+    cpu.request_interrupt(Interrupt::Timer);
+    cpu.step().unwrap(); //wakes and services
+
+    assert!(matches!(cpu.power, PowerStatus::RUNNING));
+    assert_eq!(0x50, cpu.registers.program_counter);
+    assert_eq!(0x01, cpu.memory.read(0xFFFC)); //returns to the instruction after HALT
+    assert_eq!(0x00, cpu.registers.a);
+}
+
+#[test]
+fn test_halt_ime_unset() {
+    let mut cpu = prepare_cpu();
+
+    cpu.memory_map(0x0000..0x0002, vec![0x76, 0x3C]); //HALT, INC A
+    cpu.memory.write(INTERRUPT_ENABLE, 0x01);
+
+    cpu.step().unwrap();
+    assert!(matches!(cpu.power, PowerStatus::HALTED));
+
+    cpu.request_interrupt(Interrupt::VBlank);
+    cpu.step().unwrap(); //wakes without servicing and carries on
+
+    assert!(matches!(cpu.power, PowerStatus::RUNNING));
+    assert_eq!(2, cpu.registers.program_counter);
+    assert_eq!(0x01, cpu.registers.a);
+    assert_eq!(0x01, cpu.memory.read(INTERRUPT_FLAG) & 0x1F);
+}
+
+#[test]
+fn test_halt_bug() {
+    let mut cpu = prepare_cpu();
+
+    cpu.memory_map(0x0000..0x0002, vec![0x76, 0x3C]); //HALT, INC A
+    cpu.memory.write(INTERRUPT_ENABLE, 0x01);
+    cpu.request_interrupt(Interrupt::VBlank);
+
+    cpu.step().unwrap(); //interrupt already pending with IME unset, HALT exits immediately
+    assert!(matches!(cpu.power, PowerStatus::RUNNING));
+    assert_eq!(1, cpu.registers.program_counter);
+
+    cpu.step().unwrap(); //PC fails to increment, INC A is executed twice
+    assert_eq!(1, cpu.registers.program_counter);
+
+    cpu.step().unwrap();
+    assert_eq!(2, cpu.registers.program_counter);
+    assert_eq!(0x02, cpu.registers.a);
+}
+
+#[test]
+fn test_halt_bug_operand() {
+    let mut cpu = prepare_cpu();
+
+    cpu.memory_map(0x0000..0x0003, vec![0x76, 0x3E, 0x14]); //HALT, LD A, n8
+    cpu.memory.write(INTERRUPT_ENABLE, 0x01);
+    cpu.request_interrupt(Interrupt::VBlank);
+
+    cpu.step().unwrap();
+    cpu.step().unwrap(); //the opcode byte is read again as the operand
+
+    assert_eq!(0x3E, cpu.registers.a);
+    assert_eq!(2, cpu.registers.program_counter);
+
+    cpu.step().unwrap(); //0x14 now runs as INC D
+    assert_eq!(0x01, cpu.registers.d);
+}
+
+#[test]
+fn test_timer_interrupt_wakes_halt() {
+    let mut cpu = prepare_cpu();
+
+    cpu.registers.stack_pointer = 0xFFFE;
+    cpu.ime = ImeStatus::SET;
+    cpu.memory_map(0x0000..0x0002, vec![0x00, 0x76]); //nop, HALT
+    cpu.memory.write(INTERRUPT_ENABLE, 0x04);
+    cpu.memory.write(0xFF05, 0xFF); //TIMA about to overflow
+    cpu.memory.write(0xFF07, 0x05); //every 16 t-cycles
+
+    let mut steps = 0;
+
+    while cpu.registers.program_counter != 0x50 {
+        cpu.step().unwrap();
+        steps += 1;
+
+        assert!(steps < 16, "timer interrupt never serviced");
+    }
+
+    assert_eq!(0x02, cpu.memory.read(0xFFFC)); //returns after HALT
+    assert_eq!(0x00, cpu.memory.read(INTERRUPT_FLAG) & 0x1F); //acknowledged
+}
+
+#[test]
+fn test_illegal_opcode_error() {
+    let mut cpu = prepare_cpu();
+
+    cpu.memory_map(0x0000..0x0002, vec![0x00, 0xD3]);
+
+    cpu.step().unwrap();
+
+    assert_eq!(
+        Result::Err(CpuError::IllegalOpcode { pc: 0x0001, opcode: 0xD3 }),
+        cpu.step()
+    );
+    assert_eq!(1, cpu.registers.program_counter); //left on the opcode
+}
+
+#[test]
+fn test_illegal_opcode_hang() {
+    let mut cpu = prepare_cpu();
+
+    cpu.set_illegal_opcode_policy(IllegalOpcodePolicy::HANG);
+    cpu.memory_map(0x0000..0x0003, vec![0x00, 0xFD, 0x3C]);
+    cpu.memory.write(INTERRUPT_ENABLE, 0x1F);
+    cpu.ime = ImeStatus::SET;
+
+    cpu.step().unwrap();
+    assert_eq!(Result::Ok(4), cpu.step());
+    assert!(matches!(cpu.power, PowerStatus::LOCKED));
+
+    cpu.request_interrupt(Interrupt::VBlank); //not even interrupts wake it
+    cpu.step().unwrap();
+    cpu.step().unwrap();
+
+    assert_eq!(1, cpu.registers.program_counter); //stuck on the opcode
+    assert_eq!(0x00, cpu.registers.a);
+}
+
+#[test]
+fn test_illegal_opcode_break() {
+    let mut cpu = prepare_cpu();
+
+    cpu.set_illegal_opcode_policy(IllegalOpcodePolicy::BREAK);
+    cpu.memory_map(0x0000..0x0002, vec![0xEC, 0x3C]);
+
+    assert_eq!(
+        Result::Err(CpuError::Breakpoint { pc: 0x0000, opcode: 0xEC }),
+        cpu.step()
+    );
+
+    cpu.step().unwrap(); //resumes past the opcode
+
+    assert_eq!(2, cpu.registers.program_counter);
+    assert_eq!(0x01, cpu.registers.a);
+}
+
+#[test]
+fn test_illegal_opcode_policy_parse() {
+    assert!(matches!(IllegalOpcodePolicy::parse("hang"), Option::Some(IllegalOpcodePolicy::HANG)));
+    assert!(matches!(IllegalOpcodePolicy::parse("break"), Option::Some(IllegalOpcodePolicy::BREAK)));
+    assert!(IllegalOpcodePolicy::parse("ignore").is_none());
+}
+
+#[test]
+fn test_is_illegal() {
+    let illegal: Vec<u8> = (0x00..=0xFF).filter(|op_code| is_illegal(*op_code)).collect();
+
+    assert_eq!(
+        vec![0xD3, 0xDB, 0xDD, 0xE3, 0xE4, 0xEB, 0xEC, 0xED, 0xF4, 0xFC, 0xFD],
+        illegal
+    );
+}
+
+#[test]
+fn test_cycle_counter() {
+    let mut cpu = prepare_cpu();
+
+    cpu.registers.stack_pointer = 0xFFFE;
+    cpu.memory_map(0x0000..0x0004, vec![0x00, 0x20, 0x02, 0xFB]); //nop, JR NZ +2, EI
+    cpu.memory.write(INTERRUPT_ENABLE, 0x01);
+
+    assert_eq!(Result::Ok(8), cpu.step()); //initial fetch at PC_START isn't overlapped
+    assert_eq!(Result::Ok(12), cpu.step()); //branch taken
+    assert_eq!(5, cpu.registers.program_counter);
+    assert_eq!(20, cpu.cycles());
+
+    cpu.ime = ImeStatus::SET;
+    cpu.request_interrupt(Interrupt::VBlank);
+
+    assert_eq!(Result::Ok(20), cpu.step());
+    assert_eq!(40, cpu.cycles());
+
+    cpu.flags.zero = true;
+    cpu.registers.program_counter = 0x0001;
+    cpu.step().unwrap(); //branch not taken
+
+    assert_eq!(48, cpu.cycles());
+}
+
+//m-cycles per opcode with branches not taken, https://gbdev.io/gb-opcodes/optables/
+const M_CYCLES: [u8; 256] = [
+    1, 3, 2, 2, 1, 1, 2, 1, 5, 2, 2, 2, 1, 1, 2, 1,
+    1, 3, 2, 2, 1, 1, 2, 1, 3, 2, 2, 2, 1, 1, 2, 1,
+    2, 3, 2, 2, 1, 1, 2, 1, 2, 2, 2, 2, 1, 1, 2, 1,
+    2, 3, 2, 2, 3, 3, 3, 1, 2, 2, 2, 2, 1, 1, 2, 1,
+    1, 1, 1, 1, 1, 1, 2, 1, 1, 1, 1, 1, 1, 1, 2, 1,
+    1, 1, 1, 1, 1, 1, 2, 1, 1, 1, 1, 1, 1, 1, 2, 1,
+    1, 1, 1, 1, 1, 1, 2, 1, 1, 1, 1, 1, 1, 1, 2, 1,
+    2, 2, 2, 2, 2, 2, 1, 2, 1, 1, 1, 1, 1, 1, 2, 1,
+    1, 1, 1, 1, 1, 1, 2, 1, 1, 1, 1, 1, 1, 1, 2, 1,
+    1, 1, 1, 1, 1, 1, 2, 1, 1, 1, 1, 1, 1, 1, 2, 1,
+    1, 1, 1, 1, 1, 1, 2, 1, 1, 1, 1, 1, 1, 1, 2, 1,
+    1, 1, 1, 1, 1, 1, 2, 1, 1, 1, 1, 1, 1, 1, 2, 1,
+    2, 3, 3, 4, 3, 4, 2, 4, 2, 4, 3, 0, 3, 6, 2, 4,
+    2, 3, 3, 0, 3, 4, 2, 4, 2, 4, 3, 0, 3, 0, 2, 4,
+    3, 3, 2, 0, 0, 4, 2, 4, 4, 1, 4, 0, 0, 0, 2, 4,
+    3, 3, 2, 1, 0, 4, 2, 4, 3, 2, 4, 1, 0, 0, 2, 4
+];
+
+//conditional JR, RET, JP & CALL when the branch is taken
+const M_CYCLES_TAKEN: [(u8, u8); 16] = [
+    (0x20, 3), (0x28, 3), (0x30, 3), (0x38, 3),
+    (0xC0, 5), (0xC8, 5), (0xD0, 5), (0xD8, 5),
+    (0xC2, 4), (0xCA, 4), (0xD2, 4), (0xDA, 4),
+    (0xC4, 6), (0xCC, 6), (0xD4, 6), (0xDC, 6)
+];
+
+#[test]
+fn test_t_states() {
+    for op_code in 0x00..=0xFF {
+        if is_illegal(op_code) || op_code == 0xCB {
+            continue;
+        }
+
+        let mut cpu = prepare_cpu();
+        cpu.flags.zero = op_code & 0x08 == 0; //conditions fail, NZ & NC with the flag set, Z & C without
+        cpu.flags.carry = op_code & 0x08 == 0;
+
+        assert_eq!(M_CYCLES[op_code as usize] * 4, execute(&cpu, op_code).t_states, "opcode {:#04x}", op_code);
+    }
+
+    for (op_code, m_cycles) in M_CYCLES_TAKEN {
+        let mut cpu = prepare_cpu();
+        cpu.flags.zero = op_code & 0x08 != 0;
+        cpu.flags.carry = op_code & 0x08 != 0;
+
+        assert_eq!(m_cycles * 4, execute(&cpu, op_code).t_states, "opcode {:#04x} taken", op_code);
+    }
+}
+
+#[test]
+fn test_prefixed_t_states() {
+    for op_code in 0x00..=0xFF {
+        let mut cpu = prepare_cpu();
+        cpu.memory.write(PROGRAM_COUNTER + 1, op_code);
+
+        let expected = match (op_code & 0x07, op_code) {
+            (0x06, 0x40..=0x7F) => 12, //BIT n, [HL]
+            (0x06, _) => 16, //read-modify-write [HL]
+            _ => 8
+        };
+
+        assert_eq!(expected, execute(&cpu, 0xCB).t_states, "opcode 0xCB {:#04x}", op_code);
+    }
 }
 
 #[test]
@@ -74,7 +443,7 @@ fn test_0x02() { //LD (BC), A
     assert_eq!(6, cpu.registers.program_counter);
     assert_eq!(100, cpu.registers.a);
     assert_eq!(cpu.registers.bc(), 0xA001);
-    assert_eq!(cpu.memory[0xA001], 100);
+    assert_eq!(cpu.memory.read(0xA001), 100);
 }
 
 #[test]
@@ -158,8 +527,8 @@ fn test_0x08() { //LD [n16], SP
     cpu.execute_with_args(0x08, Some(vec![0xFF, 0xA1]));
 
     assert_eq!(3, cpu.registers.program_counter);
-    assert_eq!(0x01, cpu.memory[0xA1FF]);
-    assert_eq!(0xC0, cpu.memory[0xA1FF + 1]);
+    assert_eq!(0x01, cpu.memory.read(0xA1FF));
+    assert_eq!(0xC0, cpu.memory.read(0xA1FF + 1));
 }
 
 #[test]
@@ -188,7 +557,7 @@ fn test_0x0A() { //LD A, [BC]
 
     cpu.registers.b = 0xA0;
     cpu.registers.c = 0xAF;
-    cpu.memory[0xA0AF] = 200;
+    cpu.memory.write(0xA0AF, 200);
 
     cpu.execute(0x0A);
 
@@ -323,6 +692,72 @@ fn test_0x10() { //STOP
     cpu.execute(0x10);
 
     assert_eq!(2, cpu.registers.program_counter);
+    assert!(cpu.is_stopped());
+    assert_eq!(0x00, cpu.memory.read(0xFF04));
+}
+
+#[test]
+fn test_stop_resume_on_joypad() {
+    let mut cpu = prepare_cpu();
+
+    cpu.memory_map(0x0000..0x0003, vec![0x10, 0x00, 0x3C]); //STOP, INC A
+
+    cpu.step().unwrap();
+    cpu.step().unwrap(); //nothing pressed, stays stopped
+    assert!(cpu.is_stopped());
+    assert_eq!(2, cpu.registers.program_counter);
+
+    cpu.request_interrupt(Interrupt::Timer); //only joypad input resumes
+    cpu.step().unwrap();
+    assert!(cpu.is_stopped());
+
+    cpu.request_interrupt(Interrupt::Joypad); //a request alone isn't a key press
+    cpu.step().unwrap();
+    assert!(cpu.is_stopped());
+
+    cpu.set_button(Button::A, true); //resumes even with IE masked
+    cpu.step().unwrap();
+
+    assert!(!cpu.is_stopped());
+    assert_eq!(3, cpu.registers.program_counter);
+    assert_eq!(0x01, cpu.registers.a);
+}
+
+#[test]
+fn test_stop_keeps_pending_joypad_interrupt() {
+    let mut cpu = prepare_cpu();
+
+    cpu.memory_map(0x0000..0x0004, vec![0x10, 0x00, 0xFB, 0x00]); //STOP, EI, NOP
+    cpu.memory.write(INTERRUPT_ENABLE, Interrupt::Joypad.bit());
+    cpu.request_interrupt(Interrupt::Joypad); //pending from before STOP, IME is off
+
+    cpu.step().unwrap();
+    cpu.step().unwrap(); //the stale request doesn't wake it
+
+    assert!(cpu.is_stopped());
+    assert_eq!(Interrupt::Joypad.bit(), cpu.memory.read(INTERRUPT_FLAG) & 0x1F); //left untouched
+
+    cpu.set_button(Button::B, true);
+    cpu.step().unwrap(); //EI
+    cpu.step().unwrap(); //NOP
+    cpu.step().unwrap(); //serviced
+
+    assert_eq!(0x60, cpu.registers.program_counter);
+    assert_eq!(0x00, cpu.memory.read(INTERRUPT_FLAG) & 0x1F);
+}
+
+#[test]
+fn test_stop_resume_on_button() {
+    let mut cpu = prepare_cpu();
+
+    cpu.memory_map(0x0000..0x0003, vec![0x10, 0x00, 0x3C]); //STOP, INC A
+
+    cpu.step().unwrap();
+    cpu.set_button(Button::START, true);
+    cpu.step().unwrap();
+
+    assert!(!cpu.is_stopped());
+    assert_eq!(0x01, cpu.registers.a);
 }
 
 #[test]
@@ -346,7 +781,7 @@ fn test_0x12() { //LD (DE), A
     assert_eq!(6, cpu.registers.program_counter);
     assert_eq!(100, cpu.registers.a);
     assert_eq!(cpu.registers.de(), 0xA001);
-    assert_eq!(cpu.memory[0xA001], 100);
+    assert_eq!(cpu.memory.read(0xA001), 100);
 }
 
 #[test]
@@ -478,7 +913,7 @@ fn test_0x1A() { //LD A, [DE]
 
     cpu.registers.d = 0xA0;
     cpu.registers.e = 0xAF;
-    cpu.memory[0xA0AF] = 200;
+    cpu.memory.write(0xA0AF, 200);
 
     cpu.execute(0x1A);
 
@@ -662,7 +1097,7 @@ fn test_0x22() { //LD (HL+), A
     assert_eq!(6, cpu.registers.program_counter);
     assert_eq!(cpu.registers.a, 100);
     assert_eq!(cpu.registers.hl(), 0xC002);
-    assert_eq!(cpu.memory[0xC001], 100);
+    assert_eq!(cpu.memory.read(0xC001), 100);
 }
 
 #[test]
@@ -786,7 +1221,7 @@ fn test_0x29() { //ADD HL, HL
 fn test_0x2A() { //LD A, (HL+)
     let mut cpu = prepare_cpu();
 
-    cpu.memory[0xC001] = 0x24;
+    cpu.memory.write(0xC001, 0x24);
     cpu.registers.h = 0xC0;
     cpu.registers.l = 0x01;
 
@@ -917,7 +1352,7 @@ fn test_0x32() { //LD (HL-), A
     assert_eq!(6, cpu.registers.program_counter);
     assert_eq!(cpu.registers.a, 100);
     assert_eq!(cpu.registers.hl(), 0xC000);
-    assert_eq!(cpu.memory[0xC001], 100);
+    assert_eq!(cpu.memory.read(0xC001), 100);
 }
 
 #[test]
@@ -937,12 +1372,12 @@ fn test_0x34() { //INC (HL)
 
     cpu.registers.h = 0xC0;
     cpu.registers.l = 0x01;
-    cpu.memory[0xC001] = 0x30;
+    cpu.memory.write(0xC001, 0x30);
 
     cpu.execute(0x34);
 
     assert_eq!(1, cpu.registers.program_counter);
-    assert_eq!(0x31, cpu.memory[0xC001]);
+    assert_eq!(0x31, cpu.memory.read(0xC001));
     assert_eq!(0xC001, cpu.registers.hl());
 }
 
@@ -952,12 +1387,12 @@ fn test_0x35() { //DEC (HL)
 
     cpu.registers.h = 0xC0;
     cpu.registers.l = 0x01;
-    cpu.memory[0xC001] = 0x30;
+    cpu.memory.write(0xC001, 0x30);
 
     cpu.execute(0x35);
 
     assert_eq!(1, cpu.registers.program_counter);
-    assert_eq!(0x2F, cpu.memory[0xC001]);
+    assert_eq!(0x2F, cpu.memory.read(0xC001));
     assert_eq!(0xC001, cpu.registers.hl());
 }
 
@@ -970,7 +1405,7 @@ fn test_0x36() { //LD (HL), u8
     cpu.execute_with_args(0x36, Some(vec![0x2F]));
 
     assert_eq!(2, cpu.registers.program_counter);
-    assert_eq!(0x2F, cpu.memory[0xC001]);
+    assert_eq!(0x2F, cpu.memory.read(0xC001));
 }
 
 #[test]
@@ -1036,7 +1471,7 @@ fn test_0x39() { //ADD HL, SP
 fn test_0x3A() { //LD A, (HL-)
     let mut cpu = prepare_cpu();
 
-    cpu.memory[0xC001] = 0x24;
+    cpu.memory.write(0xC001, 0x24);
     cpu.registers.h = 0xC0;
     cpu.registers.l = 0x01;
 
@@ -1143,7 +1578,7 @@ fn test_ld_register_to_register() { //all `LD r, r` instructions
             };
 
             if expected != 0 {
-                cpu.execute(opcode as u8);
+                cpu.execute(opcode);
 
                 let actual = {
                     let src = get_register(
@@ -1205,7 +1640,7 @@ fn test_ld_register_to_hl_absolute() {
         let expected = {
             let reg = get_register(&mut cpu, opcode % 0x08);
 
-            if let Option::None = reg {
+            if reg.is_none() {
                 continue;
             }
 
@@ -1214,13 +1649,13 @@ fn test_ld_register_to_hl_absolute() {
             *reg
         };
 
-        let addr = cpu.registers.hl() as usize;
+        let addr = cpu.registers.hl();
 
-        cpu.memory[addr] = 0;
+        cpu.memory.write(addr, 0);
         cpu.execute(opcode);
 
         assert_eq!(
-            cpu.memory[addr],
+            cpu.memory.read(addr),
             expected,
             "executing {:#02x}",
             opcode
@@ -1232,7 +1667,7 @@ fn test_ld_register_to_hl_absolute() {
 fn test_0x46() { //LD B, [HL]
     let mut cpu = prepare_cpu();
 
-    cpu.memory[0xC001] = 0x42;
+    cpu.memory.write(0xC001, 0x42);
     cpu.registers.h = 0xC0;
     cpu.registers.l = 0x01;
 
@@ -1247,7 +1682,7 @@ fn test_0x46() { //LD B, [HL]
 fn test_0x4E() { //LD C, [HL]
     let mut cpu = prepare_cpu();
 
-    cpu.memory[0xC001] = 0x42;
+    cpu.memory.write(0xC001, 0x42);
     cpu.registers.h = 0xC0;
     cpu.registers.l = 0x01;
 
@@ -1262,7 +1697,7 @@ fn test_0x4E() { //LD C, [HL]
 fn test_0x56() { //LD D, [HL]
     let mut cpu = prepare_cpu();
 
-    cpu.memory[0xC001] = 0x42;
+    cpu.memory.write(0xC001, 0x42);
     cpu.registers.h = 0xC0;
     cpu.registers.l = 0x01;
 
@@ -1277,7 +1712,7 @@ fn test_0x56() { //LD D, [HL]
 fn test_0x5E() { //LD E, [HL]
     let mut cpu = prepare_cpu();
 
-    cpu.memory[0xC001] = 0x42;
+    cpu.memory.write(0xC001, 0x42);
     cpu.registers.h = 0xC0;
     cpu.registers.l = 0x01;
 
@@ -1292,7 +1727,7 @@ fn test_0x5E() { //LD E, [HL]
 fn test_0x66() { //LD H, [HL]
     let mut cpu = prepare_cpu();
 
-    cpu.memory[0xC001] = 0x42;
+    cpu.memory.write(0xC001, 0x42);
     cpu.registers.h = 0xC0;
     cpu.registers.l = 0x01;
 
@@ -1307,7 +1742,7 @@ fn test_0x66() { //LD H, [HL]
 fn test_0x6E() { //LD L, [HL]
     let mut cpu = prepare_cpu();
 
-    cpu.memory[0xC001] = 0x42;
+    cpu.memory.write(0xC001, 0x42);
     cpu.registers.h = 0xC0;
     cpu.registers.l = 0x01;
 
@@ -1322,7 +1757,7 @@ fn test_0x6E() { //LD L, [HL]
 fn test_0x7E() { //LD A, [HL]
     let mut cpu = prepare_cpu();
 
-    cpu.memory[0xC001] = 0x42;
+    cpu.memory.write(0xC001, 0x42);
     cpu.registers.h = 0xC0;
     cpu.registers.l = 0x01;
 
@@ -1344,7 +1779,7 @@ fn test_register_add_to_a() { //ADD A, r
             _ => {
                 let reg = get_register(&mut cpu, opcode % 0x08);
 
-                if let Option::None = reg {
+                if reg.is_none() {
                     continue;
                 }
 
@@ -1373,7 +1808,7 @@ fn test_0x86() { //ADD A, [HL]
     cpu.registers.a = 0x0A;
     cpu.registers.h = 0xC0;
     cpu.registers.l = 0x01;
-    cpu.memory[0xC001] = 0x14;
+    cpu.memory.write(0xC001, 0x14);
 
     cpu.execute(0x86);
 
@@ -1392,7 +1827,7 @@ fn test_register_adc_to_a() { //ADC A, r
             _ => {
                 let reg = get_register(&mut cpu, opcode % 0x08);
 
-                if let Option::None = reg {
+                if reg.is_none() {
                     continue;
                 }
 
@@ -1424,7 +1859,7 @@ fn test_0x8E() { //ADC A, [HL]
     cpu.registers.a = 0x0A;
     cpu.registers.h = 0xC0;
     cpu.registers.l = 0x01;
-    cpu.memory[0xC001] = 0x14;
+    cpu.memory.write(0xC001, 0x14);
 
     cpu.execute(0x8E);
 
@@ -1442,7 +1877,7 @@ fn test_register_sub_from_a() { //SUB A, r
         let expected = {
             let reg = get_register(&mut cpu, opcode % 0x08);
 
-            if let Option::None = reg {
+            if reg.is_none() {
                 continue;
             }
 
@@ -1480,7 +1915,7 @@ fn test_0x96() { //SUB A, [HL]
     cpu.registers.a = 0x0A;
     cpu.registers.h = 0xC0;
     cpu.registers.l = 0x01;
-    cpu.memory[0xC001] = 0x02;
+    cpu.memory.write(0xC001, 0x02);
 
     cpu.execute(0x96);
 
@@ -1499,7 +1934,7 @@ fn test_register_sbc_from_a() { //SBC A, r
             _ => {
                 let reg = get_register(&mut cpu, opcode % 0x08);
 
-                if let Option::None = reg {
+                if reg.is_none() {
                     continue;
                 }
 
@@ -1531,7 +1966,7 @@ fn test_0x9E() { //SBC A, [HL]
     cpu.registers.a = 0x0A;
     cpu.registers.h = 0xC0;
     cpu.registers.l = 0x01;
-    cpu.memory[0xC001] = 0x06;
+    cpu.memory.write(0xC001, 0x06);
 
     cpu.execute(0x9E);
 
@@ -1552,7 +1987,7 @@ fn test_register_and_to_a() { //AND A, r
             _ => {
                 let reg = get_register(&mut cpu, opcode % 0x08);
 
-                if let Option::None = reg {
+                if reg.is_none() {
                     continue;
                 }
 
@@ -1588,7 +2023,7 @@ fn test_0xA6() { //AND A, [HL]
     cpu.registers.h = 0xC0;
     cpu.registers.l = 0x01;
     cpu.registers.a = 0b01010010;
-    cpu.memory[0xC001] = 0b00010011;
+    cpu.memory.write(0xC001, 0b00010011);
 
     cpu.execute(0xA6);
 
@@ -1612,7 +2047,7 @@ fn test_register_xor_to_a() { //XOR A, r
             _ => {
                 let reg = get_register(&mut cpu, opcode % 0x08);
 
-                if let Option::None = reg {
+                if reg.is_none() {
                     continue;
                 }
 
@@ -1658,7 +2093,7 @@ fn test_0xAE() { //XOR A, [HL]
     cpu.registers.h = 0xC0;
     cpu.registers.l = 0x01;
     cpu.registers.a = 0b01010010;
-    cpu.memory[0xC001] = 0b00010011;
+    cpu.memory.write(0xC001, 0b00010011);
 
     cpu.execute(0xAE);
 
@@ -1682,7 +2117,7 @@ fn test_register_or_to_a() { //OR A, r
             _ => {
                 let reg = get_register(&mut cpu, opcode % 0x08);
 
-                if let Option::None = reg {
+                if reg.is_none() {
                     continue;
                 }
 
@@ -1718,7 +2153,7 @@ fn test_0xB6() { //OR A, [HL]
     cpu.registers.h = 0xC0;
     cpu.registers.l = 0x01;
     cpu.registers.a = 0b01010010;
-    cpu.memory[0xC001] = 0b00010011;
+    cpu.memory.write(0xC001, 0b00010011);
 
     cpu.execute(0xB6);
 
@@ -1739,7 +2174,7 @@ fn test_register_cp_to_a() { //CP A, r
         let expected = {
             let reg = get_register(&mut cpu, opcode % 0x08);
 
-            if let Option::None = reg {
+            if reg.is_none() {
                 continue;
             }
 
@@ -1763,7 +2198,7 @@ fn test_register_cp_to_a() { //CP A, r
 
         let result = TO_SUB.wrapping_sub(expected);
         assert_eq!(cpu.flags.zero, result == 0, "executing {:#02x}", opcode);
-        assert_eq!(cpu.flags.subtract, true, "executing {:#02x}", opcode);
+        assert!(cpu.flags.subtract, "executing {:#02x}", opcode);
         assert_eq!(cpu.flags.half_carry, is_half_carry_subtract(cpu.registers.a, expected), "executing {:#02x}", opcode);
         assert_eq!(cpu.flags.carry, expected > TO_SUB, "executing {:#02x}", opcode);
     }
@@ -1777,7 +2212,7 @@ fn test_0xBE() { //CP A, [HL]
     cpu.registers.a = 0x0A;
     cpu.registers.h = 0xC0;
     cpu.registers.l = 0x01;
-    cpu.memory[0xC001] = 0x02;
+    cpu.memory.write(0xC001, 0x02);
 
     cpu.execute(0xBE);
 
@@ -1791,7 +2226,7 @@ fn test_0xBE() { //CP A, [HL]
     cpu.registers.a = 0x0A;
     cpu.registers.h = 0xC0;
     cpu.registers.l = 0x01;
-    cpu.memory[0xC001] = 0x0A;
+    cpu.memory.write(0xC001, 0x0A);
 
     cpu.execute(0xBE);
 
@@ -1808,8 +2243,8 @@ fn test_0xC0() { //RET NZ
     let mut cpu = prepare_cpu();
 
     cpu.registers.stack_pointer = 0x03;
-    cpu.memory[0x04] = 0xC0;
-    cpu.memory[0x03] = 0x01;
+    cpu.memory.write(0x04, 0xC0);
+    cpu.memory.write(0x03, 0x01);
     cpu.flags.zero = true;
 
     cpu.execute(0xC0);
@@ -1830,8 +2265,8 @@ fn test_0xC1() { //POP BC
     let mut cpu = prepare_cpu();
 
     cpu.registers.stack_pointer = 0x03;
-    cpu.memory[0x04] = 0xC0;
-    cpu.memory[0x03] = 0x01;
+    cpu.memory.write(0x04, 0xC0);
+    cpu.memory.write(0x03, 0x01);
 
     cpu.execute(0xC1);
 
@@ -1877,8 +2312,8 @@ fn test_0xC4() { //CALL NZ, a16
 
     assert_eq!(0xA037, cpu.registers.program_counter);
     assert_eq!(0x05, cpu.registers.stack_pointer);
-    assert_eq!(0x00, cpu.memory[0x04]);
-    assert_eq!(0x00, cpu.memory[0x03]);
+    assert_eq!(0x00, cpu.memory.read(0x04));
+    assert_eq!(0x00, cpu.memory.read(0x03));
 
     cpu.flags.zero = false;
     cpu.registers.program_counter = 0xA034;
@@ -1887,8 +2322,8 @@ fn test_0xC4() { //CALL NZ, a16
 
     assert_eq!(0xC001, cpu.registers.program_counter);
     assert_eq!(0x03, cpu.registers.stack_pointer);
-    assert_eq!(0xA0, cpu.memory[0x04]);
-    assert_eq!(0x34 + 3, cpu.memory[0x03]); //+3 to account for the instruction and two operands
+    assert_eq!(0xA0, cpu.memory.read(0x04));
+    assert_eq!(0x34 + 3, cpu.memory.read(0x03)); //+3 to account for the instruction and two operands
 }
 
 #[test]
@@ -1904,8 +2339,8 @@ fn test_0xC5() { //PUSH BC
 
     assert_eq!(1, cpu.registers.program_counter);
     assert_eq!(0x03, cpu.registers.stack_pointer);
-    assert_eq!(0xC0, cpu.memory[0x04]);
-    assert_eq!(0x01, cpu.memory[0x03]);
+    assert_eq!(0xC0, cpu.memory.read(0x04));
+    assert_eq!(0x01, cpu.memory.read(0x03));
 }
 
 #[test]
@@ -1926,8 +2361,8 @@ fn test_0xC8() { //RET Z
     let mut cpu = prepare_cpu();
 
     cpu.registers.stack_pointer = 0x03;
-    cpu.memory[0x04] = 0xC0;
-    cpu.memory[0x03] = 0x01;
+    cpu.memory.write(0x04, 0xC0);
+    cpu.memory.write(0x03, 0x01);
     cpu.flags.zero = false;
 
     cpu.execute(0xC8);
@@ -1948,8 +2383,8 @@ fn test_0xC9() { //RET
     let mut cpu = prepare_cpu();
 
     cpu.registers.stack_pointer = 0x03;
-    cpu.memory[0x04] = 0xC0;
-    cpu.memory[0x03] = 0x01;
+    cpu.memory.write(0x04, 0xC0);
+    cpu.memory.write(0x03, 0x01);
 
     cpu.execute(0xC9);
 
@@ -1985,8 +2420,8 @@ fn test_0xCC() { //CALL Z, a16
 
     assert_eq!(0xA037, cpu.registers.program_counter);
     assert_eq!(0x05, cpu.registers.stack_pointer);
-    assert_eq!(0x00, cpu.memory[0x04]);
-    assert_eq!(0x00, cpu.memory[0x03]);
+    assert_eq!(0x00, cpu.memory.read(0x04));
+    assert_eq!(0x00, cpu.memory.read(0x03));
 
     cpu.flags.zero = true;
     cpu.registers.program_counter = 0xA034;
@@ -1995,8 +2430,8 @@ fn test_0xCC() { //CALL Z, a16
 
     assert_eq!(0xC001, cpu.registers.program_counter);
     assert_eq!(0x03, cpu.registers.stack_pointer);
-    assert_eq!(0xA0, cpu.memory[0x04]);
-    assert_eq!(0x34 + 3, cpu.memory[0x03]); //+3 to account for the instruction and two operands
+    assert_eq!(0xA0, cpu.memory.read(0x04));
+    assert_eq!(0x34 + 3, cpu.memory.read(0x03)); //+3 to account for the instruction and two operands
 }
 
 #[test]
@@ -2010,8 +2445,8 @@ fn test_0xCD() { //CALL a16
 
     assert_eq!(0xC001, cpu.registers.program_counter);
     assert_eq!(0x03, cpu.registers.stack_pointer);
-    assert_eq!(0xA0, cpu.memory[0x04]);
-    assert_eq!(0x34 + 3, cpu.memory[0x03]); //+3 to account for the instruction and two operands
+    assert_eq!(0xA0, cpu.memory.read(0x04));
+    assert_eq!(0x34 + 3, cpu.memory.read(0x03)); //+3 to account for the instruction and two operands
 }
 
 #[test]
@@ -2050,8 +2485,8 @@ fn test_rst_instructions() { //RST vec
 
         assert_eq!(to16_bit(*vector, 0x00), cpu.registers.program_counter, "executing {:#02x}", opcode);
         assert_eq!(0x03, cpu.registers.stack_pointer, "executing {:#02x}", opcode);
-        assert_eq!(0xA0, cpu.memory[0x04], "executing {:#02x}", opcode);
-        assert_eq!(0x34 + 3, cpu.memory[0x03], "executing {:#02x}", opcode); //+3 to account for the instruction and two operands
+        assert_eq!(0xA0, cpu.memory.read(0x04), "executing {:#02x}", opcode);
+        assert_eq!(0x34 + 3, cpu.memory.read(0x03), "executing {:#02x}", opcode); //+3 to account for the instruction and two operands
     }
 }
 
@@ -2061,8 +2496,8 @@ fn test_0xD0() { //RET NC
     let mut cpu = prepare_cpu();
 
     cpu.registers.stack_pointer = 0x03;
-    cpu.memory[0x04] = 0xC0;
-    cpu.memory[0x03] = 0x01;
+    cpu.memory.write(0x04, 0xC0);
+    cpu.memory.write(0x03, 0x01);
     cpu.flags.carry = true;
 
     cpu.execute(0xD0);
@@ -2083,8 +2518,8 @@ fn test_0xD1() { //POP DE
     let mut cpu = prepare_cpu();
 
     cpu.registers.stack_pointer = 0x03;
-    cpu.memory[0x04] = 0xC0;
-    cpu.memory[0x03] = 0x01;
+    cpu.memory.write(0x04, 0xC0);
+    cpu.memory.write(0x03, 0x01);
 
     cpu.execute(0xD1);
 
@@ -2120,8 +2555,8 @@ fn test_0xD4() { //CALL NC, a16
 
     assert_eq!(0xA037, cpu.registers.program_counter);
     assert_eq!(0x05, cpu.registers.stack_pointer);
-    assert_eq!(0x00, cpu.memory[0x04]);
-    assert_eq!(0x00, cpu.memory[0x03]);
+    assert_eq!(0x00, cpu.memory.read(0x04));
+    assert_eq!(0x00, cpu.memory.read(0x03));
 
     cpu.flags.carry = false;
     cpu.registers.program_counter = 0xA034;
@@ -2130,8 +2565,8 @@ fn test_0xD4() { //CALL NC, a16
 
     assert_eq!(0xC001, cpu.registers.program_counter);
     assert_eq!(0x03, cpu.registers.stack_pointer);
-    assert_eq!(0xA0, cpu.memory[0x04]);
-    assert_eq!(0x34 + 3, cpu.memory[0x03]); //+3 to account for the instruction and two operands
+    assert_eq!(0xA0, cpu.memory.read(0x04));
+    assert_eq!(0x34 + 3, cpu.memory.read(0x03)); //+3 to account for the instruction and two operands
 }
 
 #[test]
@@ -2147,8 +2582,8 @@ fn test_0xD5() { //PUSH DE
 
     assert_eq!(1, cpu.registers.program_counter);
     assert_eq!(0x03, cpu.registers.stack_pointer);
-    assert_eq!(0xC0, cpu.memory[0x04]);
-    assert_eq!(0x01, cpu.memory[0x03]);
+    assert_eq!(0xC0, cpu.memory.read(0x04));
+    assert_eq!(0x01, cpu.memory.read(0x03));
 }
 
 #[test]
@@ -2169,8 +2604,8 @@ fn test_0xD8() { //RET C
     let mut cpu = prepare_cpu();
 
     cpu.registers.stack_pointer = 0x03;
-    cpu.memory[0x04] = 0xC0;
-    cpu.memory[0x03] = 0x01;
+    cpu.memory.write(0x04, 0xC0);
+    cpu.memory.write(0x03, 0x01);
     cpu.flags.carry = false;
 
     cpu.execute(0xD8);
@@ -2191,8 +2626,8 @@ fn test_0xD9() { //RETI
     let mut cpu = prepare_cpu();
 
     cpu.registers.stack_pointer = 0x03;
-    cpu.memory[0x04] = 0xC0;
-    cpu.memory[0x03] = 0x01;
+    cpu.memory.write(0x04, 0xC0);
+    cpu.memory.write(0x03, 0x01);
 
     cpu.execute(0xD9);
 
@@ -2229,8 +2664,8 @@ fn test_0xDC() { //CALL C, a16
 
     assert_eq!(0xA037, cpu.registers.program_counter);
     assert_eq!(0x05, cpu.registers.stack_pointer);
-    assert_eq!(0x00, cpu.memory[0x04]);
-    assert_eq!(0x00, cpu.memory[0x03]);
+    assert_eq!(0x00, cpu.memory.read(0x04));
+    assert_eq!(0x00, cpu.memory.read(0x03));
 
     cpu.flags.carry = true;
     cpu.registers.program_counter = 0xA034;
@@ -2239,8 +2674,8 @@ fn test_0xDC() { //CALL C, a16
 
     assert_eq!(0xC001, cpu.registers.program_counter);
     assert_eq!(0x03, cpu.registers.stack_pointer);
-    assert_eq!(0xA0, cpu.memory[0x04]);
-    assert_eq!(0x34 + 3, cpu.memory[0x03]); //+3 to account for the instruction and two operands
+    assert_eq!(0xA0, cpu.memory.read(0x04));
+    assert_eq!(0x34 + 3, cpu.memory.read(0x03)); //+3 to account for the instruction and two operands
 }
 
 #[test]
@@ -2265,7 +2700,18 @@ fn test_0xE0() { //LDH [a8], A
     cpu.execute_with_args(0xE0, Some(vec![0xC0]));
 
     assert_eq!(cpu.registers.program_counter, 2);
-    assert_eq!(0xAF, cpu.memory[0xFFC0]);
+    assert_eq!(0xAF, cpu.memory.read(0xFFC0));
+}
+
+#[test]
+#[allow(non_snake_case)]
+fn test_0xE0_interrupt_enable() { //LDH [a8], A to IE
+    let mut cpu = prepare_cpu();
+
+    cpu.registers.a = 0x04;
+    cpu.execute_with_args(0xE0, Some(vec![0xFF]));
+
+    assert_eq!(0x04, cpu.memory.read(0xFFFF));
 }
 
 #[test]
@@ -2274,8 +2720,8 @@ fn test_0xE1() { //POP HL
     let mut cpu = prepare_cpu();
 
     cpu.registers.stack_pointer = 0x03;
-    cpu.memory[0x04] = 0xC0;
-    cpu.memory[0x03] = 0x01;
+    cpu.memory.write(0x04, 0xC0);
+    cpu.memory.write(0x03, 0x01);
 
     cpu.execute(0xE1);
 
@@ -2293,7 +2739,19 @@ fn test_0xE2() { //LDH [C], A
     cpu.execute(0xE2);
 
     assert_eq!(cpu.registers.program_counter, 1);
-    assert_eq!(0xAF, cpu.memory[0xFFC0]);
+    assert_eq!(0xAF, cpu.memory.read(0xFFC0));
+}
+
+#[test]
+#[allow(non_snake_case)]
+fn test_0xE2_interrupt_enable() { //LDH [C], A to IE
+    let mut cpu = prepare_cpu();
+
+    cpu.registers.a = 0x11;
+    cpu.registers.c = 0xFF;
+    cpu.execute(0xE2);
+
+    assert_eq!(0x11, cpu.memory.read(0xFFFF));
 }
 
 #[test]
@@ -2309,8 +2767,8 @@ fn test_0xE5() { //PUSH HL
 
     assert_eq!(1, cpu.registers.program_counter);
     assert_eq!(0x03, cpu.registers.stack_pointer);
-    assert_eq!(0xC0, cpu.memory[0x04]);
-    assert_eq!(0x01, cpu.memory[0x03]);
+    assert_eq!(0xC0, cpu.memory.read(0x04));
+    assert_eq!(0x01, cpu.memory.read(0x03));
 }
 
 #[test]
@@ -2371,7 +2829,19 @@ fn test_0xEA() { //LD [a16], A
     cpu.execute_with_args(0xEA, Some(vec![0x01, 0xC0]));
 
     assert_eq!(3, cpu.registers.program_counter);
-    assert_eq!(0xAF, cpu.memory[0xC001]);
+    assert_eq!(0xAF, cpu.memory.read(0xC001));
+}
+
+#[test]
+#[allow(non_snake_case)]
+fn test_0xEA_interrupt_enable() { //LD [a16], A to IE
+    let mut cpu = prepare_cpu();
+
+    cpu.registers.a = 0x1F;
+    cpu.execute_with_args(0xEA, Some(vec![0xFF, 0xFF]));
+
+    assert_eq!(3, cpu.registers.program_counter);
+    assert_eq!(0x1F, cpu.memory.read(0xFFFF));
 }
 
 #[test]
@@ -2391,21 +2861,32 @@ fn test_0xEE() { //XOR A, n8
 fn test_0xF0() { //LDH A, [a8]
     let mut cpu = prepare_cpu();
 
-    cpu.memory[0xFFA0] = 0x69;
+    cpu.memory.write(0xFFA0, 0x69);
     cpu.execute_with_args(0xF0, Some(vec![0xA0]));
 
     assert_eq!(cpu.registers.program_counter, 2);
     assert_eq!(cpu.registers.a, 0x69);
 }
 
+#[test]
+#[allow(non_snake_case)]
+fn test_0xF0_interrupt_enable() { //LDH A, [a8] from IE
+    let mut cpu = prepare_cpu();
+
+    cpu.memory.write(0xFFFF, 0x09);
+    cpu.execute_with_args(0xF0, Some(vec![0xFF]));
+
+    assert_eq!(cpu.registers.a, 0x09);
+}
+
 #[test]
 #[allow(non_snake_case)]
 fn test_0xF1() { //POP AF
     let mut cpu = prepare_cpu();
 
     cpu.registers.stack_pointer = 0x03;
-    cpu.memory[0x04] = 0xC0;
-    cpu.memory[0x03] = 0xA0;
+    cpu.memory.write(0x04, 0xC0);
+    cpu.memory.write(0x03, 0xA0);
 
     cpu.execute(0xF1);
 
@@ -2425,7 +2906,7 @@ fn test_0xF1() { //POP AF
 fn test_0xF2() { //LDH A, [C]
     let mut cpu = prepare_cpu();
 
-    cpu.memory[0xFFA0] = 0x69;
+    cpu.memory.write(0xFFA0, 0x69);
     cpu.registers.c = 0xA0;
     cpu.execute(0xF2);
 
@@ -2459,8 +2940,8 @@ fn test_0xF5() { //PUSH AF
 
     assert_eq!(1, cpu.registers.program_counter);
     assert_eq!(0x03, cpu.registers.stack_pointer);
-    assert_eq!(0xC0, cpu.memory[0x04]);
-    assert_eq!(0xA0, cpu.memory[0x03]);
+    assert_eq!(0xC0, cpu.memory.read(0x04));
+    assert_eq!(0xA0, cpu.memory.read(0x03));
 }
 
 #[test]
@@ -2520,13 +3001,24 @@ fn test_0xF9() { //LD SP, HL
 fn test_0xFA() { //LD A, [a16]
     let mut cpu = prepare_cpu();
 
-    cpu.memory[0xC001] = 0x69;
+    cpu.memory.write(0xC001, 0x69);
     cpu.execute_with_args(0xFA, Some(vec![0x01, 0xC0]));
 
     assert_eq!(3, cpu.registers.program_counter);
     assert_eq!(0x69, cpu.registers.a);
 }
 
+#[test]
+#[allow(non_snake_case)]
+fn test_0xFA_interrupt_enable() { //LD A, [a16] from IE
+    let mut cpu = prepare_cpu();
+
+    cpu.memory.write(0xFFFF, 0x15);
+    cpu.execute_with_args(0xFA, Some(vec![0xFF, 0xFF]));
+
+    assert_eq!(cpu.registers.a, 0x15);
+}
+
 #[test]
 #[allow(non_snake_case)]
 fn test_0xFB() { //EI
@@ -2567,7 +3059,7 @@ fn test_rlc_register() { //RLC r8
         let expected = {
             let reg = get_register(&mut cpu, opcode % 0x08);
 
-            if let Option::None = reg {
+            if reg.is_none() {
                 continue;
             }
 
@@ -2582,7 +3074,7 @@ fn test_rlc_register() { //RLC r8
         let actual = {
             let reg = get_register(&mut cpu, opcode % 0x08);
 
-            if let Option::None = reg {
+            if reg.is_none() {
                 continue;
             }
 
@@ -2608,12 +3100,12 @@ fn test_pre_0x06() { //RLC [HL]
 
     cpu.registers.h = 0xC0;
     cpu.registers.l = 0x01;
-    cpu.memory[0xC001] = 0x01;
+    cpu.memory.write(0xC001, 0x01);
 
     cpu.execute_with_args(PREFIX, Some(vec![0x06]));
 
     assert_eq!(2, cpu.registers.program_counter);
-    assert_eq!(0x02, cpu.memory[0xC001]);
+    assert_eq!(0x02, cpu.memory.read(0xC001));
 }
 
 const RRC_R_START: u8 = 0x08;
@@ -2625,7 +3117,7 @@ fn test_rrc_register() { //RRC r8
         let expected = {
             let reg = get_register(&mut cpu, opcode % 0x08);
 
-            if let Option::None = reg {
+            if reg.is_none() {
                 continue;
             }
 
@@ -2640,7 +3132,7 @@ fn test_rrc_register() { //RRC r8
         let actual = {
             let reg = get_register(&mut cpu, opcode % 0x08);
 
-            if let Option::None = reg {
+            if reg.is_none() {
                 continue;
             }
 
@@ -2667,12 +3159,12 @@ fn test_pre_0x0E() { //RRC [HL]
 
     cpu.registers.h = 0xC0;
     cpu.registers.l = 0x01;
-    cpu.memory[0xC001] = 0x02;
+    cpu.memory.write(0xC001, 0x02);
 
     cpu.execute_with_args(PREFIX, Some(vec![0x0E]));
 
     assert_eq!(2, cpu.registers.program_counter);
-    assert_eq!(0x01, cpu.memory[0xC001]);
+    assert_eq!(0x01, cpu.memory.read(0xC001));
 }
 
 const RL_R_START: u8 = 0x10;
@@ -2684,7 +3176,7 @@ fn test_rl_register() { //RL r8
         let expected = {
             let reg = get_register(&mut cpu, opcode % 0x08);
 
-            if let Option::None = reg {
+            if reg.is_none() {
                 continue;
             }
 
@@ -2701,7 +3193,7 @@ fn test_rl_register() { //RL r8
         let actual = {
             let reg = get_register(&mut cpu, opcode % 0x08);
 
-            if let Option::None = reg {
+            if reg.is_none() {
                 continue;
             }
 
@@ -2728,20 +3220,20 @@ fn test_pre_0x16() { //RL [HL]
     cpu.registers.h = 0xC0;
     cpu.registers.l = 0x01;
     cpu.flags.carry = true;
-    cpu.memory[0xC001] = 0x01;
+    cpu.memory.write(0xC001, 0x01);
 
     cpu.execute_with_args(PREFIX, Some(vec![0x16]));
 
     assert_eq!(2, cpu.registers.program_counter);
-    assert_eq!(0x03, cpu.memory[0xC001]);
+    assert_eq!(0x03, cpu.memory.read(0xC001));
     assert!(!cpu.flags.carry);
 
     cpu.flags.carry = true;
-    cpu.memory[0xC001] = 0x80;
+    cpu.memory.write(0xC001, 0x80);
 
     cpu.execute_with_args(PREFIX, Some(vec![0x16]));
 
-    assert_eq!(0x01, cpu.memory[0xC001]);
+    assert_eq!(0x01, cpu.memory.read(0xC001));
     assert!(cpu.flags.carry);
 }
 
@@ -2754,7 +3246,7 @@ fn test_rr_register() { //RR r8
         let expected = {
             let reg = get_register(&mut cpu, opcode % 0x08);
 
-            if let Option::None = reg {
+            if reg.is_none() {
                 continue;
             }
 
@@ -2771,7 +3263,7 @@ fn test_rr_register() { //RR r8
         let actual = {
             let reg = get_register(&mut cpu, opcode % 0x08);
 
-            if let Option::None = reg {
+            if reg.is_none() {
                 continue;
             }
 
@@ -2798,18 +3290,18 @@ fn test_pre_0x1E() { //RR [HL]
 
     cpu.registers.h = 0xC0;
     cpu.registers.l = 0x01;
-    cpu.memory[0xC001] = 0x02;
+    cpu.memory.write(0xC001, 0x02);
     cpu.flags.carry = true;
 
     cpu.execute_with_args(PREFIX, Some(vec![0x1E]));
 
     assert_eq!(2, cpu.registers.program_counter);
-    assert_eq!(0x81, cpu.memory[0xC001]);
+    assert_eq!(0x81, cpu.memory.read(0xC001));
     assert!(!cpu.flags.carry);
 
     cpu.execute_with_args(PREFIX, Some(vec![0x1E]));
 
-    assert_eq!(0x40, cpu.memory[0xC001]);
+    assert_eq!(0x40, cpu.memory.read(0xC001));
     assert!(cpu.flags.carry);
 }
 
@@ -2822,7 +3314,7 @@ fn test_sla_register() { //SLA r8
         let expected = {
             let reg = get_register(&mut cpu, opcode % 0x08);
 
-            if let Option::None = reg {
+            if reg.is_none() {
                 continue;
             }
 
@@ -2837,7 +3329,7 @@ fn test_sla_register() { //SLA r8
         let actual = {
             let reg = get_register(&mut cpu, opcode % 0x08);
 
-            if let Option::None = reg {
+            if reg.is_none() {
                 continue;
             }
 
@@ -2864,18 +3356,18 @@ fn test_pre_0x26() { //SLA [HL]
 
     cpu.registers.h = 0xC0;
     cpu.registers.l = 0x01;
-    cpu.memory[0xC001] = 0x02;
+    cpu.memory.write(0xC001, 0x02);
 
     cpu.execute_with_args(PREFIX, Some(vec![0x26]));
 
     assert_eq!(2, cpu.registers.program_counter);
-    assert_eq!(0x04, cpu.memory[0xC001]);
+    assert_eq!(0x04, cpu.memory.read(0xC001));
     assert!(!cpu.flags.carry);
 
-    cpu.memory[0xC001] = 0x80;
+    cpu.memory.write(0xC001, 0x80);
     cpu.execute_with_args(PREFIX, Some(vec![0x26]));
 
-    assert_eq!(0x0, cpu.memory[0xC001]);
+    assert_eq!(0x0, cpu.memory.read(0xC001));
     assert!(cpu.flags.carry);
 }
 
@@ -2888,7 +3380,7 @@ fn test_sra_register() { //SRA r8
         let expected = {
             let reg = get_register(&mut cpu, opcode % 0x08);
 
-            if let Option::None = reg {
+            if reg.is_none() {
                 continue;
             }
 
@@ -2903,7 +3395,7 @@ fn test_sra_register() { //SRA r8
         let actual = {
             let reg = get_register(&mut cpu, opcode % 0x08);
 
-            if let Option::None = reg {
+            if reg.is_none() {
                 continue;
             }
 
@@ -2930,18 +3422,18 @@ fn test_pre_0x2E() { //SRA [HL]
 
     cpu.registers.h = 0xC0;
     cpu.registers.l = 0x01;
-    cpu.memory[0xC001] = 0x02;
+    cpu.memory.write(0xC001, 0x02);
 
     cpu.execute_with_args(PREFIX, Some(vec![0x2E]));
 
     assert_eq!(2, cpu.registers.program_counter);
-    assert_eq!(0x01, cpu.memory[0xC001]);
+    assert_eq!(0x01, cpu.memory.read(0xC001));
     assert!(!cpu.flags.carry);
 
-    cpu.memory[0xC001] = 0b10000001;
+    cpu.memory.write(0xC001, 0b10000001);
     cpu.execute_with_args(PREFIX, Some(vec![0x2E]));
 
-    assert_eq!(0b11000000, cpu.memory[0xC001]);
+    assert_eq!(0b11000000, cpu.memory.read(0xC001));
     assert!(cpu.flags.carry);
 }
 
@@ -2954,7 +3446,7 @@ fn test_swap_register() { //SWAP r8
         let expected = {
             let reg = get_register(&mut cpu, opcode % 0x08);
 
-            if let Option::None = reg {
+            if reg.is_none() {
                 continue;
             }
 
@@ -2968,7 +3460,7 @@ fn test_swap_register() { //SWAP r8
         let actual = {
             let reg = get_register(&mut cpu, opcode % 0x08);
 
-            if let Option::None = reg {
+            if reg.is_none() {
                 continue;
             }
 
@@ -2995,16 +3487,16 @@ fn test_pre_0x36() { //SWAP [HL]
 
     cpu.registers.h = 0xC0;
     cpu.registers.l = 0x01;
-    cpu.memory[0xC001] = 0b00000010;
+    cpu.memory.write(0xC001, 0b00000010);
 
     cpu.execute_with_args(PREFIX, Some(vec![0x36]));
 
     assert_eq!(2, cpu.registers.program_counter);
-    assert_eq!(0b00100000, cpu.memory[0xC001]);
+    assert_eq!(0b00100000, cpu.memory.read(0xC001));
 
     cpu.execute_with_args(PREFIX, Some(vec![0x36]));
 
-    assert_eq!(0b00000010, cpu.memory[0xC001]);
+    assert_eq!(0b00000010, cpu.memory.read(0xC001));
 }
 
 const SRL_R_START: u8 = 0x38;
@@ -3016,7 +3508,7 @@ fn test_srl_register() { //SRL r8
         let expected = {
             let reg = get_register(&mut cpu, opcode % 0x08);
 
-            if let Option::None = reg {
+            if reg.is_none() {
                 continue;
             }
 
@@ -3030,7 +3522,7 @@ fn test_srl_register() { //SRL r8
         let actual = {
             let reg = get_register(&mut cpu, opcode % 0x08);
 
-            if let Option::None = reg {
+            if reg.is_none() {
                 continue;
             }
 
@@ -3059,18 +3551,18 @@ fn test_pre_0x3E() { //SRL [HL]
 
     cpu.registers.h = 0xC0;
     cpu.registers.l = 0x01;
-    cpu.memory[0xC001] = 0x02;
+    cpu.memory.write(0xC001, 0x02);
 
     cpu.execute_with_args(PREFIX, Some(vec![0x3E]));
 
     assert_eq!(2, cpu.registers.program_counter);
-    assert_eq!(0x01, cpu.memory[0xC001]);
+    assert_eq!(0x01, cpu.memory.read(0xC001));
     assert!(!cpu.flags.carry);
 
-    cpu.memory[0xC001] = 0b10000001;
+    cpu.memory.write(0xC001, 0b10000001);
     cpu.execute_with_args(PREFIX, Some(vec![0x3E]));
 
-    assert_eq!(0b01000000, cpu.memory[0xC001]);
+    assert_eq!(0b01000000, cpu.memory.read(0xC001));
     assert!(cpu.flags.carry);
 }
 
@@ -3084,7 +3576,7 @@ fn test_bit_register() { //BIT u3, r8
         let expected = {
             let reg = get_register(&mut cpu, opcode % 0x08);
 
-            if let Option::None = reg {
+            if reg.is_none() {
                 continue;
             }
 
@@ -3132,14 +3624,14 @@ fn test_pre_0x46() { //BIT 0, [HL]
 
     cpu.registers.h = 0xC0;
     cpu.registers.l = 0x01;
-    cpu.memory[0xC001] = 0b00011001;
+    cpu.memory.write(0xC001, 0b00011001);
 
     cpu.execute_with_args(PREFIX, Some(vec![0x46]));
 
     assert_eq!(2, cpu.registers.program_counter);
     assert!(!cpu.flags.zero);
 
-    cpu.memory[0xC001] = 0b00011000;
+    cpu.memory.write(0xC001, 0b00011000);
     cpu.execute_with_args(PREFIX, Some(vec![0x46]));
 
     assert!(cpu.flags.zero);
@@ -3152,14 +3644,14 @@ fn test_pre_0x4E() { //BIT 1, [HL]
 
     cpu.registers.h = 0xC0;
     cpu.registers.l = 0x01;
-    cpu.memory[0xC001] = 0b00011010;
+    cpu.memory.write(0xC001, 0b00011010);
 
     cpu.execute_with_args(PREFIX, Some(vec![0x4E]));
 
     assert_eq!(2, cpu.registers.program_counter);
     assert!(!cpu.flags.zero);
 
-    cpu.memory[0xC001] = 0b00011000;
+    cpu.memory.write(0xC001, 0b00011000);
     cpu.execute_with_args(PREFIX, Some(vec![0x4E]));
 
     assert!(cpu.flags.zero);
@@ -3172,14 +3664,14 @@ fn test_pre_0x56() { //BIT 2, [HL]
 
     cpu.registers.h = 0xC0;
     cpu.registers.l = 0x01;
-    cpu.memory[0xC001] = 0b00011100;
+    cpu.memory.write(0xC001, 0b00011100);
 
     cpu.execute_with_args(PREFIX, Some(vec![0x56]));
 
     assert_eq!(2, cpu.registers.program_counter);
     assert!(!cpu.flags.zero);
 
-    cpu.memory[0xC001] = 0b00011000;
+    cpu.memory.write(0xC001, 0b00011000);
     cpu.execute_with_args(PREFIX, Some(vec![0x56]));
 
     assert!(cpu.flags.zero);
@@ -3192,14 +3684,14 @@ fn test_pre_0x5E() { //BIT 3, [HL]
 
     cpu.registers.h = 0xC0;
     cpu.registers.l = 0x01;
-    cpu.memory[0xC001] = 0b00011000;
+    cpu.memory.write(0xC001, 0b00011000);
 
     cpu.execute_with_args(PREFIX, Some(vec![0x5E]));
 
     assert_eq!(2, cpu.registers.program_counter);
     assert!(!cpu.flags.zero);
 
-    cpu.memory[0xC001] = 0b00010000;
+    cpu.memory.write(0xC001, 0b00010000);
     cpu.execute_with_args(PREFIX, Some(vec![0x5E]));
 
     assert!(cpu.flags.zero);
@@ -3212,14 +3704,14 @@ fn test_pre_0x66() { //BIT 4, [HL]
 
     cpu.registers.h = 0xC0;
     cpu.registers.l = 0x01;
-    cpu.memory[0xC001] = 0b00011000;
+    cpu.memory.write(0xC001, 0b00011000);
 
     cpu.execute_with_args(PREFIX, Some(vec![0x66]));
 
     assert_eq!(2, cpu.registers.program_counter);
     assert!(!cpu.flags.zero);
 
-    cpu.memory[0xC001] = 0b00001000;
+    cpu.memory.write(0xC001, 0b00001000);
     cpu.execute_with_args(PREFIX, Some(vec![0x66]));
 
     assert!(cpu.flags.zero);
@@ -3232,14 +3724,14 @@ fn test_pre_0x6E() { //BIT 5, [HL]
 
     cpu.registers.h = 0xC0;
     cpu.registers.l = 0x01;
-    cpu.memory[0xC001] = 0b00111000;
+    cpu.memory.write(0xC001, 0b00111000);
 
     cpu.execute_with_args(PREFIX, Some(vec![0x6E]));
 
     assert_eq!(2, cpu.registers.program_counter);
     assert!(!cpu.flags.zero);
 
-    cpu.memory[0xC001] = 0b00001000;
+    cpu.memory.write(0xC001, 0b00001000);
     cpu.execute_with_args(PREFIX, Some(vec![0x6E]));
 
     assert!(cpu.flags.zero);
@@ -3252,14 +3744,14 @@ fn test_pre_0x76() { //BIT 6, [HL]
 
     cpu.registers.h = 0xC0;
     cpu.registers.l = 0x01;
-    cpu.memory[0xC001] = 0b01011000;
+    cpu.memory.write(0xC001, 0b01011000);
 
     cpu.execute_with_args(PREFIX, Some(vec![0x76]));
 
     assert_eq!(2, cpu.registers.program_counter);
     assert!(!cpu.flags.zero);
 
-    cpu.memory[0xC001] = 0b00001000;
+    cpu.memory.write(0xC001, 0b00001000);
     cpu.execute_with_args(PREFIX, Some(vec![0x76]));
 
     assert!(cpu.flags.zero);
@@ -3272,14 +3764,14 @@ fn test_pre_0x7E() { //BIT 7, [HL]
 
     cpu.registers.h = 0xC0;
     cpu.registers.l = 0x01;
-    cpu.memory[0xC001] = 0b10011000;
+    cpu.memory.write(0xC001, 0b10011000);
 
     cpu.execute_with_args(PREFIX, Some(vec![0x7E]));
 
     assert_eq!(2, cpu.registers.program_counter);
     assert!(!cpu.flags.zero);
 
-    cpu.memory[0xC001] = 0b00001000;
+    cpu.memory.write(0xC001, 0b00001000);
     cpu.execute_with_args(PREFIX, Some(vec![0x7E]));
 
     assert!(cpu.flags.zero);
@@ -3296,7 +3788,7 @@ fn test_res_register() { //RES u3, r8
         let expected = {
             let reg = get_register(&mut cpu, opcode % 0x08);
 
-            if let Option::None = reg {
+            if reg.is_none() {
                 continue;
             }
 
@@ -3315,7 +3807,7 @@ fn test_res_register() { //RES u3, r8
         let actual = {
             let reg = get_register(&mut cpu, opcode % 0x08);
 
-            if let Option::None = reg {
+            if reg.is_none() {
                 continue;
             }
 
@@ -3349,17 +3841,17 @@ fn test_pre_0x86() { //RES 0, [HL]
 
     cpu.registers.h = 0xC0;
     cpu.registers.l = 0x01;
-    cpu.memory[0xC001] = 0b10101001;
+    cpu.memory.write(0xC001, 0b10101001);
 
     cpu.execute_with_args(PREFIX, Some(vec![0x86]));
 
     assert_eq!(2, cpu.registers.program_counter);
-    assert_eq!(0b10101000, cpu.memory[0xC001]);
+    assert_eq!(0b10101000, cpu.memory.read(0xC001));
 
-    cpu.memory[0xC001] = 0b10101000;
+    cpu.memory.write(0xC001, 0b10101000);
     cpu.execute_with_args(PREFIX, Some(vec![0x86]));
 
-    assert_eq!(0b10101000, cpu.memory[0xC001]);
+    assert_eq!(0b10101000, cpu.memory.read(0xC001));
 }
 
 #[test]
@@ -3369,17 +3861,17 @@ fn test_pre_0x8E() { //RES 1, [HL]
 
     cpu.registers.h = 0xC0;
     cpu.registers.l = 0x01;
-    cpu.memory[0xC001] = 0b10101010;
+    cpu.memory.write(0xC001, 0b10101010);
 
     cpu.execute_with_args(PREFIX, Some(vec![0x8E]));
 
     assert_eq!(2, cpu.registers.program_counter);
-    assert_eq!(0b10101000, cpu.memory[0xC001]);
+    assert_eq!(0b10101000, cpu.memory.read(0xC001));
 
-    cpu.memory[0xC001] = 0b10101000;
+    cpu.memory.write(0xC001, 0b10101000);
     cpu.execute_with_args(PREFIX, Some(vec![0x8E]));
 
-    assert_eq!(0b10101000, cpu.memory[0xC001]);
+    assert_eq!(0b10101000, cpu.memory.read(0xC001));
 }
 
 #[test]
@@ -3389,17 +3881,17 @@ fn test_pre_0x96() { //RES 2, [HL]
 
     cpu.registers.h = 0xC0;
     cpu.registers.l = 0x01;
-    cpu.memory[0xC001] = 0b10101100;
+    cpu.memory.write(0xC001, 0b10101100);
 
     cpu.execute_with_args(PREFIX, Some(vec![0x96]));
 
     assert_eq!(2, cpu.registers.program_counter);
-    assert_eq!(0b10101000, cpu.memory[0xC001]);
+    assert_eq!(0b10101000, cpu.memory.read(0xC001));
 
-    cpu.memory[0xC001] = 0b10101000;
+    cpu.memory.write(0xC001, 0b10101000);
     cpu.execute_with_args(PREFIX, Some(vec![0x96]));
 
-    assert_eq!(0b10101000, cpu.memory[0xC001]);
+    assert_eq!(0b10101000, cpu.memory.read(0xC001));
 }
 
 #[test]
@@ -3409,17 +3901,17 @@ fn test_pre_0x9E() { //RES 3, [HL]
 
     cpu.registers.h = 0xC0;
     cpu.registers.l = 0x01;
-    cpu.memory[0xC001] = 0b10101001;
+    cpu.memory.write(0xC001, 0b10101001);
 
     cpu.execute_with_args(PREFIX, Some(vec![0x9E]));
 
     assert_eq!(2, cpu.registers.program_counter);
-    assert_eq!(0b10100001, cpu.memory[0xC001]);
+    assert_eq!(0b10100001, cpu.memory.read(0xC001));
 
-    cpu.memory[0xC001] = 0b10100001;
+    cpu.memory.write(0xC001, 0b10100001);
     cpu.execute_with_args(PREFIX, Some(vec![0x9E]));
 
-    assert_eq!(0b10100001, cpu.memory[0xC001]);
+    assert_eq!(0b10100001, cpu.memory.read(0xC001));
 }
 
 #[test]
@@ -3429,17 +3921,17 @@ fn test_pre_0xA6() { //RES 4, [HL]
 
     cpu.registers.h = 0xC0;
     cpu.registers.l = 0x01;
-    cpu.memory[0xC001] = 0b10111001;
+    cpu.memory.write(0xC001, 0b10111001);
 
     cpu.execute_with_args(PREFIX, Some(vec![0xA6]));
 
     assert_eq!(2, cpu.registers.program_counter);
-    assert_eq!(0b10101001, cpu.memory[0xC001]);
+    assert_eq!(0b10101001, cpu.memory.read(0xC001));
 
-    cpu.memory[0xC001] = 0b10101001;
+    cpu.memory.write(0xC001, 0b10101001);
     cpu.execute_with_args(PREFIX, Some(vec![0xA6]));
 
-    assert_eq!(0b10101001, cpu.memory[0xC001]);
+    assert_eq!(0b10101001, cpu.memory.read(0xC001));
 }
 
 #[test]
@@ -3449,17 +3941,17 @@ fn test_pre_0xAE() { //RES 5, [HL]
 
     cpu.registers.h = 0xC0;
     cpu.registers.l = 0x01;
-    cpu.memory[0xC001] = 0b10111001;
+    cpu.memory.write(0xC001, 0b10111001);
 
     cpu.execute_with_args(PREFIX, Some(vec![0xAE]));
 
     assert_eq!(2, cpu.registers.program_counter);
-    assert_eq!(0b10011001, cpu.memory[0xC001]);
+    assert_eq!(0b10011001, cpu.memory.read(0xC001));
 
-    cpu.memory[0xC001] = 0b10011001;
+    cpu.memory.write(0xC001, 0b10011001);
     cpu.execute_with_args(PREFIX, Some(vec![0xAE]));
 
-    assert_eq!(0b10011001, cpu.memory[0xC001]);
+    assert_eq!(0b10011001, cpu.memory.read(0xC001));
 }
 
 #[test]
@@ -3469,17 +3961,17 @@ fn test_pre_0xB6() { //RES 6, [HL]
 
     cpu.registers.h = 0xC0;
     cpu.registers.l = 0x01;
-    cpu.memory[0xC001] = 0b11111001;
+    cpu.memory.write(0xC001, 0b11111001);
 
     cpu.execute_with_args(PREFIX, Some(vec![0xB6]));
 
     assert_eq!(2, cpu.registers.program_counter);
-    assert_eq!(0b10111001, cpu.memory[0xC001]);
+    assert_eq!(0b10111001, cpu.memory.read(0xC001));
 
-    cpu.memory[0xC001] = 0b10111001;
+    cpu.memory.write(0xC001, 0b10111001);
     cpu.execute_with_args(PREFIX, Some(vec![0xB6]));
 
-    assert_eq!(0b10111001, cpu.memory[0xC001]);
+    assert_eq!(0b10111001, cpu.memory.read(0xC001));
 }
 
 #[test]
@@ -3489,17 +3981,17 @@ fn test_pre_0xBE() { //RES 7, [HL]
 
     cpu.registers.h = 0xC0;
     cpu.registers.l = 0x01;
-    cpu.memory[0xC001] = 0b11111001;
+    cpu.memory.write(0xC001, 0b11111001);
 
     cpu.execute_with_args(PREFIX, Some(vec![0xBE]));
 
     assert_eq!(2, cpu.registers.program_counter);
-    assert_eq!(0b01111001, cpu.memory[0xC001]);
+    assert_eq!(0b01111001, cpu.memory.read(0xC001));
 
-    cpu.memory[0xC001] = 0b01111001;
+    cpu.memory.write(0xC001, 0b01111001);
     cpu.execute_with_args(PREFIX, Some(vec![0xBE]));
 
-    assert_eq!(0b01111001, cpu.memory[0xC001]);
+    assert_eq!(0b01111001, cpu.memory.read(0xC001));
 }
 
 const SET_R_START: u8 = 0xC0;
@@ -3513,7 +4005,7 @@ fn test_set_register() { //SET u3, r8
         let expected = {
             let reg = get_register(&mut cpu, opcode % 0x08);
 
-            if let Option::None = reg {
+            if reg.is_none() {
                 continue;
             }
 
@@ -3532,7 +4024,7 @@ fn test_set_register() { //SET u3, r8
         let actual = {
             let reg = get_register(&mut cpu, opcode % 0x08);
 
-            if let Option::None = reg {
+            if reg.is_none() {
                 continue;
             }
 
@@ -3567,17 +4059,17 @@ fn test_pre_0xC6() { //SET 0, [HL]
 
     cpu.registers.h = 0xC0;
     cpu.registers.l = 0x01;
-    cpu.memory[0xC001] = 0b00000000;
+    cpu.memory.write(0xC001, 0b00000000);
 
     cpu.execute_with_args(PREFIX, Some(vec![0xC6]));
 
     assert_eq!(2, cpu.registers.program_counter);
-    assert_eq!(0b00000001, cpu.memory[0xC001]);
+    assert_eq!(0b00000001, cpu.memory.read(0xC001));
 
-    cpu.memory[0xC001] = 0b00000001;
+    cpu.memory.write(0xC001, 0b00000001);
     cpu.execute_with_args(PREFIX, Some(vec![0xC6]));
 
-    assert_eq!(0b00000001, cpu.memory[0xC001]);
+    assert_eq!(0b00000001, cpu.memory.read(0xC001));
 }
 
 #[test]
@@ -3587,19 +4079,19 @@ fn test_pre_0xCE() { //SET 1, [HL]
 
     cpu.registers.h = 0xC0;
     cpu.registers.l = 0x01;
-    cpu.memory[0xC001] = 0b00000000;
+    cpu.memory.write(0xC001, 0b00000000);
 
     cpu.execute_with_args(PREFIX, Some(vec![0xCE]));
 
     let expected = 0b00000001 << 1;
 
     assert_eq!(2, cpu.registers.program_counter);
-    assert_eq!(expected, cpu.memory[0xC001]);
+    assert_eq!(expected, cpu.memory.read(0xC001));
 
-    cpu.memory[0xC001] = expected;
+    cpu.memory.write(0xC001, expected);
     cpu.execute_with_args(PREFIX, Some(vec![0xCE]));
 
-    assert_eq!(expected, cpu.memory[0xC001]);
+    assert_eq!(expected, cpu.memory.read(0xC001));
 }
 
 #[test]
@@ -3609,19 +4101,19 @@ fn test_pre_0xD6() { //SET 2, [HL]
 
     cpu.registers.h = 0xC0;
     cpu.registers.l = 0x01;
-    cpu.memory[0xC001] = 0b00000000;
+    cpu.memory.write(0xC001, 0b00000000);
 
     cpu.execute_with_args(PREFIX, Some(vec![0xD6]));
 
     let expected = 0b00000001 << 2;
 
     assert_eq!(2, cpu.registers.program_counter);
-    assert_eq!(expected, cpu.memory[0xC001]);
+    assert_eq!(expected, cpu.memory.read(0xC001));
 
-    cpu.memory[0xC001] = expected;
+    cpu.memory.write(0xC001, expected);
     cpu.execute_with_args(PREFIX, Some(vec![0xD6]));
 
-    assert_eq!(expected, cpu.memory[0xC001]);
+    assert_eq!(expected, cpu.memory.read(0xC001));
 }
 
 #[test]
@@ -3631,19 +4123,19 @@ fn test_pre_0xDE() { //SET 3, [HL]
 
     cpu.registers.h = 0xC0;
     cpu.registers.l = 0x01;
-    cpu.memory[0xC001] = 0b00000000;
+    cpu.memory.write(0xC001, 0b00000000);
 
     cpu.execute_with_args(PREFIX, Some(vec![0xDE]));
 
     let expected = 0b00000001 << 3;
 
     assert_eq!(2, cpu.registers.program_counter);
-    assert_eq!(expected, cpu.memory[0xC001]);
+    assert_eq!(expected, cpu.memory.read(0xC001));
 
-    cpu.memory[0xC001] = expected;
+    cpu.memory.write(0xC001, expected);
     cpu.execute_with_args(PREFIX, Some(vec![0xDE]));
 
-    assert_eq!(expected, cpu.memory[0xC001]);
+    assert_eq!(expected, cpu.memory.read(0xC001));
 }
 
 #[test]
@@ -3653,19 +4145,19 @@ fn test_pre_0xE6() { //SET 4, [HL]
 
     cpu.registers.h = 0xC0;
     cpu.registers.l = 0x01;
-    cpu.memory[0xC001] = 0b00000000;
+    cpu.memory.write(0xC001, 0b00000000);
 
     cpu.execute_with_args(PREFIX, Some(vec![0xE6]));
 
     let expected = 0b00000001 << 4;
 
     assert_eq!(2, cpu.registers.program_counter);
-    assert_eq!(expected, cpu.memory[0xC001]);
+    assert_eq!(expected, cpu.memory.read(0xC001));
 
-    cpu.memory[0xC001] = expected;
+    cpu.memory.write(0xC001, expected);
     cpu.execute_with_args(PREFIX, Some(vec![0xE6]));
 
-    assert_eq!(expected, cpu.memory[0xC001]);
+    assert_eq!(expected, cpu.memory.read(0xC001));
 }
 
 #[test]
@@ -3675,19 +4167,19 @@ fn test_pre_0xEE() { //SET 5, [HL]
 
     cpu.registers.h = 0xC0;
     cpu.registers.l = 0x01;
-    cpu.memory[0xC001] = 0b00000000;
+    cpu.memory.write(0xC001, 0b00000000);
 
     cpu.execute_with_args(PREFIX, Some(vec![0xEE]));
 
     let expected = 0b00000001 << 5;
 
     assert_eq!(2, cpu.registers.program_counter);
-    assert_eq!(expected, cpu.memory[0xC001]);
+    assert_eq!(expected, cpu.memory.read(0xC001));
 
-    cpu.memory[0xC001] = expected;
+    cpu.memory.write(0xC001, expected);
     cpu.execute_with_args(PREFIX, Some(vec![0xEE]));
 
-    assert_eq!(expected, cpu.memory[0xC001]);
+    assert_eq!(expected, cpu.memory.read(0xC001));
 }
 
 #[test]
@@ -3697,19 +4189,19 @@ fn test_pre_0xF6() { //SET 6, [HL]
 
     cpu.registers.h = 0xC0;
     cpu.registers.l = 0x01;
-    cpu.memory[0xC001] = 0b00000000;
+    cpu.memory.write(0xC001, 0b00000000);
 
     cpu.execute_with_args(PREFIX, Some(vec![0xF6]));
 
     let expected = 0b00000001 << 6;
 
     assert_eq!(2, cpu.registers.program_counter);
-    assert_eq!(expected, cpu.memory[0xC001]);
+    assert_eq!(expected, cpu.memory.read(0xC001));
 
-    cpu.memory[0xC001] = expected;
+    cpu.memory.write(0xC001, expected);
     cpu.execute_with_args(PREFIX, Some(vec![0xF6]));
 
-    assert_eq!(expected, cpu.memory[0xC001]);
+    assert_eq!(expected, cpu.memory.read(0xC001));
 }
 
 #[test]
@@ -3719,17 +4211,17 @@ fn test_pre_0xFE() { //SET 7, [HL]
 
     cpu.registers.h = 0xC0;
     cpu.registers.l = 0x01;
-    cpu.memory[0xC001] = 0b00000000;
+    cpu.memory.write(0xC001, 0b00000000);
 
     cpu.execute_with_args(PREFIX, Some(vec![0xFE]));
 
     let expected = 0b00000001 << 7;
 
     assert_eq!(2, cpu.registers.program_counter);
-    assert_eq!(expected, cpu.memory[0xC001]);
+    assert_eq!(expected, cpu.memory.read(0xC001));
 
-    cpu.memory[0xC001] = expected;
+    cpu.memory.write(0xC001, expected);
     cpu.execute_with_args(PREFIX, Some(vec![0xFE]));
 
-    assert_eq!(expected, cpu.memory[0xC001]);
+    assert_eq!(expected, cpu.memory.read(0xC001));
 }
\ No newline at end of file
@@ -1,93 +1,290 @@
+use std::io;
+use std::sync::{Arc, atomic::{AtomicBool, Ordering}};
 use std::time::{Duration, Instant};
-use std::thread;
 
 use crate::{
-    cpu::CPU,
-    ppu::{PPU, LCD_REGISTERS}
+    apu::Sample,
+    audio::AudioSink,
+    cartridge::Cartridge,
+    cpu::{CPU, CpuError, IllegalOpcodePolicy},
+    joypad::Button,
+    ppu::{CYCLES_PER_FRAME, FRAME_SIZE, Renderer}
 };
+use pacer::FramePacer;
 
-const CARTRIDGE_ROM: usize = 0x7FFF;
+pub use pacer::Speed;
 
-struct TimeState {
-    prev: Instant,
-    delay: Duration
-}
+mod pacer;
 
-impl TimeState {
-    pub fn new() -> TimeState {
-        TimeState {
-            prev: Instant::now(),
-            delay: Duration::new(0, 0)
-        }
-    }
-}
+const SAVE_INTERVAL: Duration = Duration::from_secs(1); //how often dirty cartridge RAM & recordings are flushed to disk
 
 pub struct GameBoy {
     cpu: CPU,
-    ppu: PPU
+    last_save: Instant,
+    frame_cycles: u32, //t_states into the current frame
+    speed: Speed,
+    turbo: bool, //fast-forward, runs unthrottled regardless of speed
+    pacer: Option<FramePacer>, //None runs as fast as the host allows
+    audio: Vec<Box<dyn AudioSink>>, //fed the APU's samples after every frame
+    shutdown: Arc<AtomicBool>, //set from outside, e.g. a Ctrl-C handler, to stop run at the next frame
+    turbo_toggle: Arc<AtomicBool> //set from outside, e.g. a signal, to toggle turbo at the next frame
 }
 
 impl GameBoy {
-    pub fn init(rom: Vec<u8>) -> GameBoy {
+    pub fn init(boot_rom: Vec<u8>) -> GameBoy {
         let mut cpu = CPU::new();
-        cpu.memory_map(
-            0x0000..CARTRIDGE_ROM ,
-            rom
-        );
+        cpu.map_boot_rom(boot_rom);
 
         GameBoy {
-            cpu: cpu,
-            ppu: PPU::init()
+            cpu,
+            last_save: Instant::now(),
+            frame_cycles: 0,
+            speed: Speed::NORMAL,
+            turbo: false,
+            pacer: Option::Some(FramePacer::new(1.0)),
+            audio: Vec::new(),
+            shutdown: Arc::new(AtomicBool::new(false)),
+            turbo_toggle: Arc::new(AtomicBool::new(false))
         }
     }
 
-    pub fn run(&mut self) {
-        let mut cpu_time_state = TimeState::new();
-        let mut ppu_time_state = TimeState::new();
+    //the cartridge sits underneath the boot ROM, which hands over to it at 0x0100
+    pub fn insert_cartridge(&mut self, cartridge: Cartridge) {
+        self.cpu.insert_cartridge(cartridge);
+    }
+
+    pub fn set_illegal_opcode_policy(&mut self, policy: IllegalOpcodePolicy) {
+        self.cpu.set_illegal_opcode_policy(policy);
+    }
+
+    pub fn set_ppu_access_blocking(&mut self, enabled: bool) {
+        self.cpu.set_ppu_access_blocking(enabled);
+    }
+
+    //for frontends & test harnesses, the press is seen on the next read of P1
+    pub fn set_button(&mut self, button: Button, pressed: bool) {
+        self.cpu.set_button(button, pressed);
+    }
+
+    pub fn set_sample_rate(&mut self, sample_rate: u32) {
+        self.cpu.apu_mut().set_sample_rate(sample_rate);
+    }
+
+    //set the sample rate to match before adding a sink
+    pub fn add_audio_sink(&mut self, sink: Box<dyn AudioSink>) {
+        self.audio.push(sink);
+    }
+
+    //stereo samples produced since the last call, oldest first
+    pub fn drain_samples(&mut self) -> Vec<Sample> {
+        let buffer = self.cpu.apu_mut().buffer_mut();
+        let mut samples = Vec::with_capacity(buffer.len());
+
+        while let Option::Some(sample) = buffer.pop() {
+            samples.push(sample);
+        }
+
+        samples
+    }
 
+    pub fn set_renderer(&mut self, renderer: Renderer) {
+        self.cpu.ppu_mut().set_renderer(renderer);
+    }
+
+    //storing true makes run & run_frames save and return after the current frame
+    pub fn shutdown_handle(&self) -> Arc<AtomicBool> {
+        Arc::clone(&self.shutdown)
+    }
+
+    //storing true makes run toggle turbo before the next frame, the flag is cleared once handled
+    pub fn turbo_toggle_handle(&self) -> Arc<AtomicBool> {
+        Arc::clone(&self.turbo_toggle)
+    }
+
+    pub fn set_speed(&mut self, speed: Speed) {
+        self.speed = speed;
+        self.update_pacer();
+    }
+
+    pub fn toggle_turbo(&mut self) {
+        self.turbo = !self.turbo;
+        self.update_pacer();
+    }
+
+    //emulate frames back to back for the given wall time and return emulated frames per second
+    pub fn benchmark(&mut self, duration: Duration) -> Result<f64, CpuError> {
+        let start = Instant::now();
+        let mut frames = 0;
+
+        while start.elapsed() < duration {
+            self.run_frame()?;
+            frames += 1;
+        }
+
+        Result::Ok(frames as f64 / start.elapsed().as_secs_f64())
+    }
+
+    //runs until the CPU reports an error or a shutdown is requested
+    pub fn run(&mut self) -> Result<(), CpuError> {
         loop {
-            cpu_time_state = self.cpu_run(cpu_time_state);
-            ppu_time_state = self.ppu_run(ppu_time_state);
-            thread::sleep(cpu_time_state.delay); //TODO: currently throttling by the smallest delay (cpu) but will probs need to change
+            if self.turbo_toggle.swap(false, Ordering::Relaxed) {
+                self.toggle_turbo();
+            }
+
+            if self.shutdown.load(Ordering::Relaxed) {
+                self.shutdown();
+                return Result::Ok(());
+            }
+
+            if !self.run_frame_or_break()? {
+                continue;
+            }
+
+            self.flush_audio();
+            self.save_run();
+
+            if let Option::Some(pacer) = &mut self.pacer {
+                pacer.wait();
+            }
+        }
+    }
+
+    //run a fixed number of frames as fast as possible, for headless recording
+    pub fn run_frames(&mut self, frames: u32) -> Result<(), CpuError> {
+        let mut completed = 0;
+
+        while completed < frames && !self.shutdown.load(Ordering::Relaxed) {
+            if self.run_frame_or_break()? {
+                completed += 1;
+                self.flush_audio();
+            }
+        }
+
+        self.shutdown();
+
+        Result::Ok(())
+    }
+
+    //a breakpoint pauses for the user instead of stopping, returns whether the frame was completed
+    fn run_frame_or_break(&mut self) -> Result<bool, CpuError> {
+        match self.run_frame() {
+            Result::Ok(()) => Result::Ok(true),
+            Result::Err(error @ CpuError::Breakpoint { .. }) => {
+                self.pause(error);
+                Result::Ok(false)
+            },
+            Result::Err(error) => Result::Err(error)
+        }
+    }
+
+    //step until a frame's worth of cycles has been emulated, a frame interrupted by an error is picked up by the next call
+    pub fn run_frame(&mut self) -> Result<(), CpuError> {
+        while self.frame_cycles < CYCLES_PER_FRAME {
+            self.step()?;
+        }
+
+        self.frame_cycles -= CYCLES_PER_FRAME;
+
+        Result::Ok(())
+    }
+
+    //the CPU is the master clock, everything on the bus is ticked by the t_states it took
+    pub fn step(&mut self) -> Result<u8, CpuError> {
+        let t_states = self.cpu.step()?;
+
+        self.frame_cycles += t_states as u32;
+
+        Result::Ok(t_states)
+    }
+
+    //shades 0-3 row by row, the lightest shade while the LCD is off or the CPU is stopped
+    pub fn framebuffer(&self) -> &[u8; FRAME_SIZE] {
+        self.cpu.ppu().frame()
+    }
+
+    //t_states emulated since power on
+    pub fn cycles(&self) -> u64 {
+        self.cpu.cycles()
+    }
+
+    //flush battery backed RAM to the .sav file
+    pub fn save(&mut self) {
+        self.last_save = Instant::now();
+
+        if let Option::Some(cartridge) = self.cpu.cartridge_mut() {
+            if let Result::Err(error) = cartridge.flush_save() {
+                eprintln!("Error writing save file, Error: {}", error);
+            }
         }
     }
 
     pub fn status(&self) {
+        if let Option::Some(cartridge) = self.cpu.cartridge() {
+            cartridge.status();
+        }
+
         self.cpu.status();
     }
 
-    fn cpu_run(&mut self, state: TimeState) -> TimeState {
-        if !has_delayed(&state) {
-            return state;
-        }
+    //dump state and wait for enter before resuming
+    fn pause(&self, reason: CpuError) {
+        println!("\n===BREAK===\n{}", reason);
+        self.status();
+        println!("Press enter to continue");
 
-        TimeState {
-            prev: Instant::now(),
-            delay: self.cpu.step()
+        let mut line = String::new();
+
+        if let Result::Err(error) = io::stdin().read_line(&mut line) {
+            eprintln!("Error reading stdin, Error: {}", error);
         }
     }
 
-    fn ppu_run(&mut self, state: TimeState) -> TimeState {
-        if !has_delayed(&state) {
-            return state;
+    fn update_pacer(&mut self) {
+        self.pacer = match self.speed.factor() {
+            Option::Some(factor) if !self.turbo => Option::Some(FramePacer::new(factor)),
+            _ => Option::None
+        };
+    }
+
+    //a sink that fails is dropped, emulation carries on without it
+    fn flush_audio(&mut self) {
+        if self.audio.is_empty() {
+            return; //the ring buffer overwrites itself
         }
 
-        let (values, delay) = self.ppu.step();
+        let samples = self.drain_samples();
+        self.for_each_sink(|sink| sink.write(&samples));
+    }
 
-        self.cpu.memory_map(
-            LCD_REGISTERS,
-            values
-        );
+    fn for_each_sink(&mut self, mut action: impl FnMut(&mut dyn AudioSink) -> io::Result<()>) {
+        self.audio.retain_mut(|sink| match action(sink.as_mut()) {
+            Result::Ok(()) => true,
+            Result::Err(error) => {
+                eprintln!("Error writing audio, Error: {}", error);
+                false
+            }
+        });
+    }
 
-        TimeState {
-            prev: Instant::now(),
-            delay: delay
+    //everything that has to reach the disk before exiting
+    fn shutdown(&mut self) {
+        self.flush_audio();
+        self.for_each_sink(|sink| sink.flush());
+        self.save();
+    }
+
+    //recordings are synced on the same schedule as saves
+    fn save_run(&mut self) {
+        if self.last_save.elapsed() >= SAVE_INTERVAL {
+            self.for_each_sink(|sink| sink.flush());
+            self.save();
         }
     }
 }
 
-fn has_delayed(state: &TimeState) -> bool {
-    let now = Instant::now() - state.prev;
-
-    now >= state.delay
+//a fallback for early returns, the normal exit paths go through shutdown
+impl Drop for GameBoy {
+    fn drop(&mut self) {
+        self.shutdown();
+    }
 }
\ No newline at end of file
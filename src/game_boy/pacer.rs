@@ -0,0 +1,118 @@
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::ppu::CYCLES_PER_FRAME;
+
+const CLOCK_HZ: f64 = 4194304.0; //DMG master clock, t_states per second
+pub const FRAME_RATE: f64 = CLOCK_HZ / CYCLES_PER_FRAME as f64; //~59.73Hz
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Speed {
+    QUARTER,
+    NORMAL,
+    DOUBLE,
+    UNTHROTTLED
+}
+
+impl Speed {
+    //accepts the multiplier, eg 0.25, 1, 2 or max
+    pub fn parse(value: &str) -> Option<Speed> {
+        match value {
+            "0.25" => Option::Some(Speed::QUARTER),
+            "1" => Option::Some(Speed::NORMAL),
+            "2" => Option::Some(Speed::DOUBLE),
+            "max" => Option::Some(Speed::UNTHROTTLED),
+            _ => Option::None
+        }
+    }
+
+    //None when frames should not be paced at all
+    pub fn factor(&self) -> Option<f64> {
+        match self {
+            Speed::QUARTER => Option::Some(0.25),
+            Speed::NORMAL => Option::Some(1.0),
+            Speed::DOUBLE => Option::Some(2.0),
+            Speed::UNTHROTTLED => Option::None
+        }
+    }
+}
+
+//keeps emulated frames in step with the host clock, emulation itself never looks at wall time
+pub struct FramePacer {
+    frame_duration: Duration,
+    deadline: Instant //when the current frame is due to finish
+}
+
+impl FramePacer {
+    //factor scales the frame rate, 2.0 runs frames twice as fast as real hardware
+    pub fn new(factor: f64) -> FramePacer {
+        let frame_duration = Duration::from_secs_f64(1.0 / (FRAME_RATE * factor));
+
+        FramePacer {
+            frame_duration,
+            deadline: Instant::now() + frame_duration
+        }
+    }
+
+    //sleep until the frame is due, called once a frame's worth of cycles has been emulated
+    pub fn wait(&mut self) {
+        if let Option::Some(remaining) = self.next_deadline(Instant::now()) {
+            thread::sleep(remaining);
+        }
+    }
+
+    //how long until the current frame is due, moving on to the next frame
+    fn next_deadline(&mut self, now: Instant) -> Option<Duration> {
+        let deadline = self.deadline;
+
+        if now > deadline + self.frame_duration { //fallen over a frame behind, don't try to catch up
+            self.deadline = now + self.frame_duration;
+            return Option::None;
+        }
+
+        self.deadline = deadline + self.frame_duration;
+        deadline.checked_duration_since(now)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_speed() {
+        assert_eq!(Option::Some(Speed::QUARTER), Speed::parse("0.25"));
+        assert_eq!(Option::Some(Speed::UNTHROTTLED), Speed::parse("max"));
+        assert_eq!(Option::None, Speed::parse("3"));
+
+        assert_eq!(Option::Some(2.0), Speed::DOUBLE.factor());
+        assert_eq!(Option::None, Speed::UNTHROTTLED.factor());
+    }
+
+    #[test]
+    fn test_frame_duration() {
+        assert_eq!(16742, FramePacer::new(1.0).frame_duration.as_micros()); //70224 / 4194304 seconds
+        assert_eq!(8371, FramePacer::new(2.0).frame_duration.as_micros());
+        assert_eq!(66970, FramePacer::new(0.25).frame_duration.as_micros());
+    }
+
+    #[test]
+    fn test_next_deadline() {
+        let mut pacer = FramePacer::new(1.0);
+        let start = pacer.deadline - pacer.frame_duration;
+
+        assert_eq!(Option::Some(pacer.frame_duration), pacer.next_deadline(start));
+
+        let now = start + pacer.frame_duration + Duration::from_millis(10); //part way through the second frame
+        assert_eq!(Option::Some(pacer.frame_duration - Duration::from_millis(10)), pacer.next_deadline(now));
+    }
+
+    #[test]
+    fn test_next_deadline_behind() {
+        let mut pacer = FramePacer::new(1.0);
+        let now = pacer.deadline + pacer.frame_duration * 3;
+
+        assert_eq!(Option::None, pacer.next_deadline(now));
+        assert_eq!(now + pacer.frame_duration, pacer.deadline); //restarts from now
+    }
+}
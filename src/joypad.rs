@@ -0,0 +1,143 @@
+//https://gbdev.io/pandocs/Joypad_Input.html
+pub const P1: u16 = 0xFF00;
+
+const SELECT_BUTTONS: u8 = 0x20; //0 selects A, B, Select & Start
+const SELECT_DPAD: u8 = 0x10; //0 selects the directions
+const SELECT_MASK: u8 = SELECT_BUTTONS | SELECT_DPAD;
+const LINES: u8 = 0x0F; //P10-P13, low when a selected key is held
+const UNUSED: u8 = 0xC0;
+
+//value is the P1 line the key pulls low, buttons & directions share the lines
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Button {
+    RIGHT,
+    LEFT,
+    UP,
+    DOWN,
+    A,
+    B,
+    SELECT,
+    START
+}
+
+impl Button {
+    fn line(&self) -> u8 {
+        match self {
+            Button::RIGHT | Button::A => 0x01,
+            Button::LEFT | Button::B => 0x02,
+            Button::UP | Button::SELECT => 0x04,
+            Button::DOWN | Button::START => 0x08
+        }
+    }
+
+    fn is_direction(&self) -> bool {
+        matches!(self, Button::RIGHT | Button::LEFT | Button::UP | Button::DOWN)
+    }
+}
+
+pub struct Joypad {
+    select: u8, //bits 4-5 as written, active low
+    buttons: u8, //held keys in each matrix, active high
+    directions: u8
+}
+
+impl Default for Joypad {
+    fn default() -> Self {
+        Joypad::new()
+    }
+}
+
+impl Joypad {
+    pub fn new() -> Joypad {
+        Joypad {
+            select: 0,
+            buttons: 0,
+            directions: 0
+        }
+    }
+
+    pub fn read(&self) -> u8 {
+        UNUSED | self.select | self.lines()
+    }
+
+    //returns true when a line falls, selecting a matrix with a key already held requests the interrupt too
+    pub fn write(&mut self, value: u8) -> bool {
+        let lines = self.lines();
+        self.select = value & SELECT_MASK;
+
+        lines & !self.lines() != 0
+    }
+
+    //returns true when a line falls, which requests the Joypad interrupt
+    pub fn set_button(&mut self, button: Button, pressed: bool) -> bool {
+        let lines = self.lines();
+        let keys = if button.is_direction() { &mut self.directions } else { &mut self.buttons };
+
+        if pressed {
+            *keys |= button.line();
+        } else {
+            *keys &= !button.line();
+        }
+
+        lines & !self.lines() != 0
+    }
+
+    //held keys in the selected matrices pull their line low, both can be selected at once
+    fn lines(&self) -> u8 {
+        let mut held = 0;
+
+        if self.select & SELECT_BUTTONS == 0 {
+            held |= self.buttons;
+        }
+
+        if self.select & SELECT_DPAD == 0 {
+            held |= self.directions;
+        }
+
+        !held & LINES
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_read() {
+        let mut joypad = Joypad::new();
+        joypad.write(0xFF);
+
+        assert_eq!(0xFF, joypad.read()); //nothing selected
+
+        joypad.set_button(Button::START, true);
+        joypad.set_button(Button::LEFT, true);
+        assert_eq!(0xFF, joypad.read());
+
+        joypad.write(SELECT_DPAD); //buttons
+        assert_eq!(0xD7, joypad.read());
+
+        joypad.write(SELECT_BUTTONS); //directions
+        assert_eq!(0xED, joypad.read());
+
+        joypad.write(0x00); //both matrices
+        assert_eq!(0xC5, joypad.read());
+
+        joypad.set_button(Button::START, false);
+        assert_eq!(0xCD, joypad.read());
+    }
+
+    #[test]
+    fn test_interrupt() {
+        let mut joypad = Joypad::new();
+        joypad.write(SELECT_BUTTONS); //directions only
+
+        assert!(joypad.set_button(Button::UP, true));
+        assert!(!joypad.set_button(Button::UP, true)); //already held
+        assert!(!joypad.set_button(Button::A, true)); //not selected
+        assert!(!joypad.set_button(Button::UP, false));
+
+        assert!(joypad.write(SELECT_DPAD)); //A is held, selecting the buttons pulls P10 low
+        assert!(!joypad.write(SELECT_DPAD));
+        assert!(!joypad.write(SELECT_MASK));
+    }
+}
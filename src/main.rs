@@ -1,12 +1,25 @@
 use std::io::prelude::*;
 use std::fs::File;
-use game_boy::GameBoy;
+use std::{env, path::Path, process, time::Duration};
+use std::sync::atomic::Ordering;
+use cartridge::Cartridge;
+use apu::DEFAULT_SAMPLE_RATE;
+use audio::WavWriter;
+use cpu::IllegalOpcodePolicy;
+use game_boy::{GameBoy, Speed};
+use ppu::Renderer;
 
+pub mod cartridge;
 pub mod cpu;
 pub mod ppu;
+pub mod apu;
+pub mod audio;
+pub mod joypad;
 pub mod game_boy;
 
 const BOOT_ROM_NAME: &str = "assets/dmg.bin";
+const BENCHMARK_DURATION: Duration = Duration::from_secs(10);
+const USAGE: &str = "Usage: game_boy_emulator [--speed 0.25|1|2|max] [--renderer scanline|fifo] [--illegal-opcode hang|error|break] [--turbo] [--no-access-blocking] [--audio] [--wav FILE] [--frames N] [--benchmark] [ROM]\nSIGUSR1 toggles turbo while running";
 
 fn main() {
     let boot_rom_file = File::open(BOOT_ROM_NAME);
@@ -23,8 +36,174 @@ fn main() {
     }
 
     let mut gb = GameBoy::init(rom);
+    let shutdown = gb.shutdown_handle();
+
+    watch_turbo_signal(&gb);
+
+    //the handler only raises the flag, the run loop saves & returns so nothing is lost on Ctrl-C
+    if let Result::Err(error) = ctrlc::set_handler(move || shutdown.store(true, Ordering::Relaxed)) {
+        eprintln!("Error installing Ctrl-C handler, Error: {}", error);
+    }
+    let mut rom_path = Option::None;
+    let mut benchmark = false;
+    let mut host_audio = false;
+    let mut wav_path = Option::None;
+    let mut frames = Option::None;
+    let mut args = env::args().skip(1);
+
+    while let Option::Some(arg) = args.next() {
+        match arg.as_str() {
+            "--speed" => match args.next().as_deref().and_then(Speed::parse) {
+                Option::Some(speed) => gb.set_speed(speed),
+                Option::None => {
+                    eprintln!("{}", USAGE);
+                    process::exit(1);
+                }
+            },
+            "--renderer" => match args.next().as_deref().and_then(Renderer::parse) {
+                Option::Some(renderer) => gb.set_renderer(renderer),
+                Option::None => {
+                    eprintln!("{}", USAGE);
+                    process::exit(1);
+                }
+            },
+            "--illegal-opcode" => match args.next().as_deref().and_then(IllegalOpcodePolicy::parse) {
+                Option::Some(policy) => gb.set_illegal_opcode_policy(policy),
+                Option::None => {
+                    eprintln!("{}", USAGE);
+                    process::exit(1);
+                }
+            },
+            "--turbo" => gb.toggle_turbo(),
+            "--no-access-blocking" => gb.set_ppu_access_blocking(false),
+            "--audio" => host_audio = true,
+            "--wav" => match args.next() {
+                Option::Some(path) => wav_path = Option::Some(path),
+                Option::None => {
+                    eprintln!("{}", USAGE);
+                    process::exit(1);
+                }
+            },
+            "--frames" => match args.next().and_then(|value| value.parse().ok()) {
+                Option::Some(count) => frames = Option::Some(count),
+                Option::None => {
+                    eprintln!("{}", USAGE);
+                    process::exit(1);
+                }
+            },
+            "--benchmark" => benchmark = true,
+            _ if arg.starts_with("--") => {
+                eprintln!("{}", USAGE);
+                process::exit(1);
+            },
+            _ => rom_path = Option::Some(arg)
+        }
+    }
+
+    if let Option::Some(path) = rom_path {
+        match Cartridge::load(Path::new(&path)) {
+            Result::Ok(cartridge) => {
+                for warning in &cartridge.header().warnings {
+                    eprintln!("Warning loading cartridge {}: {}", path, warning);
+                }
+
+                gb.insert_cartridge(cartridge);
+            },
+            Result::Err(error) => {
+                eprintln!("Error loading cartridge {}, Error: {}", path, error);
+                process::exit(1);
+            }
+        }
+    }
+
+    let sample_rate = if host_audio { open_host_audio(&mut gb) } else { Option::None };
+    let sample_rate = sample_rate.unwrap_or(DEFAULT_SAMPLE_RATE);
+
+    if let Option::Some(path) = wav_path {
+        match WavWriter::create(Path::new(&path), sample_rate) {
+            Result::Ok(wav) => gb.add_audio_sink(Box::new(wav)),
+            Result::Err(error) => {
+                eprintln!("Error creating WAV file {}, Error: {}", path, error);
+                process::exit(1);
+            }
+        }
+    }
+
+    if let Option::Some(count) = frames {
+        if let Result::Err(error) = gb.run_frames(count) {
+            gb.status();
+            eprintln!("Error running, Error: {}", error);
+            drop(gb); //flush the save & WAV file, process::exit skips destructors
+            process::exit(1);
+        }
+
+        return;
+    }
+
+    if benchmark {
+        match gb.benchmark(BENCHMARK_DURATION) {
+            Result::Ok(fps) => println!("Benchmark: {:.2} fps", fps),
+            Result::Err(error) => eprintln!("Error running benchmark, Error: {}", error)
+        }
+
+        return;
+    }
 
     gb.status();
-    gb.run();
-    gb.status();
+
+    if let Result::Err(error) = gb.run() {
+        gb.status();
+        eprintln!("Error running, Error: {}", error);
+        drop(gb); //flush the save, process::exit skips destructors
+        process::exit(1);
+    }
+}
+
+//SIGUSR1 toggles turbo while running, e.g. `kill -USR1 <pid>`, the signal is blocked here & waited for on its own thread
+#[cfg(unix)]
+fn watch_turbo_signal(gb: &GameBoy) {
+    use nix::sys::signal::{SigSet, Signal};
+
+    let turbo_toggle = gb.turbo_toggle_handle();
+    let mut signals = SigSet::empty();
+    signals.add(Signal::SIGUSR1);
+
+    //threads spawned afterwards inherit the mask, so only the waiting thread ever sees the signal
+    if let Result::Err(error) = signals.thread_block() {
+        eprintln!("Error blocking SIGUSR1, Error: {}", error);
+        return;
+    }
+
+    std::thread::spawn(move || {
+        while signals.wait().is_ok() {
+            turbo_toggle.store(true, Ordering::Relaxed);
+        }
+    });
+}
+
+#[cfg(not(unix))]
+fn watch_turbo_signal(_gb: &GameBoy) {}
+
+//plays through the default output device, returns the sample rate the APU has been switched to
+#[cfg(feature = "audio")]
+fn open_host_audio(gb: &mut GameBoy) -> Option<u32> {
+    match audio::HostAudio::open() {
+        Result::Ok(host) => {
+            let sample_rate = host.sample_rate();
+            gb.set_sample_rate(sample_rate);
+            gb.add_audio_sink(Box::new(host));
+
+            Option::Some(sample_rate)
+        },
+        Result::Err(error) => {
+            eprintln!("Error opening audio output, Error: {}", error);
+            Option::None
+        }
+    }
+}
+
+#[cfg(not(feature = "audio"))]
+fn open_host_audio(_gb: &mut GameBoy) -> Option<u32> {
+    eprintln!("Built without host audio, rebuild with --features audio");
+    Option::None
 }
@@ -1,56 +1,896 @@
-use std::{ops::Range, time::Duration};
+use crate::cpu::interrupts::Interrupt;
+use fifo::Fifo;
+
+mod fifo;
+mod scanline;
+
+//https://gbdev.io/pandocs/Rendering.html
+pub const LCDC: u16 = 0xFF40; //LCD control
+pub const STAT: u16 = 0xFF41; //LCD status
+pub const SCY: u16 = 0xFF42;
+pub const SCX: u16 = 0xFF43;
+pub const LY: u16 = 0xFF44; //LCD Y coordinate, read-only but writing resets it
+pub const LYC: u16 = 0xFF45; //LY compare
+pub const BGP: u16 = 0xFF47; //background palette
+pub const OBP0: u16 = 0xFF48; //object palettes
+pub const OBP1: u16 = 0xFF49;
+pub const WY: u16 = 0xFF4A; //window Y position
+pub const WX: u16 = 0xFF4B; //window X position + 7
+
+pub const SCREEN_WIDTH: usize = 160;
+pub const SCREEN_HEIGHT: usize = 144;
+pub const FRAME_SIZE: usize = SCREEN_WIDTH * SCREEN_HEIGHT;
+pub static BLANK_FRAME: [u8; FRAME_SIZE] = [0; FRAME_SIZE]; //shade 0 is the lightest
+
+pub const VRAM_START: u16 = 0x8000;
+const VRAM_SIZE: usize = 0x2000;
+pub const OAM_START: u16 = 0xFE00;
+const OAM_SIZE: usize = 0xA0; //40 sprites, 4 bytes each
+const SPRITES_PER_LINE: usize = 10;
+const SPRITE_Y_OFFSET: u8 = 16; //Y & X in OAM are the position + 16 & + 8, so sprites can be partially off screen
+const SPRITE_X_OFFSET: u8 = 8;
+const TILE_SIZE: u16 = 16; //8x8 pixels, 2 bits per pixel
+const TILE_MAP_WIDTH: u16 = 32;
+const WINDOW_X_OFFSET: u8 = 7;
 
-pub const LCD_REGISTERS: Range<usize> = 0xFF40..0xFF4B;
 const LCD_Y_MAX: u8 = 153;
+const VBLANK_START: u8 = 144; //first line past the visible 144
+const CYCLES_PER_LINE: u16 = 456;
+pub const CYCLES_PER_FRAME: u32 = CYCLES_PER_LINE as u32 * (LCD_Y_MAX as u32 + 1); //70224
+const OAM_SCAN_CYCLES: u16 = 80;
+const DRAWING_CYCLES: u16 = 172; //minimum, extended by fine scrolling
+
+const LCDC_ENABLE: u8 = 0x80;
+const LCDC_WINDOW_MAP: u8 = 0x40; //0x9800 or 0x9C00
+const LCDC_WINDOW_ENABLE: u8 = 0x20;
+const LCDC_TILE_DATA: u8 = 0x10; //0x8800 signed or 0x8000 unsigned addressing
+const LCDC_BG_MAP: u8 = 0x08; //0x9800 or 0x9C00
+const LCDC_OBJ_SIZE: u8 = 0x04; //8x8 or 8x16
+const LCDC_OBJ_ENABLE: u8 = 0x02;
+const LCDC_BG_WINDOW_ENABLE: u8 = 0x01; //on DMG clearing this blanks both background & window
+const STAT_LYC_INTERRUPT: u8 = 0x40;
+const STAT_OAM_INTERRUPT: u8 = 0x20;
+const STAT_VBLANK_INTERRUPT: u8 = 0x10;
+const STAT_HBLANK_INTERRUPT: u8 = 0x08;
+const STAT_WRITABLE: u8 = 0x78; //interrupt selects, the coincidence flag & mode are driven by the PPU
+const STAT_COINCIDENCE: u8 = 0x04;
+
+const OBJ_BG_PRIORITY: u8 = 0x80; //background colours 1-3 are drawn over the sprite
+const OBJ_Y_FLIP: u8 = 0x40;
+const OBJ_X_FLIP: u8 = 0x20;
+const OBJ_PALETTE: u8 = 0x10; //OBP0 or OBP1
+
+//value is the mode number reported in the lower 2 bits of STAT
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Mode {
+    HBLANK = 0,
+    VBLANK = 1,
+    OAM = 2,
+    DRAWING = 3
+}
+
+//scanline draws each line in one go with a fixed mode 3 length, fifo emulates the pixel pipeline dot by dot
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Renderer {
+    SCANLINE,
+    FIFO
+}
+
+impl Renderer {
+    pub fn parse(value: &str) -> Option<Renderer> {
+        match value {
+            "scanline" => Option::Some(Renderer::SCANLINE),
+            "fifo" => Option::Some(Renderer::FIFO),
+            _ => Option::None
+        }
+    }
+}
+
+//https://gbdev.io/pandocs/OAM.html
+struct Sprite {
+    y: u8,
+    x: u8,
+    tile: u8,
+    attributes: u8
+}
 
 pub struct PPU {
-    ly: u8 //LCD Y Coordinate (READ-ONLY)
+    lcdc: u8,
+    stat: u8, //only the interrupt select bits are stored
+    scy: u8,
+    scx: u8,
+    ly: u8,
+    lyc: u8,
+    bgp: u8,
+    obp0: u8,
+    obp1: u8,
+    wy: u8,
+    wx: u8,
+    vram: [u8; VRAM_SIZE],
+    oam: [u8; OAM_SIZE],
+    framebuffer: [u8; FRAME_SIZE], //frame being drawn, shades 0-3
+    frame: [u8; FRAME_SIZE], //last complete frame
+    window_line: u8, //window's own line counter, only advances on lines it was drawn
+    mode: Mode,
+    cycles: u16, //t_states into the current line
+    drawing_cycles: u16, //length of mode 3 on the current line
+    renderer: Renderer,
+    fifo: Fifo,
+    stat_line: bool //STAT interrupt is requested on the rising edge of the OR of its sources
+}
+
+impl Default for PPU {
+    fn default() -> Self {
+        PPU::init()
+    }
 }
 
 impl PPU {
     pub fn init() -> PPU {
         PPU {
-            ly: 0
+            lcdc: 0,
+            stat: 0,
+            scy: 0,
+            scx: 0,
+            ly: 0,
+            lyc: 0,
+            bgp: 0,
+            obp0: 0,
+            obp1: 0,
+            wy: 0,
+            wx: 0,
+            vram: [0; VRAM_SIZE],
+            oam: [0; OAM_SIZE],
+            framebuffer: [0; FRAME_SIZE],
+            frame: [0; FRAME_SIZE],
+            window_line: 0,
+            mode: Mode::HBLANK, //LCD starts off, which reports mode 0
+            cycles: 0,
+            drawing_cycles: DRAWING_CYCLES,
+            renderer: Renderer::SCANLINE,
+            fifo: Fifo::new(),
+            stat_line: false
+        }
+    }
+
+    pub fn mode(&self) -> Mode {
+        self.mode
+    }
+
+    //switching mid-line corrupts at most that line
+    pub fn set_renderer(&mut self, renderer: Renderer) {
+        self.renderer = renderer;
+    }
+
+    //completed at the start of each VBlank
+    pub fn frame(&self) -> &[u8; FRAME_SIZE] {
+        &self.frame
+    }
+
+    //clear the screen until the next VBlank, STOP leaves the LCD blank
+    pub fn blank(&mut self) {
+        self.frame = BLANK_FRAME;
+    }
+
+    pub fn read_vram(&self, addr: u16) -> u8 {
+        self.vram[(addr - VRAM_START) as usize]
+    }
+
+    pub fn write_vram(&mut self, addr: u16, value: u8) {
+        self.vram[(addr - VRAM_START) as usize] = value;
+    }
+
+    pub fn read_oam(&self, addr: u16) -> u8 {
+        self.oam[(addr - OAM_START) as usize]
+    }
+
+    pub fn write_oam(&mut self, addr: u16, value: u8) {
+        self.oam[(addr - OAM_START) as usize] = value;
+    }
+
+    pub fn read(&self, addr: u16) -> u8 {
+        match addr {
+            LCDC => self.lcdc,
+            STAT => self.stat | self.coincidence() | self.mode as u8,
+            SCY => self.scy,
+            SCX => self.scx,
+            LY => self.ly,
+            LYC => self.lyc,
+            BGP => self.bgp,
+            OBP0 => self.obp0,
+            OBP1 => self.obp1,
+            WY => self.wy,
+            WX => self.wx,
+            _ => unreachable!("{:#06x} is not a PPU register", addr)
+        }
+    }
+
+    pub fn write(&mut self, addr: u16, value: u8) {
+        match addr {
+            LCDC => self.write_lcdc(value),
+            STAT => self.stat = value & STAT_WRITABLE,
+            SCY => self.scy = value,
+            SCX => self.scx = value,
+            LY => self.reset_ly(),
+            LYC => self.lyc = value,
+            BGP => self.bgp = value,
+            OBP0 => self.obp0 = value,
+            OBP1 => self.obp1 = value,
+            WY => self.wy = value,
+            WX => self.wx = value,
+            _ => unreachable!("{:#06x} is not a PPU register", addr)
+        }
+    }
+
+    //advance by the t_states the CPU took, returning the IF bits of the interrupts raised along the way
+    pub fn tick(&mut self, t_states: u8) -> u8 {
+        let mut interrupts = 0;
+
+        if !self.is_enabled() {
+            return interrupts;
+        }
+
+        for _ in 0..t_states {
+            self.cycles += 1;
+
+            match self.mode {
+                Mode::OAM if self.cycles == OAM_SCAN_CYCLES => self.start_drawing(),
+                Mode::DRAWING => self.draw(),
+                _ if self.cycles == CYCLES_PER_LINE => self.next_line(&mut interrupts),
+                _ => ()
+            }
+
+            if self.update_stat_line() {
+                interrupts |= Interrupt::Stat.bit();
+            }
+        }
+
+        interrupts
+    }
+
+    fn start_drawing(&mut self) {
+        match self.renderer {
+            Renderer::SCANLINE => self.drawing_cycles = DRAWING_CYCLES + (self.scx % 8) as u16,
+            Renderer::FIFO => self.fifo_start_line()
         }
+
+        self.mode = Mode::DRAWING;
     }
 
-    //PPU cycle and return values of registers
-    pub fn step(&mut self) -> (Vec<u8>, Duration) {
-        self.ly += 1;
+    //one dot of mode 3, moving on to HBlank once the line has been drawn
+    fn draw(&mut self) {
+        let done = match self.renderer {
+            Renderer::SCANLINE if self.cycles >= OAM_SCAN_CYCLES + self.drawing_cycles => {
+                self.render_line();
+                true
+            },
+            Renderer::SCANLINE => false,
+            Renderer::FIFO => self.fifo_dot()
+        };
+
+        if done {
+            self.mode = Mode::HBLANK;
+        }
+    }
+
+    fn next_line(&mut self, interrupts: &mut u8) {
+        self.cycles = 0;
+        self.ly = if self.ly == LCD_Y_MAX { 0 } else { self.ly + 1 };
+
+        self.mode = match self.ly {
+            VBLANK_START => {
+                self.frame = self.framebuffer;
+                self.window_line = 0;
+                *interrupts |= Interrupt::VBlank.bit();
+                Mode::VBLANK
+            },
+            _ if self.ly < VBLANK_START => Mode::OAM,
+            _ => Mode::VBLANK
+        };
+    }
 
-        if self.ly > LCD_Y_MAX {
+    //restarts the frame from line 0, the value written is ignored
+    fn reset_ly(&mut self) {
+        self.ly = 0;
+        self.cycles = 0;
+        self.window_line = 0;
+
+        if self.is_enabled() {
+            self.mode = Mode::OAM;
+        }
+    }
+
+    //turning the LCD off resets LY and the mode, turning it on starts a fresh frame
+    fn write_lcdc(&mut self, value: u8) {
+        let was_enabled = self.is_enabled();
+        self.lcdc = value;
+
+        if was_enabled && !self.is_enabled() {
             self.ly = 0;
+            self.cycles = 0;
+            self.window_line = 0;
+            self.mode = Mode::HBLANK;
+            self.stat_line = false;
+            self.blank();
+        } else if !was_enabled && self.is_enabled() {
+            self.mode = Mode::OAM;
         }
+    }
+
+    //first 10 sprites in OAM order that overlap the current line, hidden sprites still count
+    fn scan_oam(&self) -> Vec<Sprite> {
+        let height = self.sprite_height();
+        let line = self.ly + SPRITE_Y_OFFSET;
+
+        self.oam
+            .chunks(4)
+            .map(|entry| Sprite {
+                y: entry[0],
+                x: entry[1],
+                tile: entry[2],
+                attributes: entry[3]
+            })
+            .filter(|sprite| line >= sprite.y && line < sprite.y.saturating_add(height))
+            .take(SPRITES_PER_LINE)
+            .collect()
+    }
 
-        (self.sync_to_memory(), delay())
+    fn sprite_height(&self) -> u8 {
+        if self.lcdc & LCDC_OBJ_SIZE != 0 { 16 } else { 8 }
     }
 
-    fn sync_to_memory(&self) -> Vec<u8> {
-        vec![
-            0x00, //0xFF40
-            0x00, //0xFF41
-            0x00, //0xFF42
-            0x00, //0xFF43
-            self.ly, //0xFF44
-        ]
+    //colour index 0-3 of a pixel within the 256x256 tile map
+    fn tile_map_pixel(&self, map: u16, x: u8, y: u8) -> u8 {
+        let map_addr = map + (y / 8) as u16 * TILE_MAP_WIDTH + (x / 8) as u16;
+        let tile = self.read_vram(map_addr);
+
+        let tile_addr = if self.lcdc & LCDC_TILE_DATA != 0 {
+            VRAM_START + tile as u16 * TILE_SIZE
+        } else {
+            (0x9000 + (tile as i8 as i32) * TILE_SIZE as i32) as u16
+        };
+
+        self.tile_pixel(tile_addr, x % 8, y % 8)
+    }
+
+    //colour index 0-3 within a tile, rows of 16 pixels carry on into the next tile for 8x16 sprites
+    fn tile_pixel(&self, tile_addr: u16, x: u8, y: u8) -> u8 {
+        let row = tile_addr + y as u16 * 2;
+        let bit = 7 - x;
+        let low = (self.read_vram(row) >> bit) & 0x01;
+        let high = (self.read_vram(row + 1) >> bit) & 0x01;
+
+        (high << 1) | low
+    }
+
+    //returns true on a rising edge of the STAT interrupt line
+    fn update_stat_line(&mut self) -> bool {
+        let line = (self.stat & STAT_LYC_INTERRUPT != 0 && self.ly == self.lyc)
+            || match self.mode {
+                Mode::HBLANK => self.stat & STAT_HBLANK_INTERRUPT != 0,
+                Mode::VBLANK => self.stat & STAT_VBLANK_INTERRUPT != 0,
+                Mode::OAM => self.stat & STAT_OAM_INTERRUPT != 0,
+                Mode::DRAWING => false
+            };
+
+        let rising = line && !self.stat_line;
+        self.stat_line = line;
+
+        rising
+    }
+
+    fn coincidence(&self) -> u8 {
+        if self.ly == self.lyc { STAT_COINCIDENCE } else { 0 }
+    }
+
+    fn is_enabled(&self) -> bool {
+        self.lcdc & LCDC_ENABLE != 0
     }
 }
 
-fn delay() -> Duration {
-    //TODO: basic implementation until I have sorted the display: https://gbdev.io/pandocs/pixel_fifo.html#pixel-fifo
-    Duration::from_micros(16740)
+//palettes map each colour index to a shade, 2 bits per index
+fn shade(palette: u8, colour: u8) -> u8 {
+    (palette >> (colour * 2)) & 0x03
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    fn enabled_ppu() -> PPU {
+        let mut ppu = PPU::init();
+        ppu.write(LCDC, LCDC_ENABLE);
+
+        ppu
+    }
+
+    fn tick_cycles(ppu: &mut PPU, cycles: u32) -> Vec<Interrupt> {
+        let mut interrupts = Vec::new();
+
+        for _ in 0..(cycles / 4) {
+            let requested = ppu.tick(4);
+
+            interrupts.extend(
+                [Interrupt::VBlank, Interrupt::Stat]
+                    .into_iter()
+                    .filter(|interrupt| requested & interrupt.bit() != 0)
+            );
+        }
+
+        interrupts
+    }
+
+    //tile 1 at 0x8010, row 0 is colour 1, row 1 is colour 2
+    fn ppu_with_tiles(lcdc: u8) -> PPU {
+        let mut ppu = PPU::init();
+
+        ppu.write_vram(0x8010, 0xFF);
+        ppu.write_vram(0x8013, 0xFF);
+        ppu.write(BGP, 0xE4); //shade matches colour
+        ppu.write(LCDC, LCDC_ENABLE | LCDC_TILE_DATA | LCDC_BG_WINDOW_ENABLE | lcdc);
+
+        ppu
+    }
+
+    fn line(ppu: &PPU, y: usize) -> &[u8] {
+        &ppu.framebuffer[y * SCREEN_WIDTH..(y + 1) * SCREEN_WIDTH]
+    }
+
     #[test]
-    fn test_step() {
+    fn test_disabled() {
         let mut ppu = PPU::init();
 
-        let (result, _) = ppu.step(); //TODO: test duration
+        tick_cycles(&mut ppu, CYCLES_PER_FRAME);
+
+        assert_eq!(0, ppu.read(LY));
+        assert_eq!(Mode::HBLANK, ppu.mode());
+    }
+
+    #[test]
+    fn test_line_modes() {
+        let mut ppu = enabled_ppu();
+
+        assert_eq!(Mode::OAM, ppu.mode());
+
+        tick_cycles(&mut ppu, 80);
+        assert_eq!(Mode::DRAWING, ppu.mode());
+
+        tick_cycles(&mut ppu, 172);
+        assert_eq!(Mode::HBLANK, ppu.mode());
+        assert_eq!(0x00, ppu.read(STAT) & 0x03); //mode 0 in the lower bits
+
+        tick_cycles(&mut ppu, 204);
+        assert_eq!(Mode::OAM, ppu.mode());
+        assert_eq!(1, ppu.read(LY));
+        assert_eq!(0x02, ppu.read(STAT) & 0x03);
+    }
+
+    #[test]
+    fn test_drawing_extended_by_scx() {
+        let mut ppu = enabled_ppu();
+        ppu.write(SCX, 0x05);
+
+        tick_cycles(&mut ppu, 80 + 172);
+        assert_eq!(Mode::DRAWING, ppu.mode());
+
+        tick_cycles(&mut ppu, 8);
+        assert_eq!(Mode::HBLANK, ppu.mode());
+    }
+
+    #[test]
+    fn test_vblank() {
+        let mut ppu = enabled_ppu();
+
+        let interrupts = tick_cycles(&mut ppu, 456 * 144);
+
+        assert_eq!(vec![Interrupt::VBlank], interrupts);
+        assert_eq!(144, ppu.read(LY));
+        assert_eq!(Mode::VBLANK, ppu.mode());
+
+        tick_cycles(&mut ppu, 456 * 10); //a whole frame later back to line 0
+
+        assert_eq!(0, ppu.read(LY));
+        assert_eq!(Mode::OAM, ppu.mode());
+    }
+
+    #[test]
+    fn test_lyc_coincidence() {
+        let mut ppu = enabled_ppu();
+        ppu.write(LYC, 2);
+        ppu.write(STAT, STAT_LYC_INTERRUPT | 0x07); //mode & coincidence can't be written
+
+        assert_eq!(STAT_LYC_INTERRUPT | Mode::OAM as u8, ppu.read(STAT));
+
+        let interrupts = tick_cycles(&mut ppu, 456 * 2);
+
+        assert_eq!(vec![Interrupt::Stat], interrupts);
+        assert_eq!(STAT_COINCIDENCE, ppu.read(STAT) & STAT_COINCIDENCE);
+
+        assert!(tick_cycles(&mut ppu, 456).is_empty()); //no longer equal
+        assert_eq!(0, ppu.read(STAT) & STAT_COINCIDENCE);
+    }
+
+    #[test]
+    fn test_mode_interrupts() {
+        let mut ppu = enabled_ppu();
+        ppu.write(STAT, STAT_VBLANK_INTERRUPT);
+
+        let interrupts = tick_cycles(&mut ppu, CYCLES_PER_FRAME);
+
+        assert_eq!(vec![Interrupt::VBlank, Interrupt::Stat], interrupts); //requested together on line 144
+
+        ppu.write(STAT, STAT_HBLANK_INTERRUPT | STAT_VBLANK_INTERRUPT);
+
+        let interrupts = tick_cycles(&mut ppu, CYCLES_PER_FRAME);
+        let stat = interrupts.iter().filter(|interrupt| **interrupt == Interrupt::Stat).count();
+
+        //one rising edge per visible line, hblank of line 143 runs straight into vblank so the line stays high
+        assert_eq!(144, stat);
+    }
+
+    #[test]
+    fn test_lcd_off() {
+        let mut ppu = enabled_ppu();
+
+        tick_cycles(&mut ppu, 456 * 10 + 100);
+        ppu.write(LCDC, 0x00);
+
+        assert_eq!(0, ppu.read(LY));
+        assert_eq!(Mode::HBLANK, ppu.mode());
+
+        ppu.write(LCDC, LCDC_ENABLE);
+        assert_eq!(Mode::OAM, ppu.mode());
+    }
+
+    #[test]
+    fn test_ly_write() {
+        let mut ppu = enabled_ppu();
+
+        tick_cycles(&mut ppu, 456 * 5 + 300);
+        ppu.write(LY, 0x42);
+
+        assert_eq!(0, ppu.read(LY));
+        assert_eq!(Mode::OAM, ppu.mode());
+
+        tick_cycles(&mut ppu, 456);
+        assert_eq!(1, ppu.read(LY)); //a whole line from the reset
+    }
+
+    #[test]
+    fn test_render_background() {
+        let mut ppu = ppu_with_tiles(0);
+        ppu.write_vram(0x9800, 0x01);
+        ppu.write_vram(0x9820, 0x01); //second row of tiles
+
+        tick_cycles(&mut ppu, 456 * 8 + 252);
+
+        assert_eq!([1; 8], line(&ppu, 0)[0..8]);
+        assert_eq!([0; 8], line(&ppu, 0)[8..16]);
+        assert_eq!([2; 8], line(&ppu, 1)[0..8]);
+        assert_eq!([1; 8], line(&ppu, 8)[0..8]);
+    }
+
+    #[test]
+    fn test_render_scroll() {
+        let mut ppu = ppu_with_tiles(0);
+        ppu.write_vram(0x9800, 0x01);
+        ppu.write_vram(0x981F, 0x01); //right edge wraps around to the left
+        ppu.write(SCX, 0xFC);
+        ppu.write(SCY, 0x01);
+
+        tick_cycles(&mut ppu, 456 + 80 + 176);
+
+        assert_eq!([2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 0], line(&ppu, 0)[0..13]);
+    }
+
+    #[test]
+    fn test_render_signed_tile_data() {
+        let mut ppu = ppu_with_tiles(0);
+        ppu.write(LCDC, LCDC_ENABLE | LCDC_BG_WINDOW_ENABLE); //0x8800 addressing
+        ppu.write_vram(0x9000, 0xFF); //tile 0
+        ppu.write_vram(0x8800, 0xFF); //tile -128
+        ppu.write_vram(0x8801, 0xFF);
+        ppu.write_vram(0x9801, 0x80);
+
+        tick_cycles(&mut ppu, 80 + 172);
+
+        assert_eq!([1; 8], line(&ppu, 0)[0..8]);
+        assert_eq!([3; 8], line(&ppu, 0)[8..16]);
+    }
+
+    #[test]
+    fn test_render_palette() {
+        let mut ppu = ppu_with_tiles(0);
+        ppu.write_vram(0x9800, 0x01);
+        ppu.write(BGP, 0x1B); //inverted
+
+        tick_cycles(&mut ppu, 80 + 172);
+
+        assert_eq!([2; 8], line(&ppu, 0)[0..8]);
+        assert_eq!([3; 8], line(&ppu, 0)[8..16]);
+    }
+
+    #[test]
+    fn test_render_bg_window_disabled() {
+        let mut ppu = ppu_with_tiles(0);
+        ppu.write_vram(0x9800, 0x01);
+        ppu.write(BGP, 0x1B);
+        ppu.write(LCDC, LCDC_ENABLE | LCDC_TILE_DATA);
+
+        tick_cycles(&mut ppu, 80 + 172);
+
+        assert_eq!([0; SCREEN_WIDTH], line(&ppu, 0));
+    }
+
+    #[test]
+    fn test_render_window() {
+        let mut ppu = ppu_with_tiles(LCDC_WINDOW_ENABLE | LCDC_WINDOW_MAP);
+        ppu.write_vram(0x9C00, 0x01);
+        ppu.write(WX, 80 + 7);
+        ppu.write(WY, 0);
+
+        tick_cycles(&mut ppu, 80 + 172);
+
+        assert_eq!([0; 80], line(&ppu, 0)[0..80]);
+        assert_eq!([1; 8], line(&ppu, 0)[80..88]);
+
+        ppu.write(WX, 200); //off screen, the window line counter is paused
+        tick_cycles(&mut ppu, 456);
+        assert_eq!([0; 8], line(&ppu, 1)[80..88]);
+
+        ppu.write(WX, 80 + 7);
+        tick_cycles(&mut ppu, 456);
+        assert_eq!([2; 8], line(&ppu, 2)[80..88]); //second row of the window tile
+    }
+
+    #[test]
+    fn test_frame() {
+        let mut ppu = ppu_with_tiles(0);
+        ppu.write_vram(0x9800, 0x01);
+
+        tick_cycles(&mut ppu, 456 * 143);
+        assert_eq!(0, ppu.frame()[0]); //not complete yet
+
+        tick_cycles(&mut ppu, 456);
+        assert_eq!(1, ppu.frame()[0]);
+
+        ppu.write(LCDC, 0x00);
+        assert_eq!(&BLANK_FRAME, ppu.frame());
+
+        ppu.write(LCDC, LCDC_ENABLE | LCDC_TILE_DATA | LCDC_BG_WINDOW_ENABLE);
+        tick_cycles(&mut ppu, 456 * 154);
+        assert_eq!(1, ppu.frame()[0]);
+
+        ppu.blank();
+        assert_eq!(&BLANK_FRAME, ppu.frame());
+    }
+
+    fn sprite(ppu: &mut PPU, index: u16, y: u8, x: u8, tile: u8, attributes: u8) {
+        let addr = OAM_START + index * 4;
+
+        ppu.write_oam(addr, y);
+        ppu.write_oam(addr + 1, x);
+        ppu.write_oam(addr + 2, tile);
+        ppu.write_oam(addr + 3, attributes);
+    }
+
+    fn ppu_with_sprites(lcdc: u8) -> PPU {
+        let mut ppu = ppu_with_tiles(LCDC_OBJ_ENABLE | lcdc);
+
+        ppu.write_vram(0x8020, 0x80); //tile 2, a single colour 1 pixel in the top left
+        ppu.write_vram(0x8030, 0xFF); //tile 3, row 0 is colour 3
+        ppu.write_vram(0x8031, 0xFF);
+        ppu.write(OBP0, 0xE4);
+        ppu.write(OBP1, 0x1B);
+
+        ppu
+    }
+
+    #[test]
+    fn test_render_sprite() {
+        let mut ppu = ppu_with_sprites(0);
+        sprite(&mut ppu, 0, 16, 8, 0x01, 0x00);
+        sprite(&mut ppu, 1, 16, 4, 0x01, OBJ_PALETTE); //partially off the left edge
+
+        tick_cycles(&mut ppu, 456 + 252);
+
+        assert_eq!([2, 2, 2, 2, 1, 1, 1, 1, 0], line(&ppu, 0)[0..9]);
+        assert_eq!([1, 1, 1, 1, 2, 2, 2, 2, 0], line(&ppu, 1)[0..9]); //colour 2 through each palette
+    }
+
+    #[test]
+    fn test_render_sprite_flip() {
+        let mut ppu = ppu_with_sprites(0);
+        sprite(&mut ppu, 0, 16, 8, 0x02, OBJ_X_FLIP);
+        sprite(&mut ppu, 1, 16, 16, 0x02, OBJ_Y_FLIP);
+
+        tick_cycles(&mut ppu, 456 * 7 + 252);
+
+        assert_eq!([0, 0, 0, 0, 0, 0, 0, 1], line(&ppu, 0)[0..8]);
+        assert_eq!([0; 8], line(&ppu, 0)[8..16]);
+        assert_eq!([1, 0, 0, 0, 0, 0, 0, 0], line(&ppu, 7)[8..16]);
+    }
 
-        assert_eq!(1, result[4]);
+    #[test]
+    fn test_render_sprite_8x16() {
+        let mut ppu = ppu_with_sprites(LCDC_OBJ_SIZE);
+        sprite(&mut ppu, 0, 16, 8, 0x03, 0x00); //the low bit is ignored, tiles 2 & 3
+        sprite(&mut ppu, 1, 16, 16, 0x02, OBJ_Y_FLIP); //flips across both tiles
+
+        tick_cycles(&mut ppu, 456 * 15 + 252);
+
+        assert_eq!(1, line(&ppu, 0)[0]);
+        assert_eq!([3; 8], line(&ppu, 8)[0..8]);
+        assert_eq!([3; 8], line(&ppu, 7)[8..16]);
+        assert_eq!(1, line(&ppu, 15)[8]);
+    }
+
+    #[test]
+    fn test_render_sprite_bg_priority() {
+        let mut ppu = ppu_with_sprites(0);
+        ppu.write_vram(0x9800, 0x01); //background colour 1 under the first 8 pixels
+        sprite(&mut ppu, 0, 16, 12, 0x01, OBJ_BG_PRIORITY | OBJ_PALETTE);
+
+        tick_cycles(&mut ppu, 252);
+
+        assert_eq!([1, 1, 1, 1, 1, 1, 1, 1, 2, 2, 2, 2, 0], line(&ppu, 0)[0..13]); //only shows over colour 0
+    }
+
+    #[test]
+    fn test_render_sprite_transparency() {
+        let mut ppu = ppu_with_sprites(0);
+        sprite(&mut ppu, 0, 16, 8, 0x02, OBJ_PALETTE); //higher priority, transparent apart from one pixel
+        sprite(&mut ppu, 1, 16, 8, 0x01, 0x00);
+
+        tick_cycles(&mut ppu, 252);
+
+        assert_eq!([2, 1, 1, 1, 1, 1, 1, 1], line(&ppu, 0)[0..8]);
+    }
+
+    #[test]
+    fn test_render_sprite_x_priority() {
+        let mut ppu = ppu_with_sprites(0);
+        sprite(&mut ppu, 0, 16, 12, 0x01, 0x00);
+        sprite(&mut ppu, 1, 16, 8, 0x01, OBJ_PALETTE); //lower X wins despite the higher OAM index
+        sprite(&mut ppu, 2, 16, 40, 0x01, 0x00);
+        sprite(&mut ppu, 3, 16, 40, 0x01, OBJ_PALETTE); //same X, the lower OAM index wins
+
+        tick_cycles(&mut ppu, 252);
+
+        assert_eq!([2, 2, 2, 2, 2, 2, 2, 2, 1, 1, 1, 1, 0], line(&ppu, 0)[0..13]);
+        assert_eq!([1; 8], line(&ppu, 0)[32..40]);
+    }
+
+    #[test]
+    fn test_render_sprite_limit() {
+        let mut ppu = ppu_with_sprites(0);
+        sprite(&mut ppu, 0, 0, 8, 0x01, 0x00); //off screen, not on this line so doesn't count
+
+        for index in 1..12 {
+            sprite(&mut ppu, index, 16, index as u8 * 8, 0x01, 0x00);
+        }
+
+        tick_cycles(&mut ppu, 252);
+
+        assert_eq!([1; 8], line(&ppu, 0)[72..80]); //10th sprite on the line
+        assert_eq!([0; 8], line(&ppu, 0)[80..88]); //11th is dropped
+    }
+
+    #[test]
+    fn test_render_sprites_disabled() {
+        let mut ppu = ppu_with_sprites(0);
+        ppu.write(LCDC, LCDC_ENABLE | LCDC_TILE_DATA | LCDC_BG_WINDOW_ENABLE);
+        sprite(&mut ppu, 0, 16, 8, 0x01, 0x00);
+
+        tick_cycles(&mut ppu, 252);
+
+        assert_eq!([0; 8], line(&ppu, 0)[0..8]);
+    }
+
+    //window, scrolling & sprites all on screen at once
+    fn busy_scene(renderer: Renderer) -> PPU {
+        let mut ppu = ppu_with_sprites(LCDC_WINDOW_ENABLE | LCDC_WINDOW_MAP);
+        ppu.set_renderer(renderer);
+
+        for index in 0..0x400 {
+            ppu.write_vram(0x9800 + index, (index % 3) as u8 * 2 + 1); //tiles 1 & 3
+            ppu.write_vram(0x9C00 + index, 0x02);
+        }
+
+        ppu.write(SCX, 13);
+        ppu.write(SCY, 3);
+        ppu.write(WX, 100);
+        ppu.write(WY, 60);
+        sprite(&mut ppu, 0, 20, 4, 0x01, OBJ_PALETTE);
+        sprite(&mut ppu, 1, 40, 50, 0x03, OBJ_X_FLIP | OBJ_BG_PRIORITY);
+        sprite(&mut ppu, 2, 40, 54, 0x02, 0x00);
+        sprite(&mut ppu, 3, 80, 120, 0x01, OBJ_Y_FLIP);
+
+        ppu
+    }
+
+    #[test]
+    fn test_fifo_matches_scanline() {
+        let mut scanline = busy_scene(Renderer::SCANLINE);
+        let mut fifo = busy_scene(Renderer::FIFO);
+
+        tick_cycles(&mut scanline, CYCLES_PER_FRAME);
+        tick_cycles(&mut fifo, CYCLES_PER_FRAME);
+
+        assert!(scanline.frame().iter().any(|&pixel| pixel != 0));
+        assert_eq!(scanline.frame(), fifo.frame());
+    }
+
+    #[test]
+    fn test_fifo_drawing_length() {
+        let mut ppu = enabled_ppu();
+        ppu.set_renderer(Renderer::FIFO);
+
+        tick_cycles(&mut ppu, 80 + 168);
+        assert_eq!(Mode::DRAWING, ppu.mode());
+
+        tick_cycles(&mut ppu, 4);
+        assert_eq!(Mode::HBLANK, ppu.mode());
+
+        ppu.write(SCX, 0x05); //discarded pixels extend mode 3
+        tick_cycles(&mut ppu, 204 + 80 + 176);
+        assert_eq!(Mode::DRAWING, ppu.mode());
+
+        tick_cycles(&mut ppu, 4);
+        assert_eq!(Mode::HBLANK, ppu.mode());
+    }
+
+    #[test]
+    fn test_fifo_sprite_penalty() {
+        let mut ppu = ppu_with_sprites(0);
+        ppu.set_renderer(Renderer::FIFO);
+        sprite(&mut ppu, 0, 16, 8, 0x01, 0x00);
+
+        tick_cycles(&mut ppu, 252 + 8);
+        assert_eq!(Mode::DRAWING, ppu.mode()); //11 dots for a sprite at the left edge
+
+        tick_cycles(&mut ppu, 4);
+        assert_eq!(Mode::HBLANK, ppu.mode());
+        assert_eq!([1; 8], line(&ppu, 0)[0..8]);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_fifo_mid_line_palette() {
+        let mut ppu = ppu_with_tiles(0);
+        ppu.set_renderer(Renderer::FIFO);
+
+        for index in 0..32 {
+            ppu.write_vram(0x9800 + index, 0x01);
+        }
+
+        tick_cycles(&mut ppu, 80 + 92); //80 pixels out
+        ppu.write(BGP, 0x00);
+        tick_cycles(&mut ppu, 80);
+
+        assert_eq!([1; 80], line(&ppu, 0)[0..80]);
+        assert_eq!([0; 80], line(&ppu, 0)[80..160]);
+
+        ppu.set_renderer(Renderer::SCANLINE); //only sees the palette at the end of the line
+        ppu.write(BGP, 0xE4);
+        tick_cycles(&mut ppu, 204 + 80 + 92);
+        ppu.write(BGP, 0x00);
+        tick_cycles(&mut ppu, 80);
+
+        assert_eq!([0; SCREEN_WIDTH], line(&ppu, 1));
+    }
+
+    #[test]
+    fn test_fifo_mid_line_scx() {
+        let mut ppu = ppu_with_tiles(0);
+        ppu.set_renderer(Renderer::FIFO);
+        ppu.write_vram(0x9800 + 15, 0x01); //only the 16th tile is drawn
+
+        tick_cycles(&mut ppu, 80 + 60); //7 tiles fetched
+        ppu.write(SCX, 0x40); //coarse scroll is read by each tile fetch
+        tick_cycles(&mut ppu, 112);
+
+        assert_eq!([0; 56], line(&ppu, 0)[0..56]);
+        assert_eq!([1; 8], line(&ppu, 0)[56..64]); //8th column fetched from 8 tiles further along
+        assert_eq!([0; 8], line(&ppu, 0)[120..128]);
+    }
+}
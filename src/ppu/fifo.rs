@@ -0,0 +1,234 @@
+use std::collections::VecDeque;
+
+use super::{
+    PPU, Sprite,
+    SCREEN_WIDTH, WINDOW_X_OFFSET, SPRITE_X_OFFSET, SPRITE_Y_OFFSET, VRAM_START, TILE_SIZE,
+    LCDC_BG_WINDOW_ENABLE, LCDC_OBJ_ENABLE, LCDC_WINDOW_ENABLE, LCDC_BG_MAP, LCDC_WINDOW_MAP,
+    OBJ_BG_PRIORITY, OBJ_Y_FLIP, OBJ_X_FLIP, OBJ_PALETTE,
+    shade
+};
+
+//https://gbdev.io/pandocs/pixel_fifo.html
+const FETCH_DOTS: u8 = 6; //tile number, data low & data high, 2 dots each
+const FIRST_FETCH_DOTS: u8 = 6; //the first fetch of a line is thrown away
+const SPRITE_FETCH_DOTS: u8 = 6;
+const SPRITE_FETCH_MAX_WAIT: u8 = 5; //waiting on the background fetcher before a sprite fetch
+
+#[derive(Clone, Copy)]
+struct ObjPixel {
+    colour: u8,
+    attributes: u8
+}
+
+const TRANSPARENT: ObjPixel = ObjPixel {
+    colour: 0,
+    attributes: 0
+};
+
+pub struct Fifo {
+    bg: VecDeque<u8>, //colour indices
+    obj: VecDeque<ObjPixel>,
+    sprites: Vec<Sprite>, //sprites on the line still to be fetched, in OAM order
+    fetch_step: u8, //dots into the current background fetch
+    fetch_x: u8, //tile column of the next fetch, from the left of the line or the window
+    window: bool, //fetching window tiles
+    stall: u8, //dots the pipeline is paused for, first fetch & sprite fetches
+    discard: u8, //pixels still to be dropped for fine scrolling
+    x: u8 //pixels output on this line
+}
+
+impl Default for Fifo {
+    fn default() -> Self {
+        Fifo::new()
+    }
+}
+
+impl Fifo {
+    pub fn new() -> Fifo {
+        Fifo {
+            bg: VecDeque::new(),
+            obj: VecDeque::new(),
+            sprites: Vec::new(),
+            fetch_step: 0,
+            fetch_x: 0,
+            window: false,
+            stall: 0,
+            discard: 0,
+            x: 0
+        }
+    }
+}
+
+impl PPU {
+    pub(super) fn fifo_start_line(&mut self) {
+        self.fifo = Fifo {
+            sprites: self.scan_oam(),
+            stall: FIRST_FETCH_DOTS,
+            discard: self.scx % 8,
+            ..Fifo::new()
+        };
+    }
+
+    //one dot of mode 3, returns true once all 160 pixels of the line have been output
+    pub(super) fn fifo_dot(&mut self) -> bool {
+        if self.fifo.x as usize == SCREEN_WIDTH { //only after switching renderer mid-line
+            return true;
+        }
+
+        if self.fifo.stall > 0 {
+            self.fifo.stall -= 1;
+            return false;
+        }
+
+        if self.start_sprite_fetch() {
+            return false;
+        }
+
+        self.start_window();
+        self.output_pixel();
+        self.fetch_dot();
+
+        if self.fifo.x as usize == SCREEN_WIDTH {
+            if self.fifo.window {
+                self.window_line += 1;
+            }
+
+            return true;
+        }
+
+        false
+    }
+
+    //sprites are fetched as the pixel they start on reaches the front of the FIFO
+    fn start_sprite_fetch(&mut self) -> bool {
+        if self.lcdc & LCDC_OBJ_ENABLE == 0 || self.fifo.bg.is_empty() {
+            return false;
+        }
+
+        let x = self.fifo.x + SPRITE_X_OFFSET;
+        let next = self.fifo.sprites
+            .iter()
+            .enumerate()
+            .filter(|(_, sprite)| sprite.x <= x)
+            .min_by_key(|(_, sprite)| sprite.x) //several can be waiting at the left edge
+            .map(|(index, _)| index);
+
+        match next {
+            Option::Some(index) => {
+                let sprite = self.fifo.sprites.remove(index);
+                let wait = FETCH_DOTS.saturating_sub(self.fifo.fetch_step).min(SPRITE_FETCH_MAX_WAIT);
+
+                self.fetch_sprite(&sprite, x - sprite.x);
+                self.fifo.stall = SPRITE_FETCH_DOTS + wait - 1; //this dot is the first
+                true
+            },
+            Option::None => false
+        }
+    }
+
+    //sprite pixels only land in transparent slots, so sprites fetched earlier keep priority
+    fn fetch_sprite(&mut self, sprite: &Sprite, skip: u8) {
+        let height = self.sprite_height();
+        let mut row = self.ly + SPRITE_Y_OFFSET - sprite.y;
+
+        if sprite.attributes & OBJ_Y_FLIP != 0 {
+            row = height - 1 - row;
+        }
+
+        let tile = if height == 16 { sprite.tile & 0xFE } else { sprite.tile };
+        let tile_addr = VRAM_START + tile as u16 * TILE_SIZE;
+
+        while self.fifo.obj.len() < 8 {
+            self.fifo.obj.push_back(TRANSPARENT);
+        }
+
+        for column in skip..8 {
+            let tile_column = if sprite.attributes & OBJ_X_FLIP != 0 { 7 - column } else { column };
+            let colour = self.tile_pixel(tile_addr, tile_column, row);
+            let slot = &mut self.fifo.obj[(column - skip) as usize];
+
+            if slot.colour == 0 {
+                *slot = ObjPixel {
+                    colour,
+                    attributes: sprite.attributes
+                };
+            }
+        }
+    }
+
+    //reaching WX restarts the fetcher on the window tile map
+    fn start_window(&mut self) {
+        let visible = self.lcdc & LCDC_WINDOW_ENABLE != 0
+            && self.ly >= self.wy
+            && self.fifo.x + WINDOW_X_OFFSET >= self.wx;
+
+        if self.fifo.window || !visible {
+            return;
+        }
+
+        self.fifo.window = true;
+        self.fifo.bg.clear();
+        self.fifo.fetch_step = 0;
+        self.fifo.fetch_x = 0;
+        self.fifo.discard = WINDOW_X_OFFSET.saturating_sub(self.wx); //WX below 7 starts part way into the window
+    }
+
+    fn fetch_dot(&mut self) {
+        if self.fifo.fetch_step < FETCH_DOTS {
+            self.fifo.fetch_step += 1;
+        }
+
+        if self.fifo.fetch_step < FETCH_DOTS || !self.fifo.bg.is_empty() {
+            return; //still fetching or waiting for the FIFO to empty
+        }
+
+        let (map, x, y) = if self.fifo.window {
+            let map = if self.lcdc & LCDC_WINDOW_MAP != 0 { 0x9C00 } else { 0x9800 };
+            (map, self.fifo.fetch_x * 8, self.window_line)
+        } else {
+            let map = if self.lcdc & LCDC_BG_MAP != 0 { 0x9C00 } else { 0x9800 };
+            (map, (self.scx / 8).wrapping_add(self.fifo.fetch_x).wrapping_mul(8), self.ly.wrapping_add(self.scy))
+        };
+
+        for column in 0..8 {
+            let colour = self.tile_map_pixel(map, x.wrapping_add(column), y);
+            self.fifo.bg.push_back(colour);
+        }
+
+        self.fifo.fetch_step = 0;
+        self.fifo.fetch_x += 1;
+    }
+
+    //palettes & enable bits are applied as each pixel leaves the FIFO
+    fn output_pixel(&mut self) {
+        let bg = match self.fifo.bg.pop_front() {
+            Option::Some(colour) => colour,
+            Option::None => return
+        };
+
+        if self.fifo.discard > 0 {
+            self.fifo.discard -= 1;
+            return;
+        }
+
+        let obj = self.fifo.obj.pop_front().unwrap_or(TRANSPARENT);
+        let bg_enabled = self.lcdc & LCDC_BG_WINDOW_ENABLE != 0;
+        let bg = if bg_enabled { bg } else { 0 };
+
+        let obj_visible = obj.colour != 0
+            && self.lcdc & LCDC_OBJ_ENABLE != 0
+            && (obj.attributes & OBJ_BG_PRIORITY == 0 || bg == 0);
+
+        let pixel = if obj_visible {
+            let palette = if obj.attributes & OBJ_PALETTE != 0 { self.obp1 } else { self.obp0 };
+            shade(palette, obj.colour)
+        } else if bg_enabled {
+            shade(self.bgp, bg)
+        } else {
+            0
+        };
+
+        self.framebuffer[self.ly as usize * SCREEN_WIDTH + self.fifo.x as usize] = pixel;
+        self.fifo.x += 1;
+    }
+}
@@ -0,0 +1,93 @@
+use super::{
+    PPU,
+    SCREEN_WIDTH, WINDOW_X_OFFSET, SPRITE_X_OFFSET, SPRITE_Y_OFFSET, VRAM_START, TILE_SIZE,
+    LCDC_BG_WINDOW_ENABLE, LCDC_OBJ_ENABLE, LCDC_WINDOW_ENABLE, LCDC_BG_MAP, LCDC_WINDOW_MAP,
+    OBJ_BG_PRIORITY, OBJ_Y_FLIP, OBJ_X_FLIP, OBJ_PALETTE,
+    shade
+};
+
+impl PPU {
+    //draws the whole line in one go at the end of mode 3, mid-line register writes are not seen
+    pub(super) fn render_line(&mut self) {
+        let mut shades = [0; SCREEN_WIDTH];
+        let mut colours = [0; SCREEN_WIDTH]; //background colour indices, sprites are hidden behind 1-3 when they ask to be
+
+        if self.lcdc & LCDC_BG_WINDOW_ENABLE != 0 {
+            self.render_background(&mut colours);
+
+            for (shade_out, colour) in shades.iter_mut().zip(colours) {
+                *shade_out = shade(self.bgp, colour);
+            }
+        }
+
+        if self.lcdc & LCDC_OBJ_ENABLE != 0 {
+            self.render_sprites(&colours, &mut shades);
+        }
+
+        let start = self.ly as usize * SCREEN_WIDTH;
+        self.framebuffer[start..start + SCREEN_WIDTH].copy_from_slice(&shades);
+    }
+
+    //https://gbdev.io/pandocs/Tile_Maps.html
+    fn render_background(&mut self, colours: &mut [u8; SCREEN_WIDTH]) {
+        let window = self.lcdc & LCDC_WINDOW_ENABLE != 0 && self.ly >= self.wy && self.wx < SCREEN_WIDTH as u8 + WINDOW_X_OFFSET;
+        let bg_map = if self.lcdc & LCDC_BG_MAP != 0 { 0x9C00 } else { 0x9800 };
+        let window_map = if self.lcdc & LCDC_WINDOW_MAP != 0 { 0x9C00 } else { 0x9800 };
+        let y = self.ly.wrapping_add(self.scy);
+
+        for x in 0..SCREEN_WIDTH as u8 {
+            colours[x as usize] = if window && x + WINDOW_X_OFFSET >= self.wx {
+                self.tile_map_pixel(window_map, x + WINDOW_X_OFFSET - self.wx, self.window_line)
+            } else {
+                self.tile_map_pixel(bg_map, x.wrapping_add(self.scx), y)
+            };
+        }
+
+        if window {
+            self.window_line += 1;
+        }
+    }
+
+    //https://gbdev.io/pandocs/OAM.html#drawing-priority ~ on DMG the lower X wins, then the lower OAM index
+    fn render_sprites(&self, colours: &[u8; SCREEN_WIDTH], shades: &mut [u8; SCREEN_WIDTH]) {
+        let mut sprites = self.scan_oam();
+        sprites.sort_by_key(|sprite| sprite.x); //stable, ties keep OAM order
+
+        let height = self.sprite_height();
+
+        for x in 0..SCREEN_WIDTH as u8 {
+            let screen_x = x + SPRITE_X_OFFSET;
+
+            let pixel = sprites
+                .iter()
+                .filter(|sprite| screen_x >= sprite.x && screen_x < sprite.x.saturating_add(8))
+                .map(|sprite| {
+                    let mut row = self.ly + SPRITE_Y_OFFSET - sprite.y;
+                    let mut column = screen_x - sprite.x;
+
+                    if sprite.attributes & OBJ_Y_FLIP != 0 {
+                        row = height - 1 - row;
+                    }
+
+                    if sprite.attributes & OBJ_X_FLIP != 0 {
+                        column = 7 - column;
+                    }
+
+                    let tile = if height == 16 { sprite.tile & 0xFE } else { sprite.tile };
+                    let tile_addr = VRAM_START + tile as u16 * TILE_SIZE;
+
+                    (sprite, self.tile_pixel(tile_addr, column, row))
+                })
+                .find(|(_, colour)| *colour != 0); //colour 0 is transparent, the next sprite shows through
+
+            if let Option::Some((sprite, colour)) = pixel {
+                if sprite.attributes & OBJ_BG_PRIORITY != 0 && colours[x as usize] != 0 {
+                    continue;
+                }
+
+                let palette = if sprite.attributes & OBJ_PALETTE != 0 { self.obp1 } else { self.obp0 };
+                shades[x as usize] = shade(palette, colour);
+            }
+        }
+    }
+}